@@ -1,13 +1,87 @@
-//! Parse a struct from stdin and dump it to stdout
+//! Parse a struct from stdin (or a file) and dump it to stdout
+//!
+//! Usage: `dump [layout|lint] [--lang <lang>] [--target <triple>] [--cache-line <bytes>] [file]`
+//!
+//! With the `layout` subcommand, a report of the layout of the struct is printed
+//! instead, with its holes, padding and cache lines. With the `lint` subcommand, the
+//! problems found by the lint pass are printed.
 
-use std::io::Read;
+use std::{io::Read, path::PathBuf, process::exit};
 
 fn main() {
-    let mut input = String::new();
-    std::io::stdin().read_to_string(&mut input).unwrap();
-    match structparse::Struct::parse(&input) {
-        Ok(s) => {
-            println!("{s:#?}");
+    let mut lang = None;
+    let mut path = None;
+    let mut layout = false;
+    let mut lint = false;
+    let mut target = structparse::Target::default();
+    let mut cache_line = 64;
+    let mut args = std::env::args().skip(1).peekable();
+    match args.next_if(|arg| arg == "layout" || arg == "lint").as_deref() {
+        Some("layout") => layout = true,
+        Some(_) => lint = true,
+        None => {}
+    }
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--lang" => {
+                let name = args.next().unwrap_or_default();
+                match structparse::Lang::from_name(&name) {
+                    Some(l) => lang = Some(l),
+                    None => {
+                        eprintln!("Unknown language: {name}");
+                        exit(1);
+                    }
+                }
+            }
+            "--target" => {
+                let triple = args.next().unwrap_or_default();
+                match structparse::Target::from_triple(&triple) {
+                    Some(t) => target = t,
+                    None => {
+                        eprintln!("Unknown target: {triple}");
+                        exit(1);
+                    }
+                }
+            }
+            "--cache-line" => {
+                let bytes = args.next().unwrap_or_default();
+                match bytes.parse() {
+                    Ok(bytes) if bytes > 0 => cache_line = bytes,
+                    _ => {
+                        eprintln!("Invalid cache line size: {bytes}");
+                        exit(1);
+                    }
+                }
+            }
+            _ => path = Some(PathBuf::from(arg)),
+        }
+    }
+    let input = match &path {
+        Some(path) => std::fs::read_to_string(path).unwrap(),
+        None => {
+            let mut input = String::new();
+            std::io::stdin().read_to_string(&mut input).unwrap();
+            input
+        }
+    };
+    match structparse::Struct::parse_detect(&input, path.as_deref(), lang) {
+        Ok((s, lang)) => {
+            eprintln!("Parsed as {lang}");
+            if lint {
+                let config = structparse::LintConfig::new();
+                for diag in s.lint(&structparse::Registry::new(), &target, &config) {
+                    println!("{diag}");
+                }
+                return;
+            }
+            if !layout {
+                println!("{s:#?}");
+                return;
+            }
+            match s.layout_for(&structparse::Registry::new(), &target) {
+                Ok(l) => print!("{}", structparse::Report::new(&s, &l, cache_line)),
+                Err(e) => eprintln!("Layout error: {e}"),
+            }
         }
         Err(e) => eprintln!("Parse error: {e}"),
     }
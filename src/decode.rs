@@ -0,0 +1,402 @@
+//! Decoding binary data through struct definitions
+
+use {
+    crate::{
+        Definition, Endian, Layout, LayoutError, Primitive, Registry, Struct, Target, Ty, Value,
+        Vector,
+    },
+    std::ops::Range,
+    thiserror::Error,
+};
+
+/// Error that can happen while decoding a value
+#[derive(Debug, PartialEq, Eq, Clone, Error)]
+pub enum DecodeError {
+    /// A buffer that ends before the value does
+    #[error("Buffer too short: {needed} bytes needed, but only {len} available")]
+    TooShort {
+        /// The number of bytes the value needs
+        needed: u64,
+        /// The number of bytes available
+        len: usize,
+    },
+    /// The layout of a nested struct couldn't be computed
+    #[error(transparent)]
+    Layout(#[from] LayoutError),
+}
+
+impl<'s> Struct<'s> {
+    /// Decode a value of this struct from the start of `data`, with its fields placed
+    /// following `layout`
+    ///
+    /// `layout` must be a layout of this struct, like the one of
+    /// [`Struct::layout_for`]. Nested structs are laid out with
+    /// [`Struct::layout_for`], looking up named types in `registry`. Integers are read
+    /// with the sizes of primitives of `target`, and enums as their underlying integer
+    /// type. Values of types that can't be interpreted, like `long double` or names
+    /// that aren't in the registry, are kept as raw bytes.
+    ///
+    /// The byte order of a value is the one fixed by its type name, like `u16be`, or
+    /// else the innermost of its field's [`Field::endian`](crate::Field::endian) and
+    /// its struct's [`Repr::endian`](crate::Repr::endian), or else the byte order of
+    /// `target`. Nested structs without a byte order of their own take the one of the
+    /// field containing them.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `data` is shorter than the struct, or if the layout of a
+    /// nested struct can't be computed.
+    pub fn decode(
+        &self,
+        layout: &Layout,
+        registry: &Registry<'s>,
+        target: &Target,
+        data: &[u8],
+    ) -> Result<Value<'s>, DecodeError> {
+        Decoder { registry, target }.struct_value(self, layout, data, target.endian)
+    }
+}
+
+/// Decodes values with the types of a registry
+struct Decoder<'a, 's> {
+    registry: &'a Registry<'s>,
+    target: &'a Target,
+}
+
+impl<'s> Decoder<'_, 's> {
+    fn struct_value(
+        &self,
+        struct_: &Struct<'s>,
+        layout: &Layout,
+        data: &[u8],
+        endian: Endian,
+    ) -> Result<Value<'s>, DecodeError> {
+        let endian = struct_.repr.endian.unwrap_or(endian);
+        let data = &data[range(0, layout.size, data.len())?];
+        let mut fields = Vec::with_capacity(struct_.fields.len());
+        for (field, placed) in struct_.fields.iter().zip(&layout.fields) {
+            let bytes = &data[range(placed.offset, placed.size, data.len())?];
+            let endian = field.endian.unwrap_or(endian);
+            let value = match placed.bits {
+                Some(bits) => {
+                    let raw = bitfield(bytes, bits.offset % 8, bits.width, endian);
+                    match self.registry.primitive(&field.ty) {
+                        Some(Primitive::Bool) => Value::Bool(raw != 0),
+                        Some(prim) if prim.is_signed() => Value::Int(sign_extend(raw, bits.width)),
+                        _ => Value::UInt(raw),
+                    }
+                }
+                None => self.value(&field.ty, bytes, endian)?,
+            };
+            fields.push((field.name, value));
+        }
+        Ok(Value::Struct(fields))
+    }
+    /// Decode a value of type `ty` from `data`, which holds exactly the bytes of its
+    /// slot, including any padding of array elements
+    fn value(&self, ty: &Ty<'s>, data: &[u8], endian: Endian) -> Result<Value<'s>, DecodeError> {
+        match ty {
+            Ty::Ident(name) => {
+                let endian = Primitive::name_endian(name).unwrap_or(endian);
+                match self.registry.resolve(name) {
+                    Some(Definition::Primitive(prim)) => self.scalar(prim, data, endian),
+                    Some(Definition::Struct(struct_)) => {
+                        let layout = struct_.layout_for(self.registry, self.target)?;
+                        self.struct_value(struct_, &layout, data, endian)
+                    }
+                    Some(Definition::Enum(enum_)) => match &enum_.ty {
+                        Some(ty) => self.value(ty, data, endian),
+                        None => self.scalar(Primitive::I32, data, endian),
+                    },
+                    Some(Definition::Alias { ty, .. }) => self.value(ty, data, endian),
+                    None => Ok(Value::Bytes(data.to_vec())),
+                }
+            }
+            Ty::Array(array) => {
+                let stride = data.len().checked_div(len_usize(array.len)?).unwrap_or(0);
+                self.elems(&array.ty, array.len, stride, data, endian)
+            }
+            Ty::Pointer(_) | Ty::FnPtr(_) => {
+                let bytes = &data[range(0, self.target.pointer_size, data.len())?];
+                Ok(read_uint(bytes, endian)
+                    .map_or_else(|| Value::Bytes(bytes.to_vec()), Value::UInt))
+            }
+            Ty::Inline(inner) => {
+                let layout = inner.layout_for(self.registry, self.target)?;
+                self.struct_value(inner, &layout, data, endian)
+            }
+            Ty::Vector(vector) => {
+                let stride = component_stride(self.registry, self.target, vector, data.len());
+                self.elems(&vector.ty, vector.len.into(), stride, data, endian)
+            }
+            Ty::Matrix(matrix) => {
+                let (len, count) = if matrix.row_major {
+                    (matrix.columns, matrix.rows)
+                } else {
+                    (matrix.rows, matrix.columns)
+                };
+                let vector = Ty::Vector(Vector {
+                    ty: matrix.ty.clone(),
+                    len,
+                });
+                let stride = data.len() / usize::from(count.max(1));
+                self.elems(&vector, count.into(), stride, data, endian)
+            }
+        }
+    }
+    /// Decode `len` elements of type `ty`, `stride` bytes apart in `data`
+    fn elems(
+        &self,
+        ty: &Ty<'s>,
+        len: u64,
+        stride: usize,
+        data: &[u8],
+        endian: Endian,
+    ) -> Result<Value<'s>, DecodeError> {
+        let elems: Result<_, _> = (0..len_usize(len)?)
+            .map(|i| {
+                let start = i.checked_mul(stride).ok_or(LayoutError::Overflow)?;
+                let slot = &data[range(start as u64, stride as u64, data.len())?];
+                self.value(ty, slot, endian)
+            })
+            .collect();
+        elems.map(Value::Array)
+    }
+    fn scalar(
+        &self,
+        prim: Primitive,
+        data: &[u8],
+        endian: Endian,
+    ) -> Result<Value<'s>, DecodeError> {
+        let Some(size) = self.target.size_of(prim) else {
+            return Ok(Value::Bytes(data.to_vec()));
+        };
+        let bytes = &data[range(0, size, data.len())?];
+        let Some(raw) = read_uint(bytes, endian) else {
+            return Ok(Value::Bytes(bytes.to_vec()));
+        };
+        Ok(match prim {
+            Primitive::Bool => Value::Bool(raw != 0),
+            Primitive::F16 => Value::Float(f16_to_f64(u16::try_from(raw).unwrap_or_default())),
+            Primitive::F32 => {
+                Value::Float(f32::from_bits(u32::try_from(raw).unwrap_or_default()).into())
+            }
+            Primitive::F64 => Value::Float(f64::from_bits(u64::try_from(raw).unwrap_or_default())),
+            Primitive::LongDouble | Primitive::Void => Value::Bytes(bytes.to_vec()),
+            Primitive::Char => u32::try_from(raw)
+                .ok()
+                .and_then(char::from_u32)
+                .map_or(Value::UInt(raw), Value::Char),
+            _ if prim.is_signed() => Value::Int(sign_extend(raw, size * 8)),
+            _ => Value::UInt(raw),
+        })
+    }
+}
+
+/// The range of `size` bytes at `offset` of a buffer of `len` bytes
+pub(crate) fn range(offset: u64, size: u64, len: usize) -> Result<Range<usize>, DecodeError> {
+    let end = offset.checked_add(size).ok_or(LayoutError::Overflow)?;
+    match (usize::try_from(offset), usize::try_from(end)) {
+        (Ok(start), Ok(end)) if end <= len => Ok(start..end),
+        _ => Err(DecodeError::TooShort { needed: end, len }),
+    }
+}
+
+/// Convert the length of an array to `usize`
+pub(crate) fn len_usize(len: u64) -> Result<usize, LayoutError> {
+    usize::try_from(len).map_err(|_| LayoutError::Overflow)
+}
+
+/// The distance in bytes between the components of `vector` in a slot of `slot` bytes
+///
+/// Unlike the elements of arrays, which are spread evenly over their slot, since
+/// layouts round the stride of arrays up to the alignment of their elements, the
+/// components of vectors are packed tightly, even when a GPU layout pads the vector,
+/// like the 16 bytes of a `vec3` in an array.
+pub(crate) fn component_stride(
+    registry: &Registry,
+    target: &Target,
+    vector: &Vector,
+    slot: usize,
+) -> usize {
+    registry
+        .primitive(&vector.ty)
+        .and_then(|prim| target.size_of(prim))
+        .and_then(|size| usize::try_from(size).ok())
+        .unwrap_or(slot / usize::from(vector.len.max(1)))
+}
+
+/// Read an unsigned integer of up to 16 bytes
+pub(crate) fn read_uint(bytes: &[u8], endian: Endian) -> Option<u128> {
+    if bytes.len() > 16 {
+        return None;
+    }
+    let fold = |acc: u128, &byte: &u8| acc << 8 | u128::from(byte);
+    Some(match endian {
+        Endian::Little => bytes.iter().rev().fold(0, fold),
+        Endian::Big => bytes.iter().fold(0, fold),
+    })
+}
+
+/// The shift of a bitfield of `width` bits starting at bit `bit` of the first of
+/// `len` bytes, within the integer read from them
+///
+/// Bitfields are allocated from the least significant bit on little endian targets,
+/// and from the most significant bit on big endian ones.
+pub(crate) fn bit_shift(len: usize, bit: u64, width: u64, endian: Endian) -> u64 {
+    match endian {
+        Endian::Little => bit,
+        Endian::Big => (len as u64 * 8).saturating_sub(bit + width),
+    }
+}
+
+/// Read the bitfield of `width` bits starting at bit `bit` of `bytes`
+fn bitfield(bytes: &[u8], bit: u64, width: u64, endian: Endian) -> u128 {
+    let raw = read_uint(bytes, endian).unwrap_or(0);
+    let shift = bit_shift(bytes.len(), bit, width, endian);
+    raw.checked_shr(u32::try_from(shift).unwrap_or(u32::MAX)).unwrap_or(0) & mask(width)
+}
+
+/// A mask of the lowest `bits` bits
+pub(crate) fn mask(bits: u64) -> u128 {
+    u128::MAX
+        .checked_shr(u32::try_from(128u64.saturating_sub(bits)).unwrap_or(128))
+        .unwrap_or(0)
+}
+
+/// Interpret the lowest `bits` bits of `raw` as a two's complement integer
+pub(crate) fn sign_extend(raw: u128, bits: u64) -> i128 {
+    let shift = u32::try_from(128u64.saturating_sub(bits)).unwrap_or(128);
+    raw.checked_shl(shift).map_or(0, |raw| raw.cast_signed() >> shift)
+}
+
+/// Convert the bits of a half precision float
+pub(crate) fn f16_to_f64(bits: u16) -> f64 {
+    let sign = if bits >> 15 == 1 { -1.0 } else { 1.0 };
+    let exp = i32::from((bits >> 10) & 0x1f);
+    let frac = f64::from(bits & 0x3ff);
+    match exp {
+        0 => sign * frac * 2f64.powi(-24),
+        0x1f if frac == 0.0 => sign * f64::INFINITY,
+        0x1f => f64::NAN,
+        _ => sign * (1.0 + frac / 1024.0) * 2f64.powi(exp - 15),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![expect(clippy::unwrap_used)]
+
+    use {super::*, crate::GpuRules, pretty_assertions::assert_eq};
+
+    #[test]
+    fn test_decode() {
+        let registry: Registry = [Struct::parse_c("struct Point { short x, y; };").unwrap()]
+            .into_iter()
+            .collect();
+        let s = Struct::parse_c(
+            "struct Rec { char tag; int len; struct Point pos[2]; float f; _Bool on;
+                void *next; long double ld; };",
+        )
+        .unwrap();
+        let target = Target::lp64();
+        let mut data = vec![0xff, 0, 0, 0, 0xfe, 0xff, 0xff, 0xff];
+        data.extend([1, 0, 2, 0, 3, 0, 0x04, 0x80]);
+        data.extend(1.5f32.to_le_bytes());
+        data.extend([1, 0, 0, 0]);
+        data.extend(0x1234u64.to_le_bytes());
+        data.extend([0xaa; 16]);
+        let layout = s.layout_for(&registry, &target).unwrap();
+        let point = |x, y| Value::Struct(vec![("x", Value::Int(x)), ("y", Value::Int(y))]);
+        assert_eq!(
+            s.decode(&layout, &registry, &target, &data),
+            Ok(Value::Struct(vec![
+                ("tag", Value::Int(-1)),
+                ("len", Value::Int(-2)),
+                ("pos", Value::Array(vec![point(1, 2), point(3, -0x7ffc)])),
+                ("f", Value::Float(1.5)),
+                ("on", Value::Bool(true)),
+                ("next", Value::UInt(0x1234)),
+                ("ld", Value::Bytes(vec![0xaa; 16])),
+            ]))
+        );
+        assert_eq!(
+            s.decode(&layout, &registry, &target, &data[..40]),
+            Err(DecodeError::TooShort {
+                needed: 48,
+                len: 40
+            })
+        );
+    }
+
+    #[test]
+    fn test_decode_gpu_layout() {
+        let s = Struct::parse_wgsl("struct U { a: array<vec3<f32>, 2>, m: mat3x3<f32> }").unwrap();
+        let registry = Registry::new();
+        let layout = s.layout_gpu(&registry, GpuRules::Std430).unwrap();
+        let data: Vec<u8> = (0..20u8).flat_map(|i| f32::from(i).to_le_bytes()).collect();
+        let vectors = |starts: &[u8]| {
+            let floats = |start: u8| (start..start + 3).map(|i| Value::Float(i.into())).collect();
+            Value::Array(starts.iter().map(|&start| Value::Array(floats(start))).collect())
+        };
+        // The vectors are 16 byte aligned, so every fourth float is padding
+        assert_eq!(
+            s.decode(&layout, &registry, &Target::lp64(), &data),
+            Ok(Value::Struct(vec![
+                ("a", vectors(&[0, 4])),
+                ("m", vectors(&[8, 12, 16])),
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_decode_bitfields_and_endianness() {
+        let s =
+            Struct::parse_c("struct B { unsigned a : 3; int b : 5; unsigned short c; };").unwrap();
+        let registry = Registry::new();
+        let target = Target::lp64();
+        let layout = s.layout_for(&registry, &target).unwrap();
+        // a = 5, b = -3
+        let data = [0b1110_1101, 0, 0x34, 0x12];
+        let value = s.decode(&layout, &registry, &target, &data).unwrap();
+        assert_eq!(value.field("a"), Some(&Value::UInt(5)));
+        assert_eq!(value.field("b"), Some(&Value::Int(-3)));
+        assert_eq!(value.field("c"), Some(&Value::UInt(0x1234)));
+        let target = Target::from_triple("powerpc-unknown-linux-gnu").unwrap();
+        let layout = s.layout_for(&registry, &target).unwrap();
+        let data = [0b1011_1101, 0, 0x12, 0x34];
+        let value = s.decode(&layout, &registry, &target, &data).unwrap();
+        assert_eq!(value.field("a"), Some(&Value::UInt(5)));
+        assert_eq!(value.field("b"), Some(&Value::Int(-3)));
+        assert_eq!(value.field("c"), Some(&Value::UInt(0x1234)));
+    }
+
+    #[test]
+    fn test_decode_mixed_endianness() {
+        let registry: Registry = [
+            Struct::parse("#[repr(C)] struct Inner { a: u16 }").unwrap(),
+            Struct::parse("#[repr(C)] #[endian(little)] struct Le { b: u16 }").unwrap(),
+        ]
+        .into_iter()
+        .collect();
+        let s = Struct::parse(
+            "#[repr(C)] #[endian(big)] struct H {
+                magic: u32, #[endian(little)] len: u16, crc: u16le, inner: Inner, le: Le,
+            }",
+        )
+        .unwrap();
+        let target = Target::lp64();
+        let layout = s.layout_for(&registry, &target).unwrap();
+        let data = [1, 2, 3, 4, 6, 5, 8, 7, 9, 10, 12, 11];
+        assert_eq!(
+            s.decode(&layout, &registry, &target, &data),
+            Ok(Value::Struct(vec![
+                ("magic", Value::UInt(0x0102_0304)),
+                ("len", Value::UInt(0x0506)),
+                ("crc", Value::UInt(0x0708)),
+                ("inner", Value::Struct(vec![("a", Value::UInt(0x090a))])),
+                ("le", Value::Struct(vec![("b", Value::UInt(0x0b0c))])),
+            ]))
+        );
+    }
+}
@@ -0,0 +1,292 @@
+//! Dependencies between the structs of a registry, for ordering their definitions
+
+use {
+    crate::{Definition, Registry, Struct, Ty},
+    std::{
+        cmp::Reverse,
+        collections::{BTreeMap, BTreeSet, BinaryHeap},
+    },
+    thiserror::Error,
+};
+
+/// The structs that the structs of a [`Registry`] refer to
+///
+/// A struct that contains another by value, directly or through arrays, vectors and
+/// aliases, needs its definition first, while one that only refers to another through
+/// a pointer only needs it declared. Enums and primitives are not part of the graph.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct DepGraph<'s> {
+    deps: BTreeMap<&'s str, Vec<Dep<'s>>>,
+}
+
+/// A struct that another one refers to
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Dep<'s> {
+    /// Name of the struct
+    pub name: &'s str,
+    /// Whether the struct is contained by value, rather than only behind pointers
+    pub by_value: bool,
+}
+
+/// An order to define the structs of a registry in
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct DefOrder<'s> {
+    /// The structs, each after the structs it contains by value
+    pub structs: Vec<&'s str>,
+    /// The structs that are referred to through a pointer before their definition, and
+    /// need to be declared first, like C's `struct Node;`
+    pub forward: Vec<&'s str>,
+}
+
+/// Structs that contain each other by value, and so would be infinitely large
+#[derive(Debug, PartialEq, Eq, Clone, Error)]
+#[error("Structs contain each other: {}", .0.join(", "))]
+pub struct CycleError(pub Vec<String>);
+
+impl<'s> Registry<'s> {
+    /// Compute the graph of the dependencies between the structs of the registry
+    ///
+    /// Names that refer to nothing are left out, see [`Registry::unresolved`].
+    #[must_use]
+    pub fn dependencies(&self) -> DepGraph<'s> {
+        let deps = self
+            .structs()
+            .map(|struct_| {
+                let mut deps = Vec::new();
+                self.collect_deps(struct_, &mut deps);
+                (struct_.name, deps)
+            })
+            .collect();
+        DepGraph { deps }
+    }
+    fn collect_deps(&self, struct_: &Struct<'s>, deps: &mut Vec<Dep<'s>>) {
+        for field in &struct_.fields {
+            self.ty_deps(&field.ty, true, &mut Vec::new(), deps);
+        }
+    }
+    /// Add the structs `ty` refers to, following aliases that aren't in `aliases` yet
+    fn ty_deps(
+        &self,
+        ty: &Ty<'s>,
+        by_value: bool,
+        aliases: &mut Vec<&'s str>,
+        deps: &mut Vec<Dep<'s>>,
+    ) {
+        match ty {
+            Ty::Ident(name) => match self.resolve(name) {
+                Some(Definition::Struct(struct_)) => {
+                    match deps.iter_mut().find(|dep| dep.name == struct_.name) {
+                        Some(dep) => dep.by_value |= by_value,
+                        None => deps.push(Dep {
+                            name: struct_.name,
+                            by_value,
+                        }),
+                    }
+                }
+                Some(Definition::Alias { name, ty }) if !aliases.contains(&name) => {
+                    aliases.push(name);
+                    self.ty_deps(ty, by_value, aliases, deps);
+                    aliases.pop();
+                }
+                _ => {}
+            },
+            Ty::Array(array) => self.ty_deps(&array.ty, by_value, aliases, deps),
+            Ty::Pointer(ty) => self.ty_deps(ty, false, aliases, deps),
+            Ty::FnPtr(fn_ptr) => {
+                for ty in fn_ptr.params.iter().chain([&*fn_ptr.ret]) {
+                    self.ty_deps(ty, false, aliases, deps);
+                }
+            }
+            Ty::Inline(inner) => {
+                for field in &inner.fields {
+                    self.ty_deps(&field.ty, by_value, aliases, deps);
+                }
+            }
+            Ty::Vector(vector) => self.ty_deps(&vector.ty, by_value, aliases, deps),
+            Ty::Matrix(matrix) => self.ty_deps(&matrix.ty, by_value, aliases, deps),
+        }
+    }
+}
+
+impl<'s> DepGraph<'s> {
+    /// The structs that the struct `name` refers to, in the order of its fields
+    #[must_use]
+    pub fn deps(&self, name: &str) -> &[Dep<'s>] {
+        self.deps.get(name).map_or(&[], Vec::as_slice)
+    }
+    /// The structs that the struct `name` contains by value
+    fn value_deps<'g>(&'g self, name: &str) -> impl Iterator<Item = &'s str> + 'g {
+        self.deps(name).iter().filter(|dep| dep.by_value).map(|dep| dep.name)
+    }
+    /// Find the groups of structs that contain each other by value
+    ///
+    /// A struct that contains itself is a group of its own. Pointers break cycles, so
+    /// `struct Node { next: *const Node }` is fine. Each group is sorted by name, and
+    /// the groups by their first name.
+    #[must_use]
+    pub fn cycles(&self) -> Vec<Vec<&'s str>> {
+        // Tarjan's algorithm for strongly connected components
+        #[derive(Default)]
+        struct State<'s> {
+            index: BTreeMap<&'s str, usize>,
+            low: BTreeMap<&'s str, usize>,
+            stack: Vec<&'s str>,
+            cycles: Vec<Vec<&'s str>>,
+        }
+        fn visit<'s>(graph: &DepGraph<'s>, name: &'s str, state: &mut State<'s>) {
+            let index = state.index.len();
+            state.index.insert(name, index);
+            state.low.insert(name, index);
+            state.stack.push(name);
+            for dep in graph.value_deps(name) {
+                let low = match state.index.get(dep) {
+                    None => {
+                        visit(graph, dep, state);
+                        state.low[dep]
+                    }
+                    Some(&index) if state.stack.contains(&dep) => index,
+                    Some(_) => continue,
+                };
+                if let Some(own) = state.low.get_mut(name) {
+                    *own = (*own).min(low);
+                }
+            }
+            if state.low[name] == index {
+                let start = state.stack.iter().rposition(|&n| n == name).unwrap_or(0);
+                let mut group = state.stack.split_off(start);
+                if group.len() > 1 || graph.value_deps(name).any(|dep| dep == name) {
+                    group.sort_unstable();
+                    state.cycles.push(group);
+                }
+            }
+        }
+        let mut state = State::default();
+        for &name in self.deps.keys() {
+            if !state.index.contains_key(name) {
+                visit(self, name, &mut state);
+            }
+        }
+        state.cycles.sort_unstable();
+        state.cycles
+    }
+    /// Find an order to define the structs in, where each struct comes after the
+    /// structs it contains by value
+    ///
+    /// Structs that don't depend on each other are ordered by name. Structs that are
+    /// referred to through a pointer before their definition are listed as needing a
+    /// forward declaration.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error listing the structs of the first cycle if structs contain each
+    /// other by value, see [`DepGraph::cycles`].
+    pub fn order(&self) -> Result<DefOrder<'s>, CycleError> {
+        if let Some(cycle) = self.cycles().into_iter().next() {
+            return Err(CycleError(cycle.into_iter().map(str::to_owned).collect()));
+        }
+        // Kahn's algorithm, taking the ready structs by name
+        let mut pending: BTreeMap<&str, usize> =
+            self.deps.keys().map(|&name| (name, self.value_deps(name).count())).collect();
+        let mut users: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+        for &name in self.deps.keys() {
+            for dep in self.value_deps(name) {
+                users.entry(dep).or_default().push(name);
+            }
+        }
+        let mut ready: BinaryHeap<_> = pending
+            .iter()
+            .filter(|&(_, &count)| count == 0)
+            .map(|(&name, _)| Reverse(name))
+            .collect();
+        let mut structs = Vec::with_capacity(self.deps.len());
+        while let Some(Reverse(name)) = ready.pop() {
+            structs.push(name);
+            for &user in users.get(name).into_iter().flatten() {
+                if let Some(count) = pending.get_mut(user) {
+                    *count -= 1;
+                    if *count == 0 {
+                        ready.push(Reverse(user));
+                    }
+                }
+            }
+        }
+        let mut defined = BTreeSet::new();
+        let mut forward = Vec::new();
+        for &name in &structs {
+            defined.insert(name);
+            for dep in self.deps(name) {
+                if !defined.contains(dep.name) && !forward.contains(&dep.name) {
+                    forward.push(dep.name);
+                }
+            }
+        }
+        Ok(DefOrder { structs, forward })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![expect(clippy::unwrap_used)]
+
+    use {super::*, pretty_assertions::assert_eq};
+
+    fn c_registry(defs: &[&'static str]) -> Registry<'static> {
+        defs.iter().map(|def| Struct::parse_c(def).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_dependencies() {
+        let mut registry = c_registry(&[
+            "struct Mesh { struct Vertex verts[4]; struct Mesh *next; Id id; };",
+            "struct Vertex { float pos[3]; struct { struct Color c; } attrs; };",
+            "struct Color { unsigned char rgba[4]; };",
+        ]);
+        registry.insert_alias("Id", Ty::Ident("Handle"));
+        registry.insert(Struct::parse_c("struct Handle { int raw; };").unwrap());
+        let graph = registry.dependencies();
+        assert_eq!(
+            graph.deps("Mesh"),
+            [
+                Dep {
+                    name: "Vertex",
+                    by_value: true
+                },
+                Dep {
+                    name: "Mesh",
+                    by_value: false
+                },
+                Dep {
+                    name: "Handle",
+                    by_value: true
+                }
+            ]
+        );
+        assert_eq!(graph.deps("Vertex")[0].name, "Color");
+        assert_eq!(graph.cycles(), Vec::<Vec<&str>>::new());
+        let order = graph.order().unwrap();
+        assert_eq!(order.structs, ["Color", "Handle", "Vertex", "Mesh"]);
+        assert_eq!(order.forward, Vec::<&str>::new());
+    }
+
+    #[test]
+    fn test_cycles_and_forward_declarations() {
+        let registry = c_registry(&[
+            "struct A { struct B b; };",
+            "struct B { struct A a[2]; };",
+            "struct C { struct C c; };",
+            "struct D { struct D *d; };",
+        ]);
+        let graph = registry.dependencies();
+        assert_eq!(graph.cycles(), [vec!["A", "B"], vec!["C"]]);
+        assert_eq!(graph.order(), Err(CycleError(vec!["A".into(), "B".into()])));
+        // Pointers break cycles, but need forward declarations
+        let registry = c_registry(&[
+            "struct Parent { struct Child *first; };",
+            "struct Child { struct Parent *parent; struct Child *next; };",
+            "struct Tree { struct Parent root; };",
+        ]);
+        let order = registry.dependencies().order().unwrap();
+        assert_eq!(order.structs, ["Child", "Parent", "Tree"]);
+        assert_eq!(order.forward, ["Parent"]);
+    }
+}
@@ -0,0 +1,561 @@
+//! Encoding values into binary data through struct definitions
+
+use {
+    crate::{
+        DecodeError, Definition, Endian, Layout, LayoutError, Primitive, Registry, Struct, Target,
+        Ty, Value, Vector,
+        decode::{bit_shift, component_stride, len_usize, mask, range, read_uint, sign_extend},
+    },
+    std::fmt,
+    thiserror::Error,
+};
+
+/// Error that can happen while encoding a value
+#[derive(Debug, PartialEq, Eq, Clone, Error)]
+pub enum EncodeError {
+    /// A value that doesn't fit in its type, like `300` for a `u8`
+    #[error("Value {value} out of range of {ty}")]
+    OutOfRange {
+        /// The type of the value
+        ty: String,
+        /// The value, as displayed
+        value: String,
+    },
+    /// An array value with a different number of elements than its type
+    #[error("Expected {expected} elements, found {found}")]
+    ArrayLength {
+        /// The number of elements of the type
+        expected: u64,
+        /// The number of elements of the value
+        found: usize,
+    },
+    /// A value of the wrong kind, like a float for a struct
+    #[error("Expected a value of type {ty}, found {value}")]
+    TypeMismatch {
+        /// The type of the value
+        ty: String,
+        /// The value, as displayed
+        value: String,
+    },
+    /// A struct value without a value for a named field
+    #[error("Missing value of field {0}")]
+    MissingField(String),
+    /// A layout that places a field beyond the end of its struct
+    #[error("Field out of bounds: {needed} bytes needed, but only {len} available")]
+    TooShort {
+        /// The number of bytes the field needs
+        needed: u64,
+        /// The size of the struct
+        len: usize,
+    },
+    /// The layout of a nested struct couldn't be computed
+    #[error(transparent)]
+    Layout(#[from] LayoutError),
+}
+
+impl From<DecodeError> for EncodeError {
+    fn from(err: DecodeError) -> Self {
+        match err {
+            DecodeError::TooShort { needed, len } => Self::TooShort { needed, len },
+            DecodeError::Layout(err) => Self::Layout(err),
+        }
+    }
+}
+
+impl<'s> Struct<'s> {
+    /// Encode a value of this struct, with its fields placed following `layout`
+    ///
+    /// This is the inverse of [`Struct::decode`], taking the same `layout`,
+    /// `registry` and `target`, and using the same byte orders. Padding, and the bits
+    /// of bitfield units that no field uses, are filled with `fill`. The fields of
+    /// `value` are matched by name, and anonymous members and unnamed bitfields by
+    /// position; those without a value are left filled. Raw bytes can stand for a
+    /// value of any type that isn't smaller.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a value doesn't fit its type, an array value has the wrong
+    /// number of elements, a named field has no value, or if the layout of a nested
+    /// struct can't be computed.
+    pub fn encode(
+        &self,
+        layout: &Layout,
+        registry: &Registry<'s>,
+        target: &Target,
+        value: &Value,
+        fill: u8,
+    ) -> Result<Vec<u8>, EncodeError> {
+        let len = usize::try_from(layout.size).map_err(|_| LayoutError::Overflow)?;
+        let mut data = vec![fill; len];
+        Encoder { registry, target }.struct_value(self, layout, value, &mut data, target.endian)?;
+        Ok(data)
+    }
+}
+
+/// Encodes values with the types of a registry
+struct Encoder<'a, 's> {
+    registry: &'a Registry<'s>,
+    target: &'a Target,
+}
+
+impl<'s> Encoder<'_, 's> {
+    fn struct_value(
+        &self,
+        struct_: &Struct<'s>,
+        layout: &Layout,
+        value: &Value,
+        data: &mut [u8],
+        endian: Endian,
+    ) -> Result<(), EncodeError> {
+        let Value::Struct(values) = value else {
+            return Err(mismatch(&struct_.name, value));
+        };
+        let endian = struct_.repr.endian.unwrap_or(endian);
+        let len = data.len();
+        let data = &mut data[range(0, layout.size, len)?];
+        for (i, (field, placed)) in struct_.fields.iter().zip(&layout.fields).enumerate() {
+            let value = match values.get(i) {
+                Some((name, value)) if *name == field.name => Some(value),
+                _ if field.name.is_empty() => None,
+                _ => values.iter().find(|(name, _)| *name == field.name).map(|(_, value)| value),
+            };
+            let Some(value) = value else {
+                if field.name.is_empty() {
+                    continue;
+                }
+                return Err(EncodeError::MissingField(field.name.to_owned()));
+            };
+            let len = data.len();
+            let bytes = &mut data[range(placed.offset, placed.size, len)?];
+            let endian = field.endian.unwrap_or(endian);
+            match placed.bits {
+                Some(bits) => {
+                    let raw = match self.registry.primitive(&field.ty) {
+                        Some(Primitive::Bool) => match value {
+                            Value::Bool(bool) => u128::from(*bool),
+                            _ => return Err(mismatch(&field.ty, value)),
+                        },
+                        prim => {
+                            let signed = prim.is_some_and(Primitive::is_signed);
+                            int_bits(value, bits.width, signed)
+                                .ok_or_else(|| out_of_range(&field.ty, value))?
+                        }
+                    };
+                    set_bitfield(bytes, bits.offset % 8, bits.width, raw, endian);
+                }
+                None => self.value(&field.ty, value, bytes, endian)?,
+            }
+        }
+        Ok(())
+    }
+    /// Encode a value of type `ty` into `data`, which holds exactly the bytes of its
+    /// slot, including any padding of array elements
+    fn value(
+        &self,
+        ty: &Ty<'s>,
+        value: &Value,
+        data: &mut [u8],
+        endian: Endian,
+    ) -> Result<(), EncodeError> {
+        if let Value::Bytes(bytes) = value {
+            let Some(slot) = data.get_mut(..bytes.len()) else {
+                return Err(mismatch(ty, value));
+            };
+            slot.copy_from_slice(bytes);
+            return Ok(());
+        }
+        match ty {
+            Ty::Ident(name) => {
+                let endian = Primitive::name_endian(name).unwrap_or(endian);
+                match self.registry.resolve(name) {
+                    Some(Definition::Primitive(prim)) => self.scalar(prim, value, data, endian),
+                    Some(Definition::Struct(struct_)) => {
+                        let layout = struct_.layout_for(self.registry, self.target)?;
+                        self.struct_value(struct_, &layout, value, data, endian)
+                    }
+                    Some(Definition::Enum(enum_)) => match &enum_.ty {
+                        Some(ty) => self.value(ty, value, data, endian),
+                        None => self.scalar(Primitive::I32, value, data, endian),
+                    },
+                    Some(Definition::Alias { ty, .. }) => self.value(ty, value, data, endian),
+                    None => Err(mismatch(ty, value)),
+                }
+            }
+            Ty::Array(array) => {
+                let stride = data.len().checked_div(len_usize(array.len)?).unwrap_or(0);
+                self.elems(&array.ty, array.len, stride, value, data, endian)
+            }
+            Ty::Pointer(_) | Ty::FnPtr(_) => {
+                let size = self.target.pointer_size;
+                let raw =
+                    int_bits(value, size * 8, false).ok_or_else(|| out_of_range(ty, value))?;
+                let len = data.len();
+                write_uint(&mut data[range(0, size, len)?], raw, endian);
+                Ok(())
+            }
+            Ty::Inline(inner) => {
+                let layout = inner.layout_for(self.registry, self.target)?;
+                self.struct_value(inner, &layout, value, data, endian)
+            }
+            Ty::Vector(vector) => {
+                let stride = component_stride(self.registry, self.target, vector, data.len());
+                self.elems(&vector.ty, vector.len.into(), stride, value, data, endian)
+            }
+            Ty::Matrix(matrix) => {
+                let (len, count) = if matrix.row_major {
+                    (matrix.columns, matrix.rows)
+                } else {
+                    (matrix.rows, matrix.columns)
+                };
+                let vector = Ty::Vector(Vector {
+                    ty: matrix.ty.clone(),
+                    len,
+                });
+                let stride = data.len() / usize::from(count.max(1));
+                self.elems(&vector, count.into(), stride, value, data, endian)
+            }
+        }
+    }
+    /// Encode `len` elements of type `ty`, `stride` bytes apart in `data`
+    fn elems(
+        &self,
+        ty: &Ty<'s>,
+        len: u64,
+        stride: usize,
+        value: &Value,
+        data: &mut [u8],
+        endian: Endian,
+    ) -> Result<(), EncodeError> {
+        let Value::Array(elems) = value else {
+            return Err(mismatch(&format_args!("[{ty}; {len}]"), value));
+        };
+        if elems.len() as u64 != len {
+            return Err(EncodeError::ArrayLength {
+                expected: len,
+                found: elems.len(),
+            });
+        }
+        for (i, elem) in elems.iter().enumerate() {
+            let start = i.checked_mul(stride).ok_or(LayoutError::Overflow)?;
+            let len = data.len();
+            self.value(
+                ty,
+                elem,
+                &mut data[range(start as u64, stride as u64, len)?],
+                endian,
+            )?;
+        }
+        Ok(())
+    }
+    fn scalar(
+        &self,
+        prim: Primitive,
+        value: &Value,
+        data: &mut [u8],
+        endian: Endian,
+    ) -> Result<(), EncodeError> {
+        let Some(size) = self.target.size_of(prim) else {
+            return Err(mismatch(&prim, value));
+        };
+        let raw = match (prim, value) {
+            (Primitive::Bool, Value::Bool(bool)) => Some(u128::from(*bool)),
+            (Primitive::F16, Value::Float(float)) => f64_to_f16(*float).map(u128::from),
+            (Primitive::F32, Value::Float(float)) => f64_to_f32(*float).map(u128::from),
+            (Primitive::F64, Value::Float(float)) => Some(float.to_bits().into()),
+            (Primitive::Char, Value::Char(char)) => Some(u32::from(*char).into()),
+            (
+                Primitive::Bool
+                | Primitive::F16
+                | Primitive::F32
+                | Primitive::F64
+                | Primitive::LongDouble
+                | Primitive::Void,
+                _,
+            )
+            | (
+                _,
+                Value::Bool(_)
+                | Value::Float(_)
+                | Value::Char(_)
+                | Value::Array(_)
+                | Value::Struct(_),
+            ) => {
+                return Err(mismatch(&prim, value));
+            }
+            _ => int_bits(value, size * 8, prim.is_signed()),
+        };
+        let raw = raw.ok_or_else(|| out_of_range(&prim, value))?;
+        let len = data.len();
+        write_uint(&mut data[range(0, size, len)?], raw, endian);
+        Ok(())
+    }
+}
+
+fn mismatch(ty: &dyn fmt::Display, value: &Value) -> EncodeError {
+    EncodeError::TypeMismatch {
+        ty: ty.to_string(),
+        value: value.to_string(),
+    }
+}
+
+fn out_of_range(ty: &dyn fmt::Display, value: &Value) -> EncodeError {
+    EncodeError::OutOfRange {
+        ty: ty.to_string(),
+        value: value.to_string(),
+    }
+}
+
+/// The lowest `bits` bits of an integer value, if it fits in them
+fn int_bits(value: &Value, bits: u64, signed: bool) -> Option<u128> {
+    let (int, negative) = match *value {
+        Value::Int(int) => (int.cast_unsigned(), int < 0),
+        Value::UInt(int) => (int, false),
+        _ => return None,
+    };
+    let raw = int & mask(bits);
+    let fits = if signed {
+        let extended = sign_extend(raw, bits);
+        extended.cast_unsigned() == int && (extended < 0) == negative
+    } else {
+        raw == int && !negative
+    };
+    fits.then_some(raw)
+}
+
+/// Write an unsigned integer into all of `bytes`
+pub(crate) fn write_uint(bytes: &mut [u8], mut raw: u128, endian: Endian) {
+    let mut write = |byte: &mut u8| {
+        *byte = raw.to_le_bytes()[0];
+        raw = raw.checked_shr(8).unwrap_or(0);
+    };
+    match endian {
+        Endian::Little => bytes.iter_mut().for_each(&mut write),
+        Endian::Big => bytes.iter_mut().rev().for_each(&mut write),
+    }
+}
+
+/// Replace the bitfield of `width` bits starting at bit `bit` of `bytes`
+fn set_bitfield(bytes: &mut [u8], bit: u64, width: u64, raw: u128, endian: Endian) {
+    let shift = u32::try_from(bit_shift(bytes.len(), bit, width, endian)).unwrap_or(u32::MAX);
+    let field = mask(width).checked_shl(shift).unwrap_or(0);
+    let old = read_uint(bytes, endian).unwrap_or(0);
+    let new = old & !field | raw.checked_shl(shift).unwrap_or(0) & field;
+    write_uint(bytes, new, endian);
+}
+
+/// The bits of a single precision float, if it is in range
+#[expect(clippy::cast_possible_truncation, reason = "range checked before")]
+fn f64_to_f32(float: f64) -> Option<u32> {
+    (!float.is_finite() || float.abs() <= f64::from(f32::MAX)).then(|| (float as f32).to_bits())
+}
+
+/// The bits of a half precision float, if it is in range, rounded to nearest even
+#[expect(
+    clippy::cast_possible_truncation,
+    clippy::cast_sign_loss,
+    reason = "range checked before"
+)]
+fn f64_to_f16(float: f64) -> Option<u16> {
+    // Halfway between the largest finite f16, 65504, and the next step of 32, from
+    // where values would round up to infinity
+    const OVERFLOW: f64 = 65520.0;
+    let sign = if float.is_sign_negative() { 0x8000 } else { 0 };
+    if float.is_nan() {
+        return Some(0x7e00);
+    }
+    if float.is_infinite() {
+        return Some(sign | 0x7c00);
+    }
+    if float.abs() >= OVERFLOW {
+        return None;
+    }
+    // Scale to an integer count of the smallest subnormal, 2^-24, and round it
+    let units = (float.abs() * 2f64.powi(24)).round_ties_even();
+    let units = units as u64;
+    if units < 0x400 {
+        // Subnormal, or the smallest normal after rounding
+        return Some(sign | u16::try_from(units).ok()?);
+    }
+    // Normal: find the exponent, then round the 10 bit fraction
+    let exp = units.ilog2() - 10;
+    let frac = (float.abs() * 2f64.powi(24 - i32::try_from(exp).ok()?)).round_ties_even();
+    let frac = frac as u64;
+    let bits = (u64::from(exp + 1) << 10) + (frac - 0x400);
+    u16::try_from(bits).ok().map(|bits| sign | bits)
+}
+
+#[cfg(test)]
+mod tests {
+    #![expect(clippy::unwrap_used)]
+
+    use {super::*, crate::GpuRules, pretty_assertions::assert_eq};
+
+    #[test]
+    fn test_encode_round_trip() {
+        let registry: Registry = [Struct::parse_c("struct Point { short x, y; };").unwrap()]
+            .into_iter()
+            .collect();
+        let s = Struct::parse_c(
+            "struct Rec { char tag; int len; struct Point pos[2]; float f; _Bool on;
+                void *next; unsigned a : 3; int b : 5; };",
+        )
+        .unwrap();
+        let target = Target::lp64();
+        let layout = s.layout_for(&registry, &target).unwrap();
+        let point = |x, y| Value::Struct(vec![("x", Value::Int(x)), ("y", Value::Int(y))]);
+        let value = Value::Struct(vec![
+            ("tag", Value::Int(-1)),
+            ("len", Value::Int(-2)),
+            ("pos", Value::Array(vec![point(1, 2), point(3, -0x7ffc)])),
+            ("f", Value::Float(1.5)),
+            ("on", Value::Bool(true)),
+            ("next", Value::UInt(0x1234)),
+            ("a", Value::UInt(5)),
+            ("b", Value::Int(-3)),
+        ]);
+        let data = s.encode(&layout, &registry, &target, &value, 0xcc).unwrap();
+        let mut expected = vec![0xff, 0xcc, 0xcc, 0xcc, 0xfe, 0xff, 0xff, 0xff];
+        expected.extend([1, 0, 2, 0, 3, 0, 0x04, 0x80]);
+        expected.extend(1.5f32.to_le_bytes());
+        expected.extend([1, 0xcc, 0xcc, 0xcc]);
+        expected.extend(0x1234u64.to_le_bytes());
+        expected.extend([0b1110_1101, 0xcc, 0xcc, 0xcc, 0xcc, 0xcc, 0xcc, 0xcc]);
+        assert_eq!(data, expected);
+        assert_eq!(s.decode(&layout, &registry, &target, &data), Ok(value));
+        let target = Target::from_triple("powerpc-unknown-linux-gnu").unwrap();
+        let s =
+            Struct::parse_c("struct B { unsigned a : 3; int b : 5; unsigned short c; };").unwrap();
+        let layout = s.layout_for(&registry, &target).unwrap();
+        let value = Value::Struct(vec![
+            ("a", Value::UInt(5)),
+            ("b", Value::Int(-3)),
+            ("c", Value::UInt(0x1234)),
+        ]);
+        let data = s.encode(&layout, &registry, &target, &value, 0).unwrap();
+        assert_eq!(data, [0b1011_1101, 0, 0x12, 0x34]);
+    }
+
+    #[test]
+    fn test_encode_gpu_layout() {
+        let s = Struct::parse_glsl("layout(std430) buffer U { vec3 a[2]; mat3 m; };").unwrap();
+        let registry = Registry::new();
+        let target = Target::lp64();
+        let layout = s.layout_gpu(&registry, GpuRules::Std430).unwrap();
+        let vectors = |starts: &[u8]| {
+            let floats = |start: u8| (start..start + 3).map(|i| Value::Float(i.into())).collect();
+            Value::Array(starts.iter().map(|&start| Value::Array(floats(start))).collect())
+        };
+        let value = Value::Struct(vec![("a", vectors(&[0, 4])), ("m", vectors(&[8, 12, 16]))]);
+        let data = s.encode(&layout, &registry, &target, &value, 0xcc).unwrap();
+        // Every fourth float pads a vector to 16 bytes
+        let expected: Vec<u8> = (0..20u8)
+            .flat_map(|i| match i % 4 {
+                3 => [0xcc; 4],
+                _ => f32::from(i).to_le_bytes(),
+            })
+            .collect();
+        assert_eq!(data, expected);
+        assert_eq!(s.decode(&layout, &registry, &target, &data), Ok(value));
+    }
+
+    #[test]
+    fn test_encode_endianness() {
+        let s = Struct::parse(
+            "#[repr(C)] #[endian(big)] struct S { a: u16, b: i32le, #[endian(little)] c: [u16; 2] }",
+        )
+        .unwrap();
+        let registry = Registry::new();
+        let target = Target::lp64();
+        let layout = s.layout_for(&registry, &target).unwrap();
+        let value = Value::Struct(vec![
+            ("a", Value::UInt(0x0102)),
+            ("b", Value::Int(-2)),
+            (
+                "c",
+                Value::Array(vec![Value::UInt(0x0304), Value::UInt(0x0506)]),
+            ),
+        ]);
+        let data = s.encode(&layout, &registry, &target, &value, 0).unwrap();
+        assert_eq!(data, [1, 2, 0, 0, 0xfe, 0xff, 0xff, 0xff, 4, 3, 6, 5]);
+        assert_eq!(s.decode(&layout, &registry, &target, &data), Ok(value));
+    }
+
+    #[test]
+    fn test_encode_floats() {
+        for float in [0.0, -1.5, 65504.0, 6.1e-5, 5.96e-8, 1.0 / 3.0] {
+            let bits = f64_to_f16(float).unwrap();
+            let half = crate::decode::f16_to_f64(bits);
+            assert!(
+                (half - float).abs() <= float.abs() / 1024.0,
+                "{float} -> {half}"
+            );
+        }
+        assert_eq!(f64_to_f16(1.0), Some(0x3c00));
+        assert_eq!(f64_to_f16(-2.0), Some(0xc000));
+        assert_eq!(f64_to_f16(65504.0), Some(0x7bff));
+        assert_eq!(f64_to_f16(65519.0), Some(0x7bff));
+        assert_eq!(f64_to_f16(-65519.99), Some(0xfbff));
+        assert_eq!(f64_to_f16(65520.0), None);
+        assert_eq!(f64_to_f32(1e39), None);
+    }
+
+    #[test]
+    fn test_encode_errors() {
+        let s = Struct::parse("struct S { a: u8, b: [i16; 2], c: bool }").unwrap();
+        let registry = Registry::new();
+        let target = Target::lp64();
+        let layout = s.layout_for(&registry, &target).unwrap();
+        let encode = |a, b, c| {
+            let value = Value::Struct(vec![("a", a), ("b", Value::Array(b)), ("c", c)]);
+            s.encode(&layout, &registry, &target, &value, 0)
+        };
+        let b = || vec![Value::Int(-0x8000), Value::UInt(0x7fff)];
+        assert_eq!(
+            encode(Value::UInt(255), b(), Value::Bool(false)),
+            Ok(vec![255, 0, 0, 0x80, 0xff, 0x7f, 0, 0])
+        );
+        assert_eq!(
+            encode(Value::UInt(256), b(), Value::Bool(false)),
+            Err(EncodeError::OutOfRange {
+                ty: "u8".into(),
+                value: "256".into()
+            })
+        );
+        assert_eq!(
+            encode(Value::Int(-1), b(), Value::Bool(false)),
+            Err(EncodeError::OutOfRange {
+                ty: "u8".into(),
+                value: "-1".into()
+            })
+        );
+        assert_eq!(
+            encode(
+                Value::UInt(0),
+                vec![Value::Int(-0x8001), Value::Int(0)],
+                Value::Bool(false)
+            ),
+            Err(EncodeError::OutOfRange {
+                ty: "i16".into(),
+                value: "-32769".into()
+            })
+        );
+        assert_eq!(
+            encode(Value::UInt(0), vec![Value::Int(0)], Value::Bool(false)),
+            Err(EncodeError::ArrayLength {
+                expected: 2,
+                found: 1
+            })
+        );
+        assert_eq!(
+            encode(Value::UInt(0), b(), Value::Float(1.0)),
+            Err(EncodeError::TypeMismatch {
+                ty: "bool".into(),
+                value: "1.0".into()
+            })
+        );
+        assert_eq!(
+            s.encode(&layout, &registry, &target, &Value::Struct(vec![]), 0),
+            Err(EncodeError::MissingField("a".into()))
+        );
+    }
+}
@@ -0,0 +1,314 @@
+//! Source languages, and detecting which one a struct definition is written in
+
+use {
+    crate::tokenize::{Token, TokenKind, tokenize},
+    std::path::Path,
+};
+
+/// A source language with a frontend in this crate
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Lang {
+    /// Rust
+    Rust,
+    /// C
+    C,
+    /// C++
+    Cpp,
+    /// Zig
+    Zig,
+    /// Go
+    Go,
+    /// The OpenGL Shading Language
+    Glsl,
+    /// The WebGPU Shading Language
+    Wgsl,
+    /// The High-Level Shading Language of Direct3D
+    Hlsl,
+    /// C#
+    CSharp,
+    /// FlatBuffers schemas
+    FlatBuffers,
+    /// Protocol Buffers schemas
+    Protobuf,
+}
+
+impl Lang {
+    /// Look up a language by its name, like `rust` or `c`
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "rust" | "rs" => Some(Self::Rust),
+            "c" => Some(Self::C),
+            "cpp" | "c++" | "cxx" => Some(Self::Cpp),
+            "zig" => Some(Self::Zig),
+            "go" | "golang" => Some(Self::Go),
+            "glsl" => Some(Self::Glsl),
+            "wgsl" => Some(Self::Wgsl),
+            "hlsl" => Some(Self::Hlsl),
+            "csharp" | "c#" | "cs" => Some(Self::CSharp),
+            "flatbuffers" | "fbs" => Some(Self::FlatBuffers),
+            "protobuf" | "proto" => Some(Self::Protobuf),
+            _ => None,
+        }
+    }
+    /// Look up the language a file extension (without the dot) belongs to
+    #[must_use]
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        match ext {
+            "rs" => Some(Self::Rust),
+            "c" | "h" => Some(Self::C),
+            "cpp" | "cc" | "cxx" | "hpp" | "hh" | "hxx" => Some(Self::Cpp),
+            "zig" => Some(Self::Zig),
+            "go" => Some(Self::Go),
+            "glsl" | "vert" | "frag" | "comp" | "geom" | "tesc" | "tese" => Some(Self::Glsl),
+            "wgsl" => Some(Self::Wgsl),
+            "hlsl" | "hlsli" | "fx" => Some(Self::Hlsl),
+            "cs" => Some(Self::CSharp),
+            "fbs" => Some(Self::FlatBuffers),
+            "proto" => Some(Self::Protobuf),
+            _ => None,
+        }
+    }
+    /// Detect the language of `input`
+    ///
+    /// A known extension of `path` takes precedence, otherwise the language is guessed
+    /// from the content. Ambiguous input is assumed to be Rust. Since C++ headers often
+    /// use the `.h` extension, those can also be detected as C++.
+    #[must_use]
+    pub fn detect(input: &str, path: Option<&Path>) -> Self {
+        match path.and_then(|path| path.extension()?.to_str()).and_then(Self::from_extension) {
+            Some(Self::C) if Self::detect_content(input) == Self::Cpp => Self::Cpp,
+            Some(lang) => lang,
+            None => Self::detect_content(input),
+        }
+    }
+    /// Guess the language of `input` from its content alone
+    #[must_use]
+    pub fn detect_content(input: &str) -> Self {
+        let Ok(tokens) = tokenize(input) else {
+            return Self::Rust;
+        };
+        // Positive scores point to Rust, negative ones to C
+        let mut score = 0i32;
+        let mut cpp = false;
+        let mut zig = false;
+        let mut go = false;
+        let mut csharp = false;
+        let mut shader = None;
+        let mut schema = None;
+        let mut depth = 0usize;
+        let mut angle_depth = 0usize;
+        for (i, tok) in tokens.iter().enumerate() {
+            let next = tokens.get(i + 1).map(|tok| tok.kind);
+            let path_sep = next == Some(TokenKind::Colon)
+                && tokens.get(i + 2).map(|tok| tok.kind) == Some(TokenKind::Colon);
+            match Self::declaration_lang(input, &tokens, i, depth) {
+                Some(lang @ (Self::FlatBuffers | Self::Protobuf)) => schema = Some(lang),
+                Some(lang) => shader = Some(lang),
+                None => {}
+            }
+            match tok.kind {
+                TokenKind::LBrace => depth += 1,
+                TokenKind::RBrace => depth = depth.saturating_sub(1),
+                TokenKind::Lt => angle_depth += 1,
+                TokenKind::Gt => angle_depth = angle_depth.saturating_sub(1),
+                // `#[attr]` versus `#pragma` and other preprocessor directives
+                TokenKind::Hash if next == Some(TokenKind::LSqBracket) => score += 3,
+                TokenKind::Hash => score -= 3,
+                // Rust separates fields with commas, C terminates declarations with semicolons
+                TokenKind::Comma if depth == 1 && angle_depth == 0 => score += 1,
+                TokenKind::Semi if depth == 1 => score -= 1,
+                TokenKind::Ident => match &input[tok.span.clone()] {
+                    "typedef" | "__attribute__" | "__declspec" | "_Alignas" => score -= 3,
+                    "class" | "namespace" | "template" | "public" | "protected" | "private"
+                    | "virtual" => {
+                        score -= 3;
+                        cpp = true;
+                    }
+                    // `const Foo = extern struct`
+                    "const"
+                        if next == Some(TokenKind::Ident)
+                            && tokens.get(i + 2).map(|tok| tok.kind) == Some(TokenKind::Eq) =>
+                    {
+                        zig = true;
+                    }
+                    // `type Foo struct` and `package foo`
+                    "type"
+                        if next == Some(TokenKind::Ident)
+                            && tokens.get(i + 2).map(|tok| tok.kind)
+                                == Some(TokenKind::KwStruct) =>
+                    {
+                        go = true;
+                    }
+                    "package" if depth == 0 && next == Some(TokenKind::Ident) => go = true,
+                    // `[StructLayout(LayoutKind.Sequential)]` and `using System;`
+                    "StructLayout" | "FieldOffset" | "MarshalAs" => csharp = true,
+                    "using"
+                        if tokens.get(i + 1).map(|tok| &input[tok.span.clone()])
+                            == Some("System") =>
+                    {
+                        csharp = true;
+                    }
+                    "pub" | "mut" => score += 2,
+                    // `name: Type` fields
+                    _ if depth == 1 && next == Some(TokenKind::Colon) && !path_sep => score += 1,
+                    _ => {}
+                },
+                // `std::` and other qualified names, which C doesn't have
+                TokenKind::Colon if next == Some(TokenKind::Colon) => cpp = true,
+                _ => {}
+            }
+        }
+        if zig {
+            return Self::Zig;
+        }
+        if go {
+            return Self::Go;
+        }
+        if csharp {
+            return Self::CSharp;
+        }
+        if let Some(lang) = schema.or(shader) {
+            return lang;
+        }
+        match (score < 0, cpp) {
+            (true, true) => Self::Cpp,
+            (true, false) => Self::C,
+            (false, _) => Self::Rust,
+        }
+    }
+    /// The shader or schema language that the `i`th of `tokens` is a telltale of, like
+    /// the `message` of `message Foo {`
+    fn declaration_lang(input: &str, tokens: &[Token], i: usize, depth: usize) -> Option<Self> {
+        let tok = &tokens[i];
+        if tok.kind != TokenKind::Ident {
+            return None;
+        }
+        let next = tokens.get(i + 1).map(|tok| tok.kind);
+        let after_next = tokens.get(i + 2).map(|tok| tok.kind);
+        // A keyword starting a named block, like `message Foo {`
+        let named_block = next == Some(TokenKind::Ident) && after_next == Some(TokenKind::LBrace);
+        let after_path_sep =
+            i >= 2 && tokens[i - 2..i].iter().all(|tok| tok.kind == TokenKind::Colon);
+        match &input[tok.span.clone()] {
+            // `uniform Camera {`, which `layout(std140)` may precede
+            "uniform" | "buffer" if depth == 0 && named_block => Some(Self::Glsl),
+            "layout" if next == Some(TokenKind::LParen) => Some(Self::Glsl),
+            "cbuffer" | "tbuffer" => Some(Self::Hlsl),
+            // `vec4<f32>` and `mat4x3<f32>`, but not C++'s `std::vector<int>`
+            name if next == Some(TokenKind::Lt)
+                && !after_path_sep
+                && is_wgsl_vector_or_matrix(name) =>
+            {
+                Some(Self::Wgsl)
+            }
+            // `table Foo {` and `root_type Foo;`
+            "table" if depth == 0 && named_block => Some(Self::FlatBuffers),
+            "root_type"
+                if depth == 0
+                    && next == Some(TokenKind::Ident)
+                    && after_next == Some(TokenKind::Semi) =>
+            {
+                Some(Self::FlatBuffers)
+            }
+            // `message Foo {` and `syntax = "proto3";`
+            "message" if depth == 0 && named_block => Some(Self::Protobuf),
+            "syntax" if depth == 0 && next == Some(TokenKind::Eq) => Some(Self::Protobuf),
+            _ => None,
+        }
+    }
+}
+
+/// Whether `name` is a WGSL vector or matrix type, like `vec4` or `mat4x3`
+fn is_wgsl_vector_or_matrix(name: &str) -> bool {
+    let dim = |b: u8| (b'2'..=b'4').contains(&b);
+    match name.as_bytes() {
+        [b'v', b'e', b'c', len] => dim(*len),
+        [b'm', b'a', b't', columns, b'x', rows] => dim(*columns) && dim(*rows),
+        _ => false,
+    }
+}
+
+impl std::fmt::Display for Lang {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Rust => "Rust",
+            Self::C => "C",
+            Self::Cpp => "C++",
+            Self::Zig => "Zig",
+            Self::Go => "Go",
+            Self::Glsl => "GLSL",
+            Self::Wgsl => "WGSL",
+            Self::Hlsl => "HLSL",
+            Self::CSharp => "C#",
+            Self::FlatBuffers => "FlatBuffers",
+            Self::Protobuf => "Protobuf",
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, pretty_assertions::assert_eq};
+    #[test]
+    fn test_detect_content() {
+        for (input, lang) in [
+            ("struct Foo { a: u32, b: [u8; 4] }", Lang::Rust),
+            ("#[repr(C)] struct Foo { a: u32 }", Lang::Rust),
+            ("struct Foo { int a; char b[4]; };", Lang::C),
+            ("typedef struct { int a; } Foo;", Lang::C),
+            ("#pragma pack(1)\nstruct Foo { x: u8 }", Lang::C),
+            ("struct Empty {}", Lang::Rust),
+            ("class Foo { public: uint32_t x; };", Lang::Cpp),
+            ("struct Foo { std::array<int, 4> a; };", Lang::Cpp),
+            ("struct Foo { std::vector<int> v; };", Lang::Cpp),
+            ("struct message { int len; char data[16]; };", Lang::C),
+            ("struct Foo { table entries; int x; };", Lang::C),
+            ("struct Foo { buffer data; int x; };", Lang::C),
+            ("uniform Camera { mat4 view; };", Lang::Glsl),
+            (
+                "class Foo { public: std::vector<uint8_t> data; };",
+                Lang::Cpp,
+            ),
+            ("struct Foo { a: core::ffi::c_int, b: u8 }", Lang::Rust),
+            ("struct Foo { pos: vec4<f32>, m: mat4x3<f32> }", Lang::Wgsl),
+            ("const Foo = extern struct { a: u32 };", Lang::Zig),
+            (
+                "pub const Foo = packed struct(u8) { a: u3, b: u5 };",
+                Lang::Zig,
+            ),
+            (
+                "[StructLayout(LayoutKind.Sequential)] struct Foo { public int A; }",
+                Lang::CSharp,
+            ),
+            ("package foo\ntype Foo struct { A uint32 }", Lang::Go),
+            (
+                "layout(std140) uniform Camera { mat4 view; vec4 pos; };",
+                Lang::Glsl,
+            ),
+            (
+                "cbuffer Camera : register(b0) { float4x4 view; float3 pos; };",
+                Lang::Hlsl,
+            ),
+            (
+                "table Monster { hp: short = 100; }\nroot_type Monster;",
+                Lang::FlatBuffers,
+            ),
+            (
+                "syntax = \"proto3\";\nmessage Foo { uint32 a = 1; }",
+                Lang::Protobuf,
+            ),
+        ] {
+            assert_eq!(Lang::detect_content(input), lang, "{input}");
+        }
+    }
+    #[test]
+    fn test_extension_takes_precedence() {
+        let input = "struct Foo { a: u32, b: u32 }";
+        assert_eq!(Lang::detect(input, Some(Path::new("foo.h"))), Lang::C);
+        assert_eq!(Lang::detect(input, Some(Path::new("foo.txt"))), Lang::Rust);
+        assert_eq!(Lang::detect(input, None), Lang::Rust);
+        let input = "namespace ns { struct Foo { int x; }; }";
+        assert_eq!(Lang::detect(input, Some(Path::new("foo.h"))), Lang::Cpp);
+    }
+}
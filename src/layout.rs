@@ -0,0 +1,1032 @@
+//! Computing the memory layout of structs
+
+use {
+    crate::{
+        Abi, Array, Definition, Primitive, Registry, ReprKind, Struct, StructKind, Target, Ty,
+        Vector,
+    },
+    thiserror::Error,
+};
+
+/// The memory layout of a struct
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Layout<'s> {
+    /// Size in bytes, including trailing padding
+    pub size: u64,
+    /// Alignment in bytes
+    pub align: u64,
+    /// The layouts of the fields, in declaration order
+    pub fields: Vec<FieldLayout<'s>>,
+    /// Bytes not covered by any field, in order of their offsets
+    pub padding: Vec<Padding>,
+}
+
+impl Layout<'_> {
+    /// The sum of the sizes of the fields
+    #[must_use]
+    pub fn field_bytes(&self) -> u64 {
+        self.fields.iter().map(|field| field.size).sum()
+    }
+    /// The gaps between fields, without the trailing padding
+    pub fn holes(&self) -> impl Iterator<Item = &Padding> {
+        self.padding.iter().filter(|padding| padding.offset + padding.size < self.size)
+    }
+    /// The number of bytes after the last field
+    #[must_use]
+    pub fn trailing_padding(&self) -> u64 {
+        self.padding
+            .last()
+            .filter(|padding| padding.offset + padding.size == self.size)
+            .map_or(0, |padding| padding.size)
+    }
+    /// The number of cache lines of `cache_line` bytes the struct spans, when it starts
+    /// at the start of a cache line
+    #[must_use]
+    pub fn cache_lines(&self, cache_line: u64) -> u64 {
+        self.size.div_ceil(cache_line.max(1))
+    }
+}
+
+/// The placement of a field within a struct
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct FieldLayout<'s> {
+    /// Name of the field
+    pub name: &'s str,
+    /// Offset from the start of the struct in bytes
+    pub offset: u64,
+    /// Size in bytes
+    pub size: u64,
+    /// Alignment in bytes, after applying `packed` and explicit alignments
+    pub align: u64,
+    /// The bits of a bitfield
+    ///
+    /// The offset and size of a bitfield are those of the bytes it overlaps.
+    pub bits: Option<BitRange>,
+}
+
+/// The placement of a bitfield within a struct
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct BitRange {
+    /// Offset from the start of the struct in bits
+    pub offset: u64,
+    /// Width in bits
+    pub width: u64,
+}
+
+/// A gap between fields, or after the last one
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Padding {
+    /// Offset from the start of the struct in bytes
+    pub offset: u64,
+    /// Size in bytes
+    pub size: u64,
+}
+
+/// Layout rules of GPU buffers
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum GpuRules {
+    /// The rules of uniform buffers in GLSL, where arrays and structs are 16 byte aligned
+    Std140,
+    /// The rules of storage buffers in GLSL, where vectors are aligned to their size,
+    /// rounded up to a power of two
+    Std430,
+    /// The rules of `VK_EXT_scalar_block_layout`, where everything is aligned like its
+    /// scalar components
+    Scalar,
+}
+
+/// A field that a CPU layout and a GPU layout place differently
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct GpuMismatch<'s> {
+    /// The field in the CPU layout
+    pub cpu: FieldLayout<'s>,
+    /// The field in the GPU layout
+    pub gpu: FieldLayout<'s>,
+}
+
+/// Error that can happen while computing a layout
+#[derive(Debug, PartialEq, Eq, Clone, Error)]
+pub enum LayoutError {
+    /// A type name that is neither a primitive nor defined in the registry
+    #[error("Unknown type: {0}")]
+    UnknownType(String),
+    /// A type without a size, like `void`
+    #[error("Type has no size: {0}")]
+    Unsized(String),
+    /// A struct that contains itself by value
+    #[error("Struct contains itself: {0}")]
+    Recursive(String),
+    /// A field of an explicit layout that has no offset
+    #[error("Field has no explicit offset: {0}")]
+    MissingOffset(String),
+    /// A size that doesn't fit into 64 bits
+    #[error("Size overflow")]
+    Overflow,
+    /// An alignment or packing that isn't a power of two
+    #[error("Alignment is not a power of two: {0}")]
+    InvalidAlign(u64),
+    /// A packed Rust struct that contains a struct with a minimum alignment, which Rust
+    /// doesn't allow
+    ///
+    /// C and C++ structs cap the alignment of such members at the packing instead.
+    #[error("Packed struct {packed} contains aligned struct {aligned}")]
+    PackedContainsAligned {
+        /// Name of the packed struct
+        packed: String,
+        /// Name of the aligned struct
+        aligned: String,
+    },
+    /// A bitfield whose type isn't an integer
+    #[error("Bitfield doesn't have an integer type: {0}")]
+    BitfieldType(String),
+    /// A bitfield with more bits than its type
+    #[error("Bitfield is wider than its type: {0}")]
+    BitfieldTooWide(String),
+    /// A layout that can't be computed yet
+    #[error("Unsupported: {0}")]
+    Unsupported(&'static str),
+}
+
+impl<'s> Struct<'s> {
+    /// Compute the memory layout of this struct for 64 bit Linux, looking up named
+    /// types in `registry`
+    ///
+    /// See [`Struct::layout_for`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a type is unknown or has no size, or if a struct contains
+    /// itself.
+    pub fn layout(&self, registry: &Registry) -> Result<Layout<'s>, LayoutError> {
+        self.layout_for(registry, &Target::default())
+    }
+    /// Compute the memory layout of this struct for `target`, looking up named types in
+    /// `registry`
+    ///
+    /// Fields are laid out following C rules, in declaration order, whatever the
+    /// [`ReprKind`] is, except for explicit layouts, which use the offsets of the
+    /// fields. Vectors and matrices are laid out like arrays of their components.
+    ///
+    /// Packing, like `repr(packed(N))` or `#pragma pack(N)`, caps the alignment of the
+    /// fields, while the explicit alignment of a field, like `alignas(N)`, still
+    /// applies in packed structs. `repr(align(N))` raises the alignment of the struct.
+    ///
+    /// Bitfields are allocated following the [`Abi`] of the target: with
+    /// [`Abi::SysV`], a bitfield only moves to the next storage unit of its type if it
+    /// would straddle it, unless the struct is packed, while with [`Abi::Msvc`], a
+    /// bitfield only shares a storage unit with the previous one if their types have
+    /// the same size.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a type is unknown or has no size, if a struct contains
+    /// itself, if an alignment isn't a power of two, if a packed struct with the Rust
+    /// representation contains an aligned one, or if a bitfield doesn't have an integer
+    /// type or is too wide.
+    pub fn layout_for(
+        &self,
+        registry: &Registry,
+        target: &Target,
+    ) -> Result<Layout<'s>, LayoutError> {
+        Engine {
+            registry,
+            target,
+            stack: vec![self.name],
+            mode: Mode::C,
+        }
+        .struct_layout(self)
+    }
+    /// Compute the memory layout of this struct for `target` like rustc would, looking
+    /// up named types in `registry`
+    ///
+    /// Unlike [`Struct::layout_for`], the fields of structs with the default
+    /// [`ReprKind::Rust`] representation, including nested ones, are reordered by
+    /// decreasing alignment, which approximates what rustc does. The fields of the
+    /// layout stay in declaration order.
+    ///
+    /// # Errors
+    ///
+    /// See [`Struct::layout_for`].
+    pub fn layout_rust(
+        &self,
+        registry: &Registry,
+        target: &Target,
+    ) -> Result<Layout<'s>, LayoutError> {
+        Engine {
+            registry,
+            target,
+            stack: vec![self.name],
+            mode: Mode::Rust,
+        }
+        .struct_layout(self)
+    }
+    /// Compute the layout of this struct in a GPU buffer, following `rules` and looking
+    /// up named types in `registry`
+    ///
+    /// With [`GpuRules::Std140`] and [`GpuRules::Std430`], two and four component
+    /// vectors are aligned to their size, while three component ones are aligned like
+    /// four component ones, but only as large as their three components. The stride of
+    /// arrays is the size of their elements rounded up to their alignment, and matrices
+    /// are laid out like arrays of their column vectors, or row vectors if they are row
+    /// major. Booleans are 4 bytes wide.
+    ///
+    /// # Errors
+    ///
+    /// See [`Struct::layout_for`].
+    pub fn layout_gpu(
+        &self,
+        registry: &Registry,
+        rules: GpuRules,
+    ) -> Result<Layout<'s>, LayoutError> {
+        let mut target = Target::lp64();
+        target.set(Primitive::Bool, 4, 4);
+        Engine {
+            registry,
+            target: &target,
+            stack: vec![self.name],
+            mode: Mode::Gpu(rules),
+        }
+        .struct_layout(self)
+    }
+    /// Find the fields whose offset or size differs between the C layout of this struct
+    /// for `target` and its GPU layout following `rules`
+    ///
+    /// This checks whether a struct shared between CPU and GPU code, like a `repr(C)`
+    /// struct uploaded to a uniform buffer, has the same layout on both sides.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if either layout can't be computed, see [`Struct::layout_for`].
+    pub fn gpu_mismatches(
+        &self,
+        registry: &Registry,
+        target: &Target,
+        rules: GpuRules,
+    ) -> Result<Vec<GpuMismatch<'s>>, LayoutError> {
+        let cpu = self.layout_for(registry, target)?;
+        let gpu = self.layout_gpu(registry, rules)?;
+        Ok(cpu
+            .fields
+            .into_iter()
+            .zip(gpu.fields)
+            .filter(|(cpu, gpu)| (cpu.offset, cpu.size) != (gpu.offset, gpu.size))
+            .map(|(cpu, gpu)| GpuMismatch { cpu, gpu })
+            .collect())
+    }
+}
+
+/// The rules a layout is computed with
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    /// C rules
+    C,
+    /// C rules, with the fields of `repr(Rust)` structs reordered like rustc does
+    Rust,
+    /// GPU buffer rules
+    Gpu(GpuRules),
+}
+
+/// Computes layouts, keeping track of the named structs being computed to detect cycles
+struct Engine<'a> {
+    registry: &'a Registry<'a>,
+    target: &'a Target,
+    stack: Vec<&'a str>,
+    mode: Mode,
+}
+
+impl Engine<'_> {
+    fn struct_layout<'t>(&mut self, struct_: &Struct<'t>) -> Result<Layout<'t>, LayoutError> {
+        if matches!(struct_.repr.kind, ReprKind::BitPacked { .. }) {
+            return Err(LayoutError::Unsupported("bit-packed structs"));
+        }
+        let repr_align = self.resolve_align(struct_.repr.align);
+        for align in [struct_.repr.packed, repr_align] {
+            check_align(align)?;
+        }
+        let mut fields = Vec::with_capacity(struct_.fields.len());
+        for field in &struct_.fields {
+            let field_align = self.resolve_align(field.align);
+            check_align(field_align)?;
+            let (size, mut align) = self.ty_layout(&field.ty)?;
+            if let Some(packed) = struct_.repr.packed {
+                // C and C++ compilers cap the alignment of aligned members too, but Rust
+                // rejects them
+                if matches!(struct_.repr.kind, ReprKind::Rust | ReprKind::Transparent)
+                    && let Some(aligned) = self.aligned_struct(&field.ty)
+                {
+                    return Err(LayoutError::PackedContainsAligned {
+                        packed: struct_.name.to_owned(),
+                        aligned: aligned.to_owned(),
+                    });
+                }
+                align = align.min(packed);
+            }
+            if let Some(min) = field_align {
+                align = align.max(min);
+            }
+            if let Some(width) = field.bits {
+                self.check_bitfield(&field.ty, field.name, width, size)?;
+            }
+            fields.push(FieldLayout {
+                name: field.name,
+                offset: 0,
+                size,
+                align,
+                bits: None,
+            });
+        }
+        let mut order: Vec<_> = (0..fields.len()).collect();
+        if self.mode == Mode::Rust && struct_.repr.kind == ReprKind::Rust {
+            order.sort_by_key(|&i| std::cmp::Reverse(fields[i].align));
+        }
+        // The end of the fields placed so far, in bits
+        let mut end: u64 = 0;
+        // The end of the open storage unit of MSVC bitfields in bits, and its size
+        let mut unit = None;
+        let mut align = 1;
+        for i in order {
+            let (field, placed) = (&struct_.fields[i], &mut fields[i]);
+            let explicit =
+                || field.offset.ok_or_else(|| LayoutError::MissingOffset(field.name.to_owned()));
+            let Some(width) = field.bits.map(u64::from) else {
+                if let Some((unit_end, _)) = unit.take() {
+                    end = unit_end;
+                }
+                placed.offset = match (struct_.repr.kind, struct_.kind) {
+                    (ReprKind::Explicit, _) => explicit()?,
+                    (_, StructKind::Union) => 0,
+                    (_, StructKind::Struct) => align_to(end.div_ceil(8), placed.align)?,
+                };
+                let field_end =
+                    placed.offset.checked_add(placed.size).ok_or(LayoutError::Overflow)?;
+                end = end.max(mul(field_end, 8)?);
+                align = align.max(placed.align);
+                continue;
+            };
+            let after_bitfield = unit.is_some();
+            let bit = match (struct_.repr.kind, struct_.kind) {
+                (ReprKind::Explicit, _) => mul(explicit()?, 8)?,
+                (_, StructKind::Union) => 0,
+                (_, StructKind::Struct) => {
+                    let packed = struct_.repr.packed.is_some();
+                    self.bitfield_start(placed, width, packed, &mut end, &mut unit)?
+                }
+            };
+            // Unnamed bitfields don't affect the alignment of the struct with SysV, and
+            // zero width ones only do with MSVC if they follow a bitfield
+            let aligns = match self.target.abi {
+                Abi::SysV => !field.name.is_empty(),
+                Abi::Msvc => width > 0 || after_bitfield,
+            };
+            if aligns {
+                align = align.max(placed.align);
+            }
+            end = end.max(bit + width);
+            placed.offset = bit / 8;
+            placed.size = (bit + width).div_ceil(8) - bit / 8;
+            placed.bits = Some(BitRange { offset: bit, width });
+        }
+        if let Some((unit_end, _)) = unit {
+            end = end.max(unit_end);
+        }
+        if let Some(min) = repr_align {
+            align = align.max(min);
+        }
+        if self.mode == Mode::Gpu(GpuRules::Std140) {
+            align = align.max(16);
+        }
+        let size = align_to(end.div_ceil(8), align)?;
+        Ok(Layout {
+            size,
+            align,
+            padding: padding(&fields, size),
+            fields,
+        })
+    }
+    /// The offset in bits of a bitfield of `width` bits of a struct, whose fields so far
+    /// end at `end` bits
+    ///
+    /// `unit` is the end in bits and the size of the open storage unit of MSVC
+    /// bitfields, which is closed by updating `end` if the bitfield doesn't fit into it.
+    /// A zero width bitfield always closes it.
+    fn bitfield_start(
+        &self,
+        placed: &FieldLayout,
+        width: u64,
+        packed: bool,
+        end: &mut u64,
+        unit: &mut Option<(u64, u64)>,
+    ) -> Result<u64, LayoutError> {
+        let unit_bits = mul(placed.size, 8)?;
+        Ok(match self.target.abi {
+            Abi::SysV if width == 0 => align_to(*end, mul(placed.align, 8)?)?,
+            Abi::SysV if !packed && *end / unit_bits != (*end + width - 1) / unit_bits => {
+                align_to(*end, unit_bits)?
+            }
+            Abi::SysV => *end,
+            Abi::Msvc => match *unit {
+                Some((unit_end, size))
+                    if width > 0 && size == placed.size && *end + width <= unit_end =>
+                {
+                    *end
+                }
+                _ => {
+                    let closed = unit.take();
+                    if let Some((unit_end, _)) = closed {
+                        *end = unit_end;
+                    }
+                    // A zero width bitfield after a bitfield aligns the next field to
+                    // its type, and is ignored otherwise
+                    if width == 0 {
+                        return match closed {
+                            Some(_) => align_to(*end, mul(placed.align, 8)?),
+                            None => Ok(*end),
+                        };
+                    }
+                    let start = mul(align_to(end.div_ceil(8), placed.align)?, 8)?;
+                    *unit = Some((start + unit_bits, placed.size));
+                    start
+                }
+            },
+        })
+    }
+    /// Replace the `Some(0)` of an alignment without an argument, like GCC's
+    /// `__attribute__((aligned))`, with the largest alignment of the target
+    fn resolve_align(&self, align: Option<u64>) -> Option<u64> {
+        align.map(|align| {
+            if align == 0 {
+                self.target.max_align
+            } else {
+                align
+            }
+        })
+    }
+    /// Check that a bitfield of `width` bits has an integer type of `size` bytes that is
+    /// wide enough
+    fn check_bitfield(
+        &self,
+        ty: &Ty,
+        name: &str,
+        width: u16,
+        size: u64,
+    ) -> Result<(), LayoutError> {
+        let int = match ty {
+            Ty::Ident(ident) => match self.registry.resolve(ident) {
+                Some(Definition::Primitive(prim)) => prim == Primitive::Bool || prim.is_int(),
+                Some(Definition::Enum(_)) => true,
+                Some(Definition::Alias { ty, .. }) => {
+                    return self.check_bitfield(ty, name, width, size);
+                }
+                Some(Definition::Struct(_)) | None => false,
+            },
+            _ => false,
+        };
+        if !int {
+            return Err(LayoutError::BitfieldType(name.to_owned()));
+        }
+        if u64::from(width) > size * 8 {
+            return Err(LayoutError::BitfieldTooWide(name.to_owned()));
+        }
+        Ok(())
+    }
+    /// The name of a struct with a minimum alignment that `ty` contains by value
+    ///
+    /// Must only be called on types whose layout has been computed, which rules out
+    /// cycles.
+    fn aligned_struct<'t>(&'t self, ty: &'t Ty<'t>) -> Option<&'t str> {
+        match ty {
+            Ty::Ident(name) => match self.registry.resolve(name)? {
+                Definition::Struct(struct_) if struct_.repr.align.is_some() => Some(struct_.name),
+                Definition::Struct(struct_) => {
+                    struct_.fields.iter().find_map(|field| self.aligned_struct(&field.ty))
+                }
+                Definition::Alias { ty, .. } => self.aligned_struct(ty),
+                Definition::Primitive(_) | Definition::Enum(_) => None,
+            },
+            Ty::Inline(struct_) if struct_.repr.align.is_some() => Some(struct_.name),
+            Ty::Inline(struct_) => {
+                struct_.fields.iter().find_map(|field| self.aligned_struct(&field.ty))
+            }
+            Ty::Array(array) => self.aligned_struct(&array.ty),
+            Ty::Pointer(_) | Ty::FnPtr(_) | Ty::Vector(_) | Ty::Matrix(_) => None,
+        }
+    }
+    /// The size and alignment of a type
+    fn ty_layout(&mut self, ty: &Ty) -> Result<(u64, u64), LayoutError> {
+        match ty {
+            Ty::Ident(name) => match self.registry.resolve(name) {
+                Some(Definition::Primitive(prim)) => {
+                    self.target.scalar(prim).ok_or_else(|| LayoutError::Unsized(prim.to_string()))
+                }
+                Some(Definition::Struct(struct_)) => {
+                    if self.stack.contains(&struct_.name) {
+                        return Err(LayoutError::Recursive(struct_.name.to_owned()));
+                    }
+                    self.stack.push(struct_.name);
+                    let layout = self.struct_layout(struct_);
+                    self.stack.pop();
+                    layout.map(|layout| (layout.size, layout.align))
+                }
+                // C enums are `int`s, unless they have an underlying type
+                Some(Definition::Enum(enum_)) => match &enum_.ty {
+                    Some(ty) => self.ty_layout(ty),
+                    None => self
+                        .target
+                        .scalar(Primitive::I32)
+                        .ok_or_else(|| LayoutError::Unsized(enum_.name.to_owned())),
+                },
+                Some(Definition::Alias { name, ty }) => {
+                    if self.stack.contains(&name) {
+                        return Err(LayoutError::Recursive(name.to_owned()));
+                    }
+                    self.stack.push(name);
+                    let layout = self.ty_layout(ty);
+                    self.stack.pop();
+                    layout
+                }
+                None => Err(LayoutError::UnknownType((*name).to_owned())),
+            },
+            Ty::Array(array) => {
+                let (size, mut align) = self.ty_layout(&array.ty)?;
+                let stride = match self.mode {
+                    Mode::C | Mode::Rust | Mode::Gpu(GpuRules::Scalar) => size,
+                    Mode::Gpu(rules) => {
+                        if rules == GpuRules::Std140 {
+                            align = align.max(16);
+                        }
+                        align_to(size, align)?
+                    }
+                };
+                Ok((mul(stride, array.len)?, align))
+            }
+            Ty::Pointer(_) | Ty::FnPtr(_) => {
+                Ok((self.target.pointer_size, self.target.pointer_size))
+            }
+            Ty::Inline(struct_) => {
+                let layout = self.struct_layout(struct_)?;
+                Ok((layout.size, layout.align))
+            }
+            Ty::Vector(vector) => {
+                let (size, align) = self.ty_layout(&vector.ty)?;
+                let align = match self.mode {
+                    Mode::C | Mode::Rust | Mode::Gpu(GpuRules::Scalar) => align,
+                    Mode::Gpu(_) => mul(size, u64::from(vector.len).next_power_of_two())?,
+                };
+                Ok((mul(size, vector.len.into())?, align))
+            }
+            // An array of column vectors, or of row vectors for row major matrices
+            Ty::Matrix(matrix) => {
+                let (vector_len, count) = if matrix.row_major {
+                    (matrix.columns, matrix.rows)
+                } else {
+                    (matrix.rows, matrix.columns)
+                };
+                self.ty_layout(&Ty::Array(Array {
+                    ty: Box::new(Ty::Vector(Vector {
+                        ty: matrix.ty.clone(),
+                        len: vector_len,
+                    })),
+                    len: count.into(),
+                    flexible: false,
+                }))
+            }
+        }
+    }
+}
+
+/// Check that an alignment, if there is one, is a power of two
+fn check_align(align: Option<u64>) -> Result<(), LayoutError> {
+    match align {
+        Some(align) if !align.is_power_of_two() => Err(LayoutError::InvalidAlign(align)),
+        _ => Ok(()),
+    }
+}
+
+/// Round `offset` up to a multiple of `align`
+fn align_to(offset: u64, align: u64) -> Result<u64, LayoutError> {
+    offset.checked_next_multiple_of(align.max(1)).ok_or(LayoutError::Overflow)
+}
+
+fn mul(size: u64, len: u64) -> Result<u64, LayoutError> {
+    size.checked_mul(len).ok_or(LayoutError::Overflow)
+}
+
+/// The bytes of a struct of `size` bytes that aren't covered by any of `fields`
+fn padding(fields: &[FieldLayout], size: u64) -> Vec<Padding> {
+    let mut spans: Vec<_> =
+        fields.iter().map(|field| (field.offset, field.offset + field.size)).collect();
+    spans.sort_unstable();
+    let mut padding = Vec::new();
+    let mut covered = 0;
+    for (start, end) in spans.into_iter().chain([(size, size)]) {
+        if start > covered {
+            padding.push(Padding {
+                offset: covered,
+                size: start - covered,
+            });
+        }
+        covered = covered.max(end);
+    }
+    padding
+}
+
+#[cfg(test)]
+mod tests {
+    #![expect(clippy::unwrap_used)]
+
+    use {super::*, crate::Item, pretty_assertions::assert_eq};
+
+    fn offsets<'s>(layout: &Layout<'s>) -> Vec<(&'s str, u64, u64)> {
+        layout
+            .fields
+            .iter()
+            .map(|field| (field.name, field.offset, field.size))
+            .collect()
+    }
+
+    fn pad(offset: u64, size: u64) -> Padding {
+        Padding { offset, size }
+    }
+
+    #[test]
+    fn test_c_layout_with_padding() {
+        let s =
+            Struct::parse_c("struct Foo { char a; int b; short c; double d; char e; };").unwrap();
+        let layout = s.layout(&Registry::new()).unwrap();
+        assert_eq!(
+            offsets(&layout),
+            [
+                ("a", 0, 1),
+                ("b", 4, 4),
+                ("c", 8, 2),
+                ("d", 16, 8),
+                ("e", 24, 1)
+            ]
+        );
+        assert_eq!((layout.size, layout.align), (32, 8));
+        assert_eq!(layout.padding, [pad(1, 3), pad(10, 6), pad(25, 7)]);
+    }
+
+    #[test]
+    fn test_nested_structs_and_arrays() {
+        let registry: Registry = [
+            Struct::parse("#[repr(C)] struct Point { x: f32, y: f32, z: f32 }").unwrap(),
+            Struct::parse_c("struct Tag { char name[3]; };").unwrap(),
+        ]
+        .into_iter()
+        .collect();
+        let s = Struct::parse_c(
+            "struct Mesh { struct Tag tag; struct Point points[2]; void *data;
+                struct { short a; } inner; };",
+        )
+        .unwrap();
+        let layout = s.layout(&registry).unwrap();
+        assert_eq!(
+            offsets(&layout),
+            [
+                ("tag", 0, 3),
+                ("points", 4, 24),
+                ("data", 32, 8),
+                ("inner", 40, 2)
+            ]
+        );
+        assert_eq!((layout.size, layout.align), (48, 8));
+    }
+
+    #[test]
+    fn test_enums_and_aliases() {
+        let mut registry: Registry = Item::parse_fbs("enum Color : uint8 { Red } enum Tag { A }")
+            .unwrap()
+            .into_iter()
+            .collect();
+        registry.insert_alias("Handle", Ty::Ident("u64"));
+        registry.insert_alias(
+            "Loop",
+            Ty::Array(Array {
+                ty: Box::new(Ty::Ident("Loop")),
+                len: 2,
+                flexible: false,
+            }),
+        );
+        let s = Struct::parse("#[repr(C)] struct S { c: Color, t: Tag, h: Handle }").unwrap();
+        let layout = s.layout(&registry).unwrap();
+        assert_eq!(offsets(&layout), [("c", 0, 1), ("t", 4, 4), ("h", 8, 8)]);
+        let s = Struct::parse("struct L { l: Loop }").unwrap();
+        assert_eq!(
+            s.layout(&registry),
+            Err(LayoutError::Recursive("Loop".into()))
+        );
+    }
+
+    #[test]
+    fn test_union_and_explicit_layouts() {
+        let s = Struct::parse_c("union U { char c; int i; double d[2]; };").unwrap();
+        let layout = s.layout(&Registry::new()).unwrap();
+        assert_eq!(offsets(&layout), [("c", 0, 1), ("i", 0, 4), ("d", 0, 16)]);
+        assert_eq!(layout.size, 16);
+        assert_eq!(layout.padding, []);
+        let s = Struct::parse_csharp(
+            "[StructLayout(LayoutKind.Explicit)] struct V {
+                [FieldOffset(0)] byte Tag; [FieldOffset(8)] long Value; }",
+        )
+        .unwrap();
+        let layout = s.layout(&Registry::new()).unwrap();
+        assert_eq!(offsets(&layout), [("Tag", 0, 1), ("Value", 8, 8)]);
+        assert_eq!(layout.padding, [pad(1, 7)]);
+    }
+
+    #[test]
+    fn test_packed_and_aligned() {
+        let s = Struct::parse("#[repr(C, packed)] struct P { a: u8, b: u32 }").unwrap();
+        let layout = s.layout(&Registry::new()).unwrap();
+        assert_eq!(offsets(&layout), [("a", 0, 1), ("b", 1, 4)]);
+        assert_eq!((layout.size, layout.align), (5, 1));
+        let s = Struct::parse("#[repr(C, align(16))] struct A { a: u8 }").unwrap();
+        let layout = s.layout(&Registry::new()).unwrap();
+        assert_eq!((layout.size, layout.align), (16, 16));
+        assert_eq!(layout.padding, [pad(1, 15)]);
+    }
+
+    #[test]
+    fn test_pragma_pack_and_field_alignment() {
+        let s = Struct::parse_c(
+            "#pragma pack(push, 2)
+            struct P { char a; int b; _Alignas(8) char c; double d; };
+            #pragma pack(pop)",
+        )
+        .unwrap();
+        let layout = s.layout(&Registry::new()).unwrap();
+        assert_eq!(
+            offsets(&layout),
+            [("a", 0, 1), ("b", 2, 4), ("c", 8, 1), ("d", 10, 8)]
+        );
+        assert_eq!((layout.size, layout.align), (24, 8));
+        let s = Struct::parse("#[repr(C, packed(4))] struct P { a: u8, b: u64 }").unwrap();
+        let layout = s.layout(&Registry::new()).unwrap();
+        assert_eq!(offsets(&layout), [("a", 0, 1), ("b", 4, 8)]);
+        assert_eq!((layout.size, layout.align), (12, 4));
+    }
+
+    #[test]
+    fn test_aligned_without_argument() {
+        let s = Struct::parse_c(
+            "struct A { char a; int b __attribute__((aligned)); } __attribute__((aligned));",
+        )
+        .unwrap();
+        let registry = Registry::new();
+        // The largest alignment of the target
+        let layout = s.layout_for(&registry, &Target::lp64()).unwrap();
+        assert_eq!(offsets(&layout), [("a", 0, 1), ("b", 16, 4)]);
+        assert_eq!((layout.size, layout.align), (32, 16));
+        let layout = s.layout_for(&registry, &Target::ilp32()).unwrap();
+        assert_eq!(offsets(&layout), [("a", 0, 1), ("b", 8, 4)]);
+        assert_eq!((layout.size, layout.align), (16, 8));
+    }
+
+    #[test]
+    fn test_packed_c_struct_caps_aligned_member() {
+        let registry: Registry = [Struct::parse_c(
+            "struct __declspec(align(16)) M128A { long long low; long long high; };",
+        )
+        .unwrap()]
+        .into_iter()
+        .collect();
+        let s = Struct::parse_c(
+            "#pragma pack(push, 8)
+            struct CTX { int a; struct M128A x; };",
+        )
+        .unwrap();
+        let layout = s.layout(&registry).unwrap();
+        assert_eq!(offsets(&layout), [("a", 0, 4), ("x", 8, 16)]);
+        assert_eq!((layout.size, layout.align), (24, 8));
+        let s = Struct::parse_c("struct P { char c; struct M128A x; } __attribute__((packed));")
+            .unwrap();
+        let layout = s.layout(&registry).unwrap();
+        assert_eq!(offsets(&layout), [("c", 0, 1), ("x", 1, 16)]);
+        assert_eq!((layout.size, layout.align), (17, 1));
+    }
+
+    #[test]
+    fn test_invalid_packing() {
+        let registry: Registry = [
+            Struct::parse("#[repr(C, align(8))] struct A { a: u8 }").unwrap(),
+            Struct::parse("#[repr(C)] struct B { a: [A; 2] }").unwrap(),
+        ]
+        .into_iter()
+        .collect();
+        let s = Struct::parse("#[repr(packed)] struct P { a: u8, b: B }").unwrap();
+        assert_eq!(
+            s.layout(&registry),
+            Err(LayoutError::PackedContainsAligned {
+                packed: "P".into(),
+                aligned: "A".into(),
+            })
+        );
+        let s = Struct::parse("#[repr(packed)] struct P { a: u8, b: *const A }").unwrap();
+        assert!(s.layout(&registry).is_ok());
+        let s = Struct::parse("#[repr(C, align(12))] struct A { a: u8 }").unwrap();
+        assert_eq!(s.layout(&registry), Err(LayoutError::InvalidAlign(12)));
+    }
+
+    #[test]
+    fn test_targets() {
+        let s = Struct::parse_c("struct T { char c; long l; double d; void *p; };").unwrap();
+        let registry = Registry::new();
+        let layout = |triple| {
+            let target = Target::from_triple(triple).unwrap();
+            let layout = s.layout_for(&registry, &target).unwrap();
+            (offsets(&layout), layout.size)
+        };
+        assert_eq!(
+            layout("x86_64-unknown-linux-gnu"),
+            (
+                vec![("c", 0, 1), ("l", 8, 8), ("d", 16, 8), ("p", 24, 8)],
+                32
+            )
+        );
+        assert_eq!(
+            layout("x86_64-pc-windows-msvc"),
+            (
+                vec![("c", 0, 1), ("l", 4, 4), ("d", 8, 8), ("p", 16, 8)],
+                24
+            )
+        );
+        assert_eq!(
+            layout("armv7-unknown-linux-gnueabihf"),
+            (
+                vec![("c", 0, 1), ("l", 4, 4), ("d", 8, 8), ("p", 16, 4)],
+                24
+            )
+        );
+        assert_eq!(
+            layout("i686-unknown-linux-gnu"),
+            (
+                vec![("c", 0, 1), ("l", 4, 4), ("d", 8, 8), ("p", 16, 4)],
+                20
+            )
+        );
+    }
+
+    #[test]
+    fn test_bitfields() {
+        let s = Struct::parse_c(
+            "struct B { char a : 3; int b : 4; char c : 2; int : 0; char d; short e : 9; };",
+        )
+        .unwrap();
+        let registry = Registry::new();
+        let bits = |target| {
+            let layout = s.layout_for(&registry, &target).unwrap();
+            let bits: Vec<_> = layout
+                .fields
+                .iter()
+                .map(|field| field.bits.map_or((field.offset * 8, 0), |b| (b.offset, b.width)))
+                .collect();
+            (bits, layout.size)
+        };
+        // GCC and Clang pack bitfields of different types together, until one would
+        // straddle a storage unit of its type
+        assert_eq!(
+            bits(Target::lp64()),
+            (vec![(0, 3), (3, 4), (8, 2), (32, 0), (32, 0), (48, 9)], 8)
+        );
+        // MSVC starts a new storage unit whenever the size of the type changes, and a
+        // zero width bitfield aligns to its type
+        assert_eq!(
+            bits(Target::llp64()),
+            (
+                vec![(0, 3), (32, 4), (64, 2), (96, 0), (96, 0), (112, 9)],
+                16
+            )
+        );
+        for (input, size, align) in [
+            ("struct D { char a : 3; int : 0; };", 4, 4),
+            ("struct Z { char a; int : 0; char b; };", 2, 1),
+        ] {
+            let layout =
+                Struct::parse_c(input).unwrap().layout_for(&registry, &Target::llp64()).unwrap();
+            assert_eq!((layout.size, layout.align), (size, align), "{input}");
+        }
+        let s = Struct::parse_c("struct P { char a : 7; short b : 10; } __attribute__((packed));")
+            .unwrap();
+        let layout = s.layout(&registry).unwrap();
+        assert_eq!(
+            layout.fields[1].bits,
+            Some(BitRange {
+                offset: 7,
+                width: 10
+            })
+        );
+        assert_eq!((layout.fields[1].offset, layout.fields[1].size), (0, 3));
+        assert_eq!(layout.size, 3);
+    }
+
+    #[test]
+    fn test_gpu_layouts() {
+        let registry: Registry =
+            [Struct::parse_glsl("struct Light { vec3 dir; float i; };").unwrap()]
+                .into_iter()
+                .collect();
+        let s = Struct::parse_glsl(
+            "layout(std140) uniform Block {
+                vec3 a; float b; vec2 c; float d[2]; mat3 m; Light lights[2]; bool on;
+            };",
+        )
+        .unwrap();
+        let layout = |rules| {
+            let layout = s.layout_gpu(&registry, rules).unwrap();
+            (offsets(&layout), layout.size)
+        };
+        assert_eq!(
+            layout(GpuRules::Std140),
+            (
+                vec![
+                    ("a", 0, 12),
+                    ("b", 12, 4),
+                    ("c", 16, 8),
+                    ("d", 32, 32),
+                    ("m", 64, 48),
+                    ("lights", 112, 32),
+                    ("on", 144, 4)
+                ],
+                160
+            )
+        );
+        assert_eq!(
+            layout(GpuRules::Std430),
+            (
+                vec![
+                    ("a", 0, 12),
+                    ("b", 12, 4),
+                    ("c", 16, 8),
+                    ("d", 24, 8),
+                    ("m", 32, 48),
+                    ("lights", 80, 32),
+                    ("on", 112, 4)
+                ],
+                128
+            )
+        );
+        assert_eq!(
+            layout(GpuRules::Scalar),
+            (
+                vec![
+                    ("a", 0, 12),
+                    ("b", 12, 4),
+                    ("c", 16, 8),
+                    ("d", 24, 8),
+                    ("m", 32, 36),
+                    ("lights", 68, 32),
+                    ("on", 100, 4)
+                ],
+                104
+            )
+        );
+    }
+
+    #[test]
+    fn test_gpu_mismatches() {
+        let s = Struct::parse("#[repr(C)] struct U { a: [f32; 3], b: f32, c: [f32; 2] }").unwrap();
+        let mismatches =
+            s.gpu_mismatches(&Registry::new(), &Target::lp64(), GpuRules::Std140).unwrap();
+        let names: Vec<_> = mismatches
+            .iter()
+            .map(|m| (m.cpu.name, m.cpu.offset, m.gpu.offset, m.gpu.size))
+            .collect();
+        assert_eq!(
+            names,
+            [("a", 0, 0, 48), ("b", 12, 48, 4), ("c", 16, 64, 32)]
+        );
+        // A `vec4` followed by a scalar is laid out the same way on both sides
+        let s = Struct::parse_glsl("struct U { vec4 a; float b; };").unwrap();
+        let mismatches = s.gpu_mismatches(&Registry::new(), &Target::lp64(), GpuRules::Std430);
+        assert_eq!(mismatches, Ok(Vec::new()));
+    }
+
+    #[test]
+    fn test_layout_errors() {
+        let registry = Registry::new();
+        let s = Struct::parse("struct A { a: Missing }").unwrap();
+        assert_eq!(
+            s.layout(&registry),
+            Err(LayoutError::UnknownType("Missing".into()))
+        );
+        let registry: Registry = [
+            Struct::parse("struct A { b: B }").unwrap(),
+            Struct::parse("struct B { a: [A; 2] }").unwrap(),
+        ]
+        .into_iter()
+        .collect();
+        let s = registry.get("A").unwrap();
+        assert_eq!(s.layout(&registry), Err(LayoutError::Recursive("A".into())));
+        let s = Struct::parse_c("struct F { float f : 3; };").unwrap();
+        assert_eq!(
+            s.layout(&registry),
+            Err(LayoutError::BitfieldType("f".into()))
+        );
+        let s = Struct::parse_c("struct W { char c : 9; };").unwrap();
+        assert_eq!(
+            s.layout(&registry),
+            Err(LayoutError::BitfieldTooWide("c".into()))
+        );
+    }
+}
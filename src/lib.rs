@@ -7,14 +7,54 @@ use {
     tokenize::{TokenKind, TokenizeErrorKind, tokenize},
 };
 
+mod decode;
+mod deps;
+mod encode;
+mod lang;
+mod layout;
+mod lint;
 mod parse;
+mod path;
+mod primitive;
+mod registry;
+mod reorder;
+mod report;
+mod target;
 mod tokenize;
+mod value;
+
+pub use {
+    decode::DecodeError,
+    deps::{CycleError, DefOrder, Dep, DepGraph},
+    encode::EncodeError,
+    lang::Lang,
+    layout::{BitRange, FieldLayout, GpuMismatch, GpuRules, Layout, LayoutError, Padding},
+    lint::{Diagnostic, Lint, LintConfig, Severity},
+    parse::go::GoOptions,
+    path::{FieldPath, PathError, PathParseError, PathSegment, ResolvedPath},
+    primitive::{Primitive, PrimitiveMap},
+    registry::{Definition, Registry, Unresolved},
+    reorder::Reordered,
+    report::Report,
+    target::{Abi, Endian, Target},
+    value::Value,
+};
 
 /// A parsed struct
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct Struct<'s> {
     /// The name of the struct
+    ///
+    /// Empty for anonymous inline structs.
     pub name: &'s str,
+    /// Whether this is a struct or a union
+    pub kind: StructKind,
+    /// Layout representation, like `#[repr(C, packed)]`
+    pub repr: Repr,
+    /// The namespaces the struct is declared in, outermost first
+    pub namespace: Vec<&'s str>,
+    /// Attributes that don't affect the layout, like `#[derive(Debug)]`
+    pub attrs: Vec<Attr<'s>>,
     /// The fields of the struct
     pub fields: Vec<Field<'s>>,
 }
@@ -22,18 +62,359 @@ pub struct Struct<'s> {
 impl<'s> Struct<'s> {
     /// Parse a struct definition from a string
     ///
+    /// An `#[endian(big)]` or `#[endian(little)]` attribute sets the byte order of the
+    /// struct or of a field.
+    ///
     /// # Errors
     ///
     /// Returns an error if the text failed to parse as a struct.
     pub fn parse(input: &'s str) -> Result<Self, StructParseError> {
-        match tokenize(input) {
-            Ok(tokens) => parse::parse_struct(input, &tokens),
-            Err(e) => Err(StructParseError {
-                span: e.span,
-                kind: StructParseErrorKind::Tokenize(e.kind),
-            }),
+        parse::parse_struct(input, &tokenize(input)?)
+    }
+    /// Parse a C struct definition from a string
+    ///
+    /// Accepts both `struct Foo { ... };` and `typedef struct { ... } Foo;` forms.
+    /// GCC and MSVC packing and alignment attributes, as well as `#pragma pack`
+    /// directives preceding the struct, are turned into the [`Repr`] of the struct
+    /// and the alignment of its fields. Likewise, GCC's `scalar_storage_order`
+    /// attribute and pragma set the byte order of the struct, and the attribute that of
+    /// a field.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the text failed to parse as a C struct.
+    pub fn parse_c(input: &'s str) -> Result<Self, StructParseError> {
+        parse::c::parse_struct(input, &tokenize(input)?)
+    }
+    /// Parse a C++ class or struct definition from a string
+    ///
+    /// The first class, struct or union definition is parsed, skipping preprocessor
+    /// directives, forward declarations and other declarations before it. Namespaces it
+    /// is declared in are recorded in [`Struct::namespace`].
+    ///
+    /// Non-virtual base classes become leading fields with a `base` attribute, and all
+    /// fields carry their access specifier as an `access` attribute. Member functions and
+    /// static members are skipped; virtual functions and inheritance are not supported.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the text failed to parse as a C++ class.
+    pub fn parse_cpp(input: &'s str) -> Result<Self, StructParseError> {
+        parse::cpp::parse_struct(input, &tokenize(input)?)
+    }
+    /// Parse a Zig struct definition from a string
+    ///
+    /// The first `const Name = struct { ... };` declaration is parsed. `extern` structs use
+    /// the C representation, `packed` ones [`ReprKind::BitPacked`], and others the
+    /// unspecified default representation.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the text failed to parse as a Zig struct.
+    pub fn parse_zig(input: &'s str) -> Result<Self, StructParseError> {
+        parse::zig::parse_struct(input, &tokenize(input)?)
+    }
+    /// Parse a Go struct type declaration from a string
+    ///
+    /// The first `type Name struct { ... }` declaration is parsed, with `int` and `uint`
+    /// taken to be 64 bits wide. Struct tags become field attributes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the text failed to parse as a Go struct.
+    pub fn parse_go(input: &'s str) -> Result<Self, StructParseError> {
+        Self::parse_go_with(input, GoOptions::default())
+    }
+    /// Parse a Go struct type declaration from a string, with the given options
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the text failed to parse as a Go struct.
+    pub fn parse_go_with(input: &'s str, opts: GoOptions) -> Result<Self, StructParseError> {
+        parse::go::parse_struct(input, &tokenize(input)?, opts)
+    }
+    /// Parse a GLSL uniform block, shader storage block or struct from a string
+    ///
+    /// The first block or struct declaration is parsed. Layout qualifiers, like
+    /// `std140` in `layout(std140) uniform Block { ... };`, become attributes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the text failed to parse as a GLSL block or struct.
+    pub fn parse_glsl(input: &'s str) -> Result<Self, StructParseError> {
+        parse::shader::parse_glsl(input, &tokenize(input)?)
+    }
+    /// Parse a WGSL struct from a string
+    ///
+    /// The first struct declaration is parsed. `@align(N)` sets the alignment of a
+    /// field, and other attributes are kept.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the text failed to parse as a WGSL struct.
+    pub fn parse_wgsl(input: &'s str) -> Result<Self, StructParseError> {
+        parse::wgsl::parse_struct(input, &tokenize(input)?)
+    }
+    /// Parse an HLSL constant buffer or struct from a string
+    ///
+    /// The first `cbuffer`, `tbuffer` or struct declaration is parsed. Register
+    /// bindings and `packoffset` annotations become attributes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the text failed to parse as an HLSL constant buffer or struct.
+    pub fn parse_hlsl(input: &'s str) -> Result<Self, StructParseError> {
+        parse::shader::parse_hlsl(input, &tokenize(input)?)
+    }
+    /// Parse a C# struct from a string
+    ///
+    /// The first struct declaration is parsed. `StructLayout` sets the representation
+    /// and packing, `FieldOffset` the offsets of fields of explicit layouts, and
+    /// `MarshalAs` the unmanaged types of fields. Builtin types are given their Rust
+    /// names, so `uint` becomes `u32`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the text failed to parse as a C# struct.
+    pub fn parse_csharp(input: &'s str) -> Result<Self, StructParseError> {
+        parse::csharp::parse_struct(input, &tokenize(input)?)
+    }
+    /// Parse the first struct or table of a FlatBuffers schema
+    ///
+    /// See [`Item::parse_fbs`] for how the schema is mapped to the model.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the text failed to parse as a FlatBuffers schema, or has no
+    /// struct or table.
+    pub fn parse_fbs(input: &'s str) -> Result<Self, StructParseError> {
+        Item::first_struct(input, Item::parse_fbs(input)?)
+    }
+    /// Parse the first message of a Protobuf schema
+    ///
+    /// See [`Item::parse_proto`] for how the schema is mapped to the model.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the text failed to parse as a Protobuf schema, or has no
+    /// message.
+    pub fn parse_proto(input: &'s str) -> Result<Self, StructParseError> {
+        Item::first_struct(input, Item::parse_proto(input)?)
+    }
+    /// Parse a struct definition written in `lang`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the text failed to parse as a struct in that language.
+    pub fn parse_lang(input: &'s str, lang: Lang) -> Result<Self, StructParseError> {
+        match lang {
+            Lang::Rust => Self::parse(input),
+            Lang::C => Self::parse_c(input),
+            Lang::Cpp => Self::parse_cpp(input),
+            Lang::Zig => Self::parse_zig(input),
+            Lang::Go => Self::parse_go(input),
+            Lang::Glsl => Self::parse_glsl(input),
+            Lang::Wgsl => Self::parse_wgsl(input),
+            Lang::Hlsl => Self::parse_hlsl(input),
+            Lang::CSharp => Self::parse_csharp(input),
+            Lang::FlatBuffers => Self::parse_fbs(input),
+            Lang::Protobuf => Self::parse_proto(input),
         }
     }
+    /// Parse a struct definition, detecting the language it is written in
+    ///
+    /// The language is detected from the extension of `path` if available, and from the
+    /// content otherwise (see [`Lang::detect`]). Passing `Some` as `lang` skips
+    /// detection. Returns the language of the frontend that was used along with the
+    /// struct.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the text failed to parse as a struct in the chosen language.
+    pub fn parse_detect(
+        input: &'s str,
+        path: Option<&std::path::Path>,
+        lang: Option<Lang>,
+    ) -> Result<(Self, Lang), StructParseError> {
+        let lang = lang.unwrap_or_else(|| Lang::detect(input, path));
+        Ok((Self::parse_lang(input, lang)?, lang))
+    }
+    /// Look up an attribute by name
+    #[must_use]
+    pub fn attr(&self, name: &str) -> Option<&Attr<'s>> {
+        self.attrs.iter().find(|attr| attr.name == name)
+    }
+    /// Look up a field by name
+    ///
+    /// The fields of anonymous struct and union members are looked up as if they were
+    /// fields of this struct, like C11 does.
+    #[must_use]
+    pub fn field(&self, name: &str) -> Option<&Field<'s>> {
+        self.fields.iter().find_map(|field| match &field.ty {
+            Ty::Inline(inner) if field.is_anonymous() => inner.field(name),
+            _ => (field.name == name).then_some(field),
+        })
+    }
+    /// Look up the index path of a field by name
+    ///
+    /// Each index selects a field of the current struct, descending into anonymous
+    /// members until the named field is reached.
+    #[must_use]
+    pub fn field_path(&self, name: &str) -> Option<Vec<usize>> {
+        self.fields.iter().enumerate().find_map(|(i, field)| match &field.ty {
+            Ty::Inline(inner) if field.is_anonymous() => {
+                let mut path = inner.field_path(name)?;
+                path.insert(0, i);
+                Some(path)
+            }
+            _ => (field.name == name).then(|| vec![i]),
+        })
+    }
+}
+
+/// A parsed enum
+#[derive(Debug, PartialEq, Clone)]
+pub struct Enum<'s> {
+    /// The name of the enum
+    pub name: &'s str,
+    /// The underlying integer type, if one is given, like `ubyte` in FlatBuffers'
+    /// `enum Color : ubyte { ... }`
+    pub ty: Option<Ty<'s>>,
+    /// The namespaces the enum is declared in, outermost first
+    pub namespace: Vec<&'s str>,
+    /// Attributes of the enum, like FlatBuffers' `bit_flags`
+    pub attrs: Vec<Attr<'s>>,
+    /// The variants of the enum
+    pub variants: Vec<Variant<'s>>,
+}
+
+impl<'s> Enum<'s> {
+    /// Look up an attribute by name
+    #[must_use]
+    pub fn attr(&self, name: &str) -> Option<&Attr<'s>> {
+        self.attrs.iter().find(|attr| attr.name == name)
+    }
+    /// Look up a variant by name
+    #[must_use]
+    pub fn variant(&self, name: &str) -> Option<&Variant<'s>> {
+        self.variants.iter().find(|variant| variant.name == name)
+    }
+}
+
+/// An enum variant
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Variant<'s> {
+    /// Name of the variant
+    pub name: &'s str,
+    /// The value of the variant, with implicit values counted up from the previous one
+    pub value: i64,
+    /// Attributes of the variant
+    pub attrs: Vec<Attr<'s>>,
+}
+
+/// A top level item of a schema, which can declare multiple types
+#[derive(Debug, PartialEq, Clone)]
+pub enum Item<'s> {
+    /// A struct, or a struct-like type like a FlatBuffers table or Protobuf message
+    Struct(Struct<'s>),
+    /// An enum
+    Enum(Enum<'s>),
+}
+
+impl<'s> Item<'s> {
+    /// Parse all structs, tables, enums and unions of a FlatBuffers schema
+    ///
+    /// Structs have the C representation, since FlatBuffers lays them out like C.
+    /// Tables, which have no fixed layout, carry a `table` attribute. Field metadata
+    /// like `(id: 3, deprecated)` and default values become field attributes, and
+    /// vectors like `[ubyte]` become fields of their element type with a `vector`
+    /// attribute. Unions become enums with a `union` attribute.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the text failed to parse as a FlatBuffers schema.
+    pub fn parse_fbs(input: &'s str) -> Result<Vec<Self>, StructParseError> {
+        parse::fbs::parse_items(input, &tokenize(input)?)
+    }
+    /// Parse all messages and enums of a Protobuf schema
+    ///
+    /// Messages carry a `message` attribute. Field numbers become `number` attributes,
+    /// labels like `repeated` and `optional` become attributes of the same name, and
+    /// members of a `oneof` get a `oneof` attribute naming it. Nested messages and enums
+    /// are returned after their parent, with its name in their namespace.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the text failed to parse as a Protobuf schema.
+    pub fn parse_proto(input: &'s str) -> Result<Vec<Self>, StructParseError> {
+        parse::proto::parse_items(input, &tokenize(input)?)
+    }
+    /// The first struct among `items`
+    fn first_struct(input: &str, items: Vec<Self>) -> Result<Struct<'s>, StructParseError> {
+        items
+            .into_iter()
+            .find_map(|item| match item {
+                Self::Struct(struct_) => Some(struct_),
+                Self::Enum(_) => None,
+            })
+            .ok_or(StructParseError {
+                span: input.len()..input.len(),
+                kind: StructParseErrorKind::UnexpectedEnd,
+            })
+    }
+}
+
+/// Layout representation of a struct
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub struct Repr {
+    /// How the fields are laid out
+    pub kind: ReprKind,
+    /// Maximum alignment of the fields, like `repr(packed(N))`
+    ///
+    /// Plain `repr(packed)` is `Some(1)`.
+    pub packed: Option<u64>,
+    /// Minimum alignment of the struct, like `repr(align(N))`
+    ///
+    /// `Some(0)` stands for the largest alignment of the target, like GCC's
+    /// `__attribute__((aligned))` without an argument.
+    pub align: Option<u64>,
+    /// Byte order of the fields, like `#[endian(big)]` or GCC's `scalar_storage_order`
+    ///
+    /// If `None`, the byte order of the target is used. See [`Struct::decode`].
+    pub endian: Option<Endian>,
+}
+
+/// How the fields of a struct are laid out
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum ReprKind {
+    /// The default Rust representation, which may reorder fields
+    #[default]
+    Rust,
+    /// The C representation, with fields in declaration order
+    C,
+    /// `repr(transparent)`, with the layout of the single non-zero-sized field
+    Transparent,
+    /// Fields placed at explicit offsets, like C#'s `LayoutKind.Explicit`
+    ///
+    /// See [`Field::offset`].
+    Explicit,
+    /// Fields packed bit by bit into an integer, like Zig's `packed struct`
+    BitPacked {
+        /// The integer the fields are packed into, like the `u32` in `packed struct(u32)`
+        ///
+        /// If `None`, an integer as wide as all fields together is used.
+        backing: Option<Primitive>,
+    },
+}
+
+/// Whether an aggregate is a struct or a union
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum StructKind {
+    /// A struct, whose fields are laid out one after another
+    #[default]
+    Struct,
+    /// A union, whose fields all share the same storage
+    Union,
 }
 
 /// Error that can happen while parsing a struct
@@ -43,6 +424,14 @@ pub struct StructParseError {
     span: std::ops::Range<usize>,
     kind: StructParseErrorKind,
 }
+impl From<tokenize::TokenizeError> for StructParseError {
+    fn from(e: tokenize::TokenizeError) -> Self {
+        Self {
+            span: e.span,
+            kind: StructParseErrorKind::Tokenize(e.kind),
+        }
+    }
+}
 impl StructParseError {
     fn unexpected(tok: tokenize::Token) -> Self {
         Self {
@@ -67,31 +456,174 @@ pub enum StructParseErrorKind {
     /// Num parse error
     #[error("Num parse error: {0}")]
     NumParse(#[from] ParseIntError),
+    /// A declarator that doesn't describe a valid field type
+    #[error("Invalid declarator")]
+    InvalidDeclarator,
+    /// A language feature that is recognized, but not supported
+    #[error("Unsupported: {0}")]
+    Unsupported(&'static str),
 }
 
 /// A struct field
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct Field<'s> {
     /// Name of the struct field
+    ///
+    /// Empty for anonymous struct and union members.
     pub name: &'s str,
     /// Type of the struct field
     pub ty: Ty<'s>,
+    /// Explicitly requested minimum alignment, like C's `alignas(N)`
+    ///
+    /// `Some(0)` stands for the largest alignment of the target, like GCC's
+    /// `__attribute__((aligned))` without an argument.
+    pub align: Option<u64>,
+    /// Explicit offset of the field in bytes, like C#'s `[FieldOffset(N)]`
+    pub offset: Option<u64>,
+    /// Width of a bitfield in bits, like the `3` of C's `unsigned flags : 3;`
+    pub bits: Option<u16>,
+    /// Byte order of the field, like `#[endian(big)]`
+    ///
+    /// If `None`, the byte order of the struct is used.
+    pub endian: Option<Endian>,
+    /// Span of the field in the input it was parsed from
+    ///
+    /// This covers the name of the field, or the start of its declaration if it has no
+    /// name.
+    pub span: std::ops::Range<usize>,
+    /// Attributes that don't affect the layout, like C++ access specifiers
+    pub attrs: Vec<Attr<'s>>,
+}
+
+impl<'s> Field<'s> {
+    /// Whether this is an anonymous struct or union member, whose fields are accessed
+    /// as if they were fields of the containing struct
+    #[must_use]
+    pub fn is_anonymous(&self) -> bool {
+        self.name.is_empty()
+    }
+    /// Look up an attribute by name
+    #[must_use]
+    pub fn attr(&self, name: &str) -> Option<&Attr<'s>> {
+        self.attrs.iter().find(|attr| attr.name == name)
+    }
+}
+
+/// An attribute attached to a struct or field
+///
+/// Frontends use these for metadata that has no dedicated place in the model, like
+/// Rust's `#[derive(Debug)]` or C++'s `private:` (as `access = private`).
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Attr<'s> {
+    /// Name of the attribute
+    pub name: &'s str,
+    /// Value of the attribute, if it has one
+    ///
+    /// For Rust attributes, this is the text inside the parentheses of `#[name(...)]`,
+    /// or the literal of `#[name = "..."]` without quotes.
+    pub value: Option<&'s str>,
 }
 
 /// A type
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub enum Ty<'s> {
     /// A type marked by an identifier
     Ident(&'s str),
     /// An array type
     Array(Array<'s>),
+    /// A pointer to a type
+    Pointer(Box<Ty<'s>>),
+    /// A function pointer
+    FnPtr(FnPtr<'s>),
+    /// A struct or union defined inline, like C's `struct { int a; } s;`
+    Inline(Box<Struct<'s>>),
+    /// A shader vector, like GLSL's `vec4`
+    Vector(Vector<'s>),
+    /// A shader matrix, like GLSL's `mat4`
+    Matrix(Matrix<'s>),
+}
+
+/// Types are written in Rust syntax, like `[*const u8; 4]`, vectors and matrices in
+/// WGSL syntax, like `mat4x3<f32>`, and inline structs by their keyword and name.
+impl std::fmt::Display for Ty<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Ident(name) => f.write_str(name),
+            Self::Array(array) if array.flexible => write!(f, "[{}]", array.ty),
+            Self::Array(array) => write!(f, "[{}; {}]", array.ty, array.len),
+            Self::Pointer(ty) => write!(f, "*const {ty}"),
+            Self::FnPtr(fn_ptr) => {
+                f.write_str("fn(")?;
+                for (i, param) in fn_ptr.params.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{param}")?;
+                }
+                write!(f, ") -> {}", fn_ptr.ret)
+            }
+            Self::Inline(struct_) => {
+                let keyword = match struct_.kind {
+                    StructKind::Struct => "struct",
+                    StructKind::Union => "union",
+                };
+                match struct_.name {
+                    "" => f.write_str(keyword),
+                    name => write!(f, "{keyword} {name}"),
+                }
+            }
+            Self::Vector(vector) => write!(f, "vec{}<{}>", vector.len, vector.ty),
+            Self::Matrix(matrix) => {
+                write!(f, "mat{}x{}<{}>", matrix.columns, matrix.rows, matrix.ty)
+            }
+        }
+    }
 }
 
 /// An array
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct Array<'s> {
     /// The type of the elements
     pub ty: Box<Ty<'s>>,
     /// The length of the array
     pub len: u64,
+    /// Whether the array has no length, like C's flexible array member `char data[];`
+    /// or WGSL's runtime-sized `array<u32>`
+    ///
+    /// Like arrays of length 0, these don't take any space in the layout of a struct.
+    pub flexible: bool,
+}
+
+/// A shader vector
+#[derive(Debug, PartialEq, Clone)]
+pub struct Vector<'s> {
+    /// The type of the components, like `float` for GLSL's `vec4`
+    pub ty: Box<Ty<'s>>,
+    /// The number of components, from 2 to 4
+    pub len: u8,
+}
+
+/// A shader matrix
+///
+/// Matrices are stored as an array of column vectors, or of row vectors if they are
+/// row major.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Matrix<'s> {
+    /// The type of the components, like `float` for GLSL's `mat4`
+    pub ty: Box<Ty<'s>>,
+    /// The number of columns
+    pub columns: u8,
+    /// The number of rows
+    pub rows: u8,
+    /// Whether the matrix is stored row by row, like with GLSL's `layout(row_major)`
+    pub row_major: bool,
+}
+
+/// A function pointer
+#[derive(Debug, PartialEq, Clone)]
+pub struct FnPtr<'s> {
+    /// The types of the parameters
+    pub params: Vec<Ty<'s>>,
+    /// The return type
+    pub ret: Box<Ty<'s>>,
 }
@@ -0,0 +1,450 @@
+//! Checking struct definitions for likely mistakes
+
+use {
+    crate::{Field, Registry, Repr, Struct, Target, Ty},
+    std::{collections::HashMap, fmt, ops::Range},
+};
+
+/// A check of the lint pass
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Lint {
+    /// Fields with the same name, including fields of anonymous members
+    DuplicateField,
+    /// Type names that are neither primitives nor defined in the registry
+    UnresolvedType,
+    /// Arrays without elements, like C's `char data[0];`
+    ZeroLengthArray,
+    /// Field names that are keywords of Rust or C
+    KeywordName,
+    /// Holes between fields of at least [`LintConfig::min_hole`] bytes
+    SuspiciousPadding,
+    /// Fields of packed structs that aren't at a multiple of their natural alignment
+    MisalignedPacked,
+    /// Arrays larger than [`LintConfig::max_array_bytes`]
+    HugeArray,
+    /// Field names with uppercase letters
+    NonSnakeCase,
+}
+
+impl Lint {
+    /// All lints
+    pub const ALL: [Self; 8] = [
+        Self::DuplicateField,
+        Self::UnresolvedType,
+        Self::ZeroLengthArray,
+        Self::KeywordName,
+        Self::SuspiciousPadding,
+        Self::MisalignedPacked,
+        Self::HugeArray,
+        Self::NonSnakeCase,
+    ];
+    /// The name of the lint, like `duplicate_field`
+    #[must_use]
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::DuplicateField => "duplicate_field",
+            Self::UnresolvedType => "unresolved_type",
+            Self::ZeroLengthArray => "zero_length_array",
+            Self::KeywordName => "keyword_name",
+            Self::SuspiciousPadding => "suspicious_padding",
+            Self::MisalignedPacked => "misaligned_packed",
+            Self::HugeArray => "huge_array",
+            Self::NonSnakeCase => "non_snake_case",
+        }
+    }
+    /// Look up a lint by its name
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|lint| lint.name() == name)
+    }
+    /// The severity of the lint unless configured otherwise
+    #[must_use]
+    pub fn default_severity(self) -> Severity {
+        match self {
+            Self::DuplicateField | Self::UnresolvedType => Severity::Error,
+            Self::ZeroLengthArray
+            | Self::KeywordName
+            | Self::SuspiciousPadding
+            | Self::MisalignedPacked
+            | Self::HugeArray => Severity::Warning,
+            Self::NonSnakeCase => Severity::Allow,
+        }
+    }
+}
+
+impl fmt::Display for Lint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// How seriously a lint is taken
+#[derive(Debug, PartialEq, Eq, Clone, Copy, PartialOrd, Ord)]
+pub enum Severity {
+    /// The lint is disabled
+    Allow,
+    /// The lint is reported as a warning
+    Warning,
+    /// The lint is reported as an error
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Allow => "allow",
+            Self::Warning => "warning",
+            Self::Error => "error",
+        })
+    }
+}
+
+/// The severities of the lints, and their thresholds
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct LintConfig {
+    severities: HashMap<Lint, Severity>,
+    /// Smallest hole that [`Lint::SuspiciousPadding`] reports, in bytes
+    pub min_hole: u64,
+    /// Largest array that [`Lint::HugeArray`] doesn't report, in bytes
+    pub max_array_bytes: u64,
+}
+
+impl Default for LintConfig {
+    fn default() -> Self {
+        Self {
+            severities: HashMap::new(),
+            min_hole: 4,
+            max_array_bytes: 1 << 20,
+        }
+    }
+}
+
+impl LintConfig {
+    /// Create a configuration with the default severities and thresholds
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Set the severity of a lint
+    pub fn set(&mut self, lint: Lint, severity: Severity) {
+        self.severities.insert(lint, severity);
+    }
+    /// The severity of a lint
+    #[must_use]
+    pub fn severity(&self, lint: Lint) -> Severity {
+        self.severities.get(&lint).copied().unwrap_or_else(|| lint.default_severity())
+    }
+}
+
+/// A problem found by the lint pass
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Diagnostic<'s> {
+    /// The lint that found the problem
+    pub lint: Lint,
+    /// The configured severity of the lint
+    pub severity: Severity,
+    /// Name of the field with the problem
+    pub field: &'s str,
+    /// Span of the field in the input it was parsed from
+    pub span: Range<usize>,
+    /// Description of the problem
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}[{}] at {:?}: {}",
+            self.severity, self.lint, self.span, self.message
+        )
+    }
+}
+
+/// Keywords of Rust, which can't be used as names in generated Rust code
+const RUST_KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern",
+    "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub",
+    "ref", "return", "self", "Self", "static", "struct", "super", "trait", "true", "type",
+    "unsafe", "use", "where", "while",
+];
+
+/// Keywords Rust reserves for future use, which can't be used as names either
+const RUST_RESERVED_KEYWORDS: &[&str] = &[
+    "abstract", "become", "box", "do", "final", "gen", "macro", "override", "priv", "try",
+    "typeof", "unsized", "virtual", "yield",
+];
+
+/// Keywords of C that aren't also keywords of Rust
+const C_KEYWORDS: &[&str] = &[
+    "auto", "case", "char", "default", "double", "float", "goto", "inline", "int", "long",
+    "register", "restrict", "short", "signed", "sizeof", "switch", "typedef", "union", "unsigned",
+    "void", "volatile",
+];
+
+impl<'s> Struct<'s> {
+    /// Check this struct for likely mistakes, looking up named types in `registry`
+    ///
+    /// The checks of [`Lint`] are run with the severities of `config`, skipping those
+    /// that are allowed. The checks of the layout, like
+    /// [`Lint::SuspiciousPadding`], use the layout for `target`, and are skipped if it
+    /// can't be computed. The diagnostics are sorted by the position of their field.
+    #[must_use]
+    pub fn lint(
+        &self,
+        registry: &Registry<'s>,
+        target: &Target,
+        config: &LintConfig,
+    ) -> Vec<Diagnostic<'s>> {
+        let mut lints = Linter {
+            config,
+            diagnostics: Vec::new(),
+        };
+        for unresolved in registry.unresolved_in(self) {
+            lints.report(
+                Lint::UnresolvedType,
+                unresolved.field,
+                unresolved.span,
+                format!("unknown type `{}`", unresolved.name),
+            );
+        }
+        lints.fields(self, &mut HashMap::new());
+        lints.layout(self, registry, target);
+        let mut diagnostics = lints.diagnostics;
+        diagnostics.sort_by_key(|diag| (diag.span.start, diag.lint.name()));
+        diagnostics
+    }
+}
+
+/// Collects the diagnostics of the enabled lints
+struct Linter<'c, 's> {
+    config: &'c LintConfig,
+    diagnostics: Vec<Diagnostic<'s>>,
+}
+
+impl<'s> Linter<'_, 's> {
+    fn report(&mut self, lint: Lint, field: &'s str, span: Range<usize>, message: String) {
+        let severity = self.config.severity(lint);
+        if severity != Severity::Allow {
+            self.diagnostics.push(Diagnostic {
+                lint,
+                severity,
+                field,
+                span,
+                message,
+            });
+        }
+    }
+    /// Run the lints of single fields, descending into inline structs
+    ///
+    /// `names` holds the names of the fields seen so far in the same scope, which
+    /// anonymous members share with their parent.
+    fn fields(&mut self, struct_: &Struct<'s>, names: &mut HashMap<&'s str, Range<usize>>) {
+        for field in &struct_.fields {
+            if let Ty::Inline(inner) = &field.ty {
+                if field.is_anonymous() {
+                    self.fields(inner, names);
+                } else {
+                    self.fields(inner, &mut HashMap::new());
+                }
+            }
+            self.field(field, names);
+        }
+    }
+    fn field(&mut self, field: &Field<'s>, names: &mut HashMap<&'s str, Range<usize>>) {
+        let span = || field.span.clone();
+        if !field.name.is_empty() {
+            if let Some(first) = names.get(field.name) {
+                let message = format!("field `{}` is already declared at {first:?}", field.name);
+                self.report(Lint::DuplicateField, field.name, span(), message);
+            } else {
+                names.insert(field.name, span());
+            }
+        }
+        if [RUST_KEYWORDS, RUST_RESERVED_KEYWORDS, C_KEYWORDS]
+            .iter()
+            .any(|keywords| keywords.contains(&field.name))
+        {
+            let message = format!("field name `{}` is a keyword", field.name);
+            self.report(Lint::KeywordName, field.name, span(), message);
+        }
+        if field.name.chars().any(char::is_uppercase) {
+            let snake = to_snake_case(field.name);
+            let message = format!("field `{}` should be snake case: `{snake}`", field.name);
+            self.report(Lint::NonSnakeCase, field.name, span(), message);
+        }
+        let mut ty = &field.ty;
+        while let Ty::Array(array) = ty {
+            if array.len == 0 && !array.flexible {
+                let message = format!("field `{}` is an array without elements", field.name);
+                self.report(Lint::ZeroLengthArray, field.name, span(), message);
+                break;
+            }
+            ty = &array.ty;
+        }
+    }
+    /// Run the lints of the layout, if it can be computed
+    fn layout(&mut self, struct_: &Struct<'s>, registry: &Registry<'s>, target: &Target) {
+        let Ok(layout) = struct_.layout_for(registry, target) else {
+            return;
+        };
+        let mut holes = layout.holes().peekable();
+        for (field, placed) in struct_.fields.iter().zip(&layout.fields) {
+            while let Some(hole) = holes.next_if(|hole| hole.offset < placed.offset) {
+                if hole.size >= self.config.min_hole {
+                    let message = format!(
+                        "{} bytes of padding before field `{}`",
+                        hole.size, field.name
+                    );
+                    self.report(
+                        Lint::SuspiciousPadding,
+                        field.name,
+                        field.span.clone(),
+                        message,
+                    );
+                }
+            }
+            if matches!(field.ty, Ty::Array(_)) && placed.size > self.config.max_array_bytes {
+                let message = format!("array field `{}` takes {} bytes", field.name, placed.size);
+                self.report(Lint::HugeArray, field.name, field.span.clone(), message);
+            }
+        }
+        if struct_.repr.packed.is_none() {
+            return;
+        }
+        // The alignments of the fields without packing are their natural alignments
+        let unpacked = Struct {
+            repr: Repr {
+                packed: None,
+                ..struct_.repr
+            },
+            ..struct_.clone()
+        };
+        let Ok(natural) = unpacked.layout_for(registry, target) else {
+            return;
+        };
+        for ((field, placed), natural) in
+            struct_.fields.iter().zip(&layout.fields).zip(&natural.fields)
+        {
+            if placed.bits.is_none() && placed.offset % natural.align != 0 {
+                let message = format!(
+                    "field `{}` at offset {} is not aligned to {} bytes",
+                    field.name, placed.offset, natural.align
+                );
+                self.report(
+                    Lint::MisalignedPacked,
+                    field.name,
+                    field.span.clone(),
+                    message,
+                );
+            }
+        }
+    }
+}
+
+/// Convert a name like `FieldName` or `fieldName` to snake case
+fn to_snake_case(name: &str) -> String {
+    let mut snake = String::with_capacity(name.len() + 4);
+    let mut prev_lower = false;
+    for c in name.chars() {
+        if c.is_uppercase() {
+            if prev_lower {
+                snake.push('_');
+            }
+            snake.extend(c.to_lowercase());
+            prev_lower = false;
+        } else {
+            snake.push(c);
+            prev_lower = c.is_lowercase() || c.is_ascii_digit();
+        }
+    }
+    snake
+}
+
+#[cfg(test)]
+mod tests {
+    #![expect(clippy::unwrap_used)]
+
+    use {super::*, pretty_assertions::assert_eq};
+
+    fn lints<'s>(diagnostics: &[Diagnostic<'s>]) -> Vec<(Lint, &'s str)> {
+        diagnostics.iter().map(|diag| (diag.lint, diag.field)).collect()
+    }
+
+    #[test]
+    fn test_field_lints() {
+        let input = "struct S {
+            int type;
+            Missing m;
+            char data[0];
+            struct { int type; int x; };
+            struct { int type; } named;
+            int HeaderLen;
+            char tail[];
+        };";
+        let s = Struct::parse_c(input).unwrap();
+        let mut config = LintConfig::new();
+        config.set(Lint::NonSnakeCase, Severity::Warning);
+        config.set(Lint::SuspiciousPadding, Severity::Allow);
+        let diagnostics = s.lint(&Registry::new(), &Target::lp64(), &config);
+        assert_eq!(
+            lints(&diagnostics),
+            [
+                (Lint::KeywordName, "type"),
+                (Lint::UnresolvedType, "m"),
+                (Lint::ZeroLengthArray, "data"),
+                (Lint::DuplicateField, "type"),
+                (Lint::KeywordName, "type"),
+                (Lint::KeywordName, "type"),
+                (Lint::NonSnakeCase, "HeaderLen")
+            ]
+        );
+        assert_eq!(&input[diagnostics[1].span.clone()], "m");
+        assert_eq!(diagnostics[3].severity, Severity::Error);
+        assert_eq!(
+            diagnostics[6].to_string(),
+            "warning[non_snake_case] at 179..188: field `HeaderLen` should be snake case: \
+             `header_len`"
+        );
+    }
+
+    #[test]
+    fn test_reserved_keywords() {
+        let s = Struct::parse_c("struct S { int box; int gen; int x; };").unwrap();
+        let diagnostics = s.lint(&Registry::new(), &Target::lp64(), &LintConfig::new());
+        assert_eq!(
+            lints(&diagnostics),
+            [(Lint::KeywordName, "box"), (Lint::KeywordName, "gen")]
+        );
+    }
+    #[test]
+    fn test_layout_lints() {
+        let s = Struct::parse_c(
+            "#pragma pack(1)
+            struct P { char tag; int len; char buf[2000000]; };",
+        )
+        .unwrap();
+        let target = Target::lp64();
+        let diagnostics = s.lint(&Registry::new(), &target, &LintConfig::new());
+        assert_eq!(
+            lints(&diagnostics),
+            [(Lint::MisalignedPacked, "len"), (Lint::HugeArray, "buf")]
+        );
+        let s = Struct::parse("#[repr(C)] struct H { a: u8, b: u64, c: u16, d: u32 }").unwrap();
+        let mut config = LintConfig::new();
+        config.min_hole = 2;
+        let diagnostics = s.lint(&Registry::new(), &target, &config);
+        assert_eq!(
+            lints(&diagnostics),
+            [
+                (Lint::SuspiciousPadding, "b"),
+                (Lint::SuspiciousPadding, "d")
+            ]
+        );
+        assert_eq!(
+            diagnostics[0].message,
+            "7 bytes of padding before field `b`"
+        );
+        assert_eq!(Lint::from_name("huge_array"), Some(Lint::HugeArray));
+    }
+}
@@ -1,14 +1,27 @@
 use {
     crate::{
-        Array, Field, Struct, StructParseError, StructParseErrorKind, Ty,
+        Array, Attr, Endian, Field, Repr, ReprKind, Struct, StructKind, StructParseError,
+        StructParseErrorKind, Ty,
         tokenize::{Token, TokenKind},
     },
-    std::num::ParseIntError,
+    std::{iter::Peekable, ops::Range},
 };
 
+pub mod c;
+pub mod cpp;
+pub mod csharp;
+pub mod fbs;
+pub mod go;
+pub mod proto;
+pub mod shader;
+pub mod wgsl;
+pub mod zig;
+
 #[cfg(test)]
 mod tests;
 
+type Toks<'t> = Peekable<std::slice::Iter<'t, Token>>;
+
 trait TokIterExt {
     fn expect_tok(&mut self, tok_kind: TokenKind) -> Result<Token, StructParseError>;
     fn next_tok(&mut self) -> Result<Token, StructParseError>;
@@ -45,67 +58,346 @@ impl<'a, T: Iterator<Item = &'a Token>> TokIterExt for T {
     }
 }
 
+trait PeekExt {
+    fn peek_kind(&mut self) -> Option<TokenKind>;
+    fn eat(&mut self, tok_kind: TokenKind) -> bool;
+    fn peek_ident<'a>(&mut self, src: &'a str) -> Option<&'a str>;
+}
+
+impl PeekExt for Toks<'_> {
+    fn peek_kind(&mut self) -> Option<TokenKind> {
+        self.peek().map(|tok| tok.kind)
+    }
+
+    fn eat(&mut self, tok_kind: TokenKind) -> bool {
+        self.next_if(|tok| tok.kind == tok_kind).is_some()
+    }
+
+    fn peek_ident<'a>(&mut self, src: &'a str) -> Option<&'a str> {
+        self.peek()
+            .filter(|tok| tok.kind == TokenKind::Ident)
+            .map(|tok| &src[tok.span.clone()])
+    }
+}
+
+/// Parse an integer literal, accepting radix prefixes and C/Rust type suffixes
+fn parse_int(src: &str, tok: &Token) -> Result<u64, StructParseError> {
+    parse_int_text(&src[tok.span.clone()], tok.span.clone())
+}
+
+/// Parse the text of an integer literal, like [`parse_int`], reporting errors at `span`
+fn parse_int_text(text: &str, span: Range<usize>) -> Result<u64, StructParseError> {
+    let text = ["usize", "u8", "u16", "u32", "u64", "u128"]
+        .iter()
+        .find_map(|suffix| text.strip_suffix(suffix))
+        .unwrap_or(text)
+        .trim_end_matches(['u', 'U', 'l', 'L']);
+    let (digits, radix) = if let Some(hex) = text.strip_prefix("0x").or(text.strip_prefix("0X")) {
+        (hex, 16)
+    } else if let Some(bin) = text.strip_prefix("0b").or(text.strip_prefix("0B")) {
+        (bin, 2)
+    } else if let Some(oct) = text.strip_prefix("0o") {
+        (oct, 8)
+    } else {
+        (text, 10)
+    };
+    u64::from_str_radix(&digits.replace('_', ""), radix).map_err(|e| StructParseError {
+        span,
+        kind: e.into(),
+    })
+}
+
+/// Parse a possibly negative integer literal, like an enum value
+///
+/// The tokenizer ignores `-`, so the sign is looked up in the source text.
+fn parse_signed(src: &str, tok: &Token) -> Result<i64, StructParseError> {
+    let negative = src[..tok.span.start].trim_end().ends_with('-');
+    let n = i64::try_from(parse_int(src, tok)?).map_err(|_| StructParseError {
+        span: tok.span.clone(),
+        kind: StructParseErrorKind::Unsupported("enum values above i64::MAX"),
+    })?;
+    Ok(if negative { -n } else { n })
+}
+
+/// Skip tokens up to (but not including) the `close` token that ends the current group
+fn skip_group(toks: &mut Toks, close: TokenKind) -> Result<(), StructParseError> {
+    let mut depth = 0usize;
+    loop {
+        match toks.peek_kind() {
+            Some(kind) if kind == close && depth == 0 => return Ok(()),
+            Some(TokenKind::LParen | TokenKind::LSqBracket | TokenKind::LBrace) => depth += 1,
+            Some(TokenKind::RParen | TokenKind::RSqBracket | TokenKind::RBrace) => {
+                depth = depth.saturating_sub(1);
+            }
+            Some(_) => {}
+            None => {
+                return Err(StructParseError {
+                    span: 0..0,
+                    kind: StructParseErrorKind::UnexpectedEnd,
+                });
+            }
+        }
+        toks.next();
+    }
+}
+
+/// Parse a parenthesized integer, like the `(4)` in `align(4)`
+fn parse_paren_int(src: &str, toks: &mut Toks) -> Result<u64, StructParseError> {
+    toks.expect_tok(TokenKind::LParen)?;
+    let n = parse_int(src, &toks.expect_tok(TokenKind::NumLit)?)?;
+    toks.expect_tok(TokenKind::RParen)?;
+    Ok(n)
+}
+
+/// Parse a path like `std::array`, returning its text
+fn parse_path<'a>(src: &'a str, toks: &mut Toks) -> Result<&'a str, StructParseError> {
+    let first = toks.expect_tok(TokenKind::Ident)?;
+    let mut end = first.span.end;
+    loop {
+        let mut ahead = toks.clone();
+        match (ahead.next(), ahead.next(), ahead.next()) {
+            (Some(c1), Some(c2), Some(seg))
+                if c1.kind == TokenKind::Colon
+                    && c2.kind == TokenKind::Colon
+                    && c1.span.end == c2.span.start
+                    && seg.kind == TokenKind::Ident =>
+            {
+                end = seg.span.end;
+                *toks = ahead;
+            }
+            _ => return Ok(&src[first.span.start..end]),
+        }
+    }
+}
+
+/// Extend a name to a dotted path, like `std.os.linux.timespec`
+///
+/// The dots are ignored by the tokenizer, so this joins identifiers separated by a
+/// single dot.
+fn parse_dotted<'a>(src: &'a str, first: &Token, toks: &mut Toks) -> &'a str {
+    let mut end = first.span.end;
+    while let Some(tok) = toks
+        .next_if(|tok| tok.kind == TokenKind::Ident && src.get(end..tok.span.start) == Some("."))
+    {
+        end = tok.span.end;
+    }
+    &src[first.span.start..end]
+}
+
+/// Strip the quotes of a string literal token
+fn str_lit<'a>(src: &'a str, tok: &Token) -> &'a str {
+    let text = &src[tok.span.clone()];
+    text.get(1..text.len() - 1).unwrap_or_default()
+}
+
 pub fn parse_struct<'a>(src: &'a str, tokens: &[Token]) -> Result<Struct<'a>, StructParseError> {
-    let mut toks = tokens.iter();
-    toks.expect_tok(TokenKind::KwStruct)?;
+    let mut toks = tokens.iter().peekable();
+    let mut repr = Repr::default();
+    let mut attrs = Vec::new();
+    let mut endian = None;
+    while toks.eat(TokenKind::Hash) {
+        attrs.extend(parse_attr(src, &mut toks, Some(&mut repr), &mut endian)?);
+    }
+    repr.endian = endian;
+    let kind = parse_struct_kind(src, toks.next_tok()?)?;
     let name_tok = toks.expect_tok(TokenKind::Ident)?;
-    toks.expect_tok(TokenKind::LBrace)?;
+    let mut struct_ = parse_struct_body(src, &mut toks, &src[name_tok.span], kind)?;
+    struct_.repr = repr;
+    struct_.attrs = attrs;
+    Ok(struct_)
+}
+
+/// Parse an outer attribute after its `#`
+///
+/// If `repr` is given, `repr` attributes are applied to it, and `None` is returned.
+/// Likewise, `endian` attributes are stored in `endian`.
+fn parse_attr<'a>(
+    src: &'a str,
+    toks: &mut Toks,
+    repr: Option<&mut Repr>,
+    endian: &mut Option<Endian>,
+) -> Result<Option<Attr<'a>>, StructParseError> {
+    toks.expect_tok(TokenKind::LSqBracket)?;
+    let name = parse_path(src, toks)?;
+    if name == "endian" {
+        *endian = Some(parse_endian(src, toks)?);
+        toks.expect_tok(TokenKind::RSqBracket)?;
+        return Ok(None);
+    }
+    if let Some(repr) = repr
+        && name == "repr"
+    {
+        parse_repr(src, toks, repr)?;
+        toks.expect_tok(TokenKind::RSqBracket)?;
+        return Ok(None);
+    }
+    let value = if toks.eat(TokenKind::LParen) {
+        let start = toks.peek().map_or(src.len(), |tok| tok.span.start);
+        skip_group(toks, TokenKind::RParen)?;
+        let end = toks.next_tok()?.span.start;
+        Some(src[start.min(end)..end].trim())
+    } else if toks.eat(TokenKind::Eq) {
+        let tok = toks.next_tok()?;
+        Some(match tok.kind {
+            TokenKind::StrLit => str_lit(src, &tok),
+            _ => &src[tok.span],
+        })
+    } else {
+        None
+    };
+    toks.expect_tok(TokenKind::RSqBracket)?;
+    Ok(Some(Attr { name, value }))
+}
+
+/// Parse the byte order of an `endian` attribute, like `(big)` or `= "little"`
+fn parse_endian(src: &str, toks: &mut Toks) -> Result<Endian, StructParseError> {
+    let paren = toks.eat(TokenKind::LParen);
+    if !paren {
+        toks.expect_tok(TokenKind::Eq)?;
+    }
+    let tok = toks.next_tok()?;
+    let name = match tok.kind {
+        TokenKind::StrLit => str_lit(src, &tok),
+        _ => &src[tok.span.clone()],
+    };
+    let endian = Endian::from_name(name).ok_or_else(|| StructParseError::unexpected(tok))?;
+    if paren {
+        toks.expect_tok(TokenKind::RParen)?;
+    }
+    Ok(endian)
+}
+
+/// Parse the hints of a `repr` attribute, like `(C, packed(2))`
+fn parse_repr(src: &str, toks: &mut Toks, repr: &mut Repr) -> Result<(), StructParseError> {
+    toks.expect_tok(TokenKind::LParen)?;
+    while toks.peek_kind() != Some(TokenKind::RParen) {
+        let hint_tok = toks.expect_tok(TokenKind::Ident)?;
+        match &src[hint_tok.span.clone()] {
+            "C" => repr.kind = ReprKind::C,
+            "Rust" => repr.kind = ReprKind::Rust,
+            "transparent" => repr.kind = ReprKind::Transparent,
+            "packed" if toks.peek_kind() == Some(TokenKind::LParen) => {
+                repr.packed = Some(parse_paren_int(src, toks)?);
+            }
+            "packed" => repr.packed = Some(1),
+            "align" => repr.align = Some(parse_paren_int(src, toks)?),
+            _ => return Err(StructParseError::unexpected(hint_tok)),
+        }
+        if !toks.eat(TokenKind::Comma) {
+            break;
+        }
+    }
+    toks.expect_tok(TokenKind::RParen)?;
+    Ok(())
+}
+
+/// Whether `tok` is `struct` or `union`
+///
+/// `union` is only a contextual keyword in Rust, so it's tokenized as an identifier.
+fn is_struct_kind(src: &str, tok: &Token) -> bool {
+    tok.kind == TokenKind::KwStruct
+        || (tok.kind == TokenKind::Ident && &src[tok.span.clone()] == "union")
+}
+
+fn parse_struct_kind(src: &str, tok: Token) -> Result<StructKind, StructParseError> {
+    match tok.kind {
+        TokenKind::KwStruct => Ok(StructKind::Struct),
+        _ if is_struct_kind(src, &tok) => Ok(StructKind::Union),
+        _ => Err(StructParseError::unexpected(tok)),
+    }
+}
+
+fn parse_struct_body<'a>(
+    src: &'a str,
+    tokens: &mut Toks,
+    name: &'a str,
+    kind: StructKind,
+) -> Result<Struct<'a>, StructParseError> {
+    tokens.expect_tok(TokenKind::LBrace)?;
     let mut struct_ = Struct {
-        name: &src[name_tok.span.clone()],
+        name,
+        kind,
+        repr: Repr::default(),
+        namespace: Vec::new(),
+        attrs: Vec::new(),
         fields: Vec::new(),
     };
-    while let Some(field) = parse_field(src, &mut toks)? {
+    while let Some(field) = parse_field(src, tokens)? {
         struct_.fields.push(field);
     }
     Ok(struct_)
 }
 
-fn parse_field<'a, 'tok>(
-    src: &'a str,
-    tokens: &mut impl Iterator<Item = &'tok Token>,
-) -> Result<Option<Field<'a>>, StructParseError> {
-    let tok = tokens.next_tok()?;
-    match tok.kind {
-        TokenKind::Ident => {
-            let name = &src[tok.span.clone()];
-            tokens.expect_tok(TokenKind::Colon)?;
-            let ty = parse_ty(src, tokens)?;
-            Ok(Some(Field { name, ty }))
+fn parse_field<'a>(src: &'a str, tokens: &mut Toks) -> Result<Option<Field<'a>>, StructParseError> {
+    let mut attrs = Vec::new();
+    let mut endian = None;
+    loop {
+        let tok = tokens.next_tok()?;
+        match tok.kind {
+            TokenKind::Hash => attrs.extend(parse_attr(src, tokens, None, &mut endian)?),
+            TokenKind::Ident => {
+                let span = tok.span.clone();
+                let name = match &src[tok.span] {
+                    // Unnamed field, as in RFC 2102
+                    "_" => "",
+                    name => name,
+                };
+                tokens.expect_tok(TokenKind::Colon)?;
+                let ty = parse_ty(src, tokens)?;
+                return Ok(Some(Field {
+                    name,
+                    ty,
+                    align: None,
+                    offset: None,
+                    bits: None,
+                    endian,
+                    span,
+                    attrs,
+                }));
+            }
+            TokenKind::RBrace => return Ok(None),
+            // Comma consumed, try parsing field again
+            TokenKind::Comma => {}
+            _ => return Err(StructParseError::unexpected(tok)),
         }
-        TokenKind::RBrace => Ok(None),
-        // Comma consumed, try parsing field again
-        TokenKind::Comma => parse_field(src, tokens),
-        _ => Err(StructParseError::unexpected(tok)),
     }
 }
 
-fn parse_ty<'a, 'tok>(
-    src: &'a str,
-    tokens: &mut impl Iterator<Item = &'tok Token>,
-) -> Result<Ty<'a>, StructParseError> {
+fn parse_ty<'a>(src: &'a str, tokens: &mut Toks) -> Result<Ty<'a>, StructParseError> {
     let tok = tokens.next_tok()?;
     match tok.kind {
-        TokenKind::Ident => Ok(Ty::Ident(&src[tok.span.clone()])),
+        TokenKind::Ident
+            if !is_struct_kind(src, &tok) || tokens.peek_kind() != Some(TokenKind::LBrace) =>
+        {
+            Ok(Ty::Ident(&src[tok.span.clone()]))
+        }
         TokenKind::LSqBracket => Ok(Ty::Array(parse_array(src, tokens)?)),
+        TokenKind::KwStruct | TokenKind::Ident => {
+            let kind = parse_struct_kind(src, tok)?;
+            Ok(Ty::Inline(Box::new(parse_struct_body(
+                src, tokens, "", kind,
+            )?)))
+        }
+        TokenKind::Star => {
+            let tok = tokens.expect_tok(TokenKind::Ident)?;
+            match &src[tok.span.clone()] {
+                "const" | "mut" => Ok(Ty::Pointer(Box::new(parse_ty(src, tokens)?))),
+                _ => Err(StructParseError::unexpected(tok)),
+            }
+        }
         _ => Err(StructParseError::unexpected(tok)),
     }
 }
 
-fn parse_array<'a, 'tok>(
-    src: &'a str,
-    tokens: &mut impl Iterator<Item = &'tok Token>,
-) -> Result<Array<'a>, StructParseError> {
+fn parse_array<'a>(src: &'a str, tokens: &mut Toks) -> Result<Array<'a>, StructParseError> {
     let ty = parse_ty(src, tokens)?;
     tokens.expect_tok(TokenKind::Semi)?;
     let len_tok = tokens.expect_tok(TokenKind::NumLit)?;
-    let len: u64 =
-        src[len_tok.span.clone()].parse().map_err(|e: ParseIntError| StructParseError {
-            span: len_tok.span.clone(),
-            kind: e.into(),
-        })?;
+    let len = parse_int(src, &len_tok)?;
     tokens.expect_tok(TokenKind::RSqBracket)?;
     Ok(Array {
         ty: Box::new(ty),
         len,
+        flexible: false,
     })
 }
@@ -0,0 +1,682 @@
+//! C frontend
+
+use {
+    super::{
+        PeekExt, TokIterExt, Toks, is_struct_kind, parse_int, parse_paren_int, parse_path,
+        parse_struct_kind, skip_group, str_lit,
+    },
+    crate::{
+        Array, Endian, Field, FnPtr, Repr, ReprKind, Struct, StructKind, StructParseError,
+        StructParseErrorKind, Ty,
+        tokenize::{Token, TokenKind},
+    },
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Words that can make up a builtin C type name, like `unsigned long int`
+const BUILTIN_TYPE_WORDS: &[&str] = &[
+    "void", "char", "short", "int", "long", "float", "double", "signed", "unsigned", "_Bool",
+    "_Complex",
+];
+
+/// Type qualifiers and storage classes, which have no effect on layout
+const QUALIFIERS: &[&str] = &[
+    "const", "volatile", "restrict", "static", "register", "mutable",
+];
+
+/// Packing, alignment and byte order requested through attributes
+#[derive(Default, Clone, Copy)]
+pub(super) struct LayoutAttrs {
+    packed: bool,
+    align: Option<u64>,
+    endian: Option<Endian>,
+}
+
+impl LayoutAttrs {
+    fn add_align(&mut self, align: u64) {
+        self.align = Some(self.align.map_or(align, |prev| prev.max(align)));
+    }
+    pub(super) fn apply_to(self, struct_: &mut Struct) {
+        if self.packed {
+            struct_.repr.packed = Some(1);
+        }
+        struct_.repr.align = self.align.max(struct_.repr.align);
+        struct_.repr.endian = self.endian.or(struct_.repr.endian);
+    }
+}
+
+/// State of `#pragma pack` and `#pragma scalar_storage_order` directives
+#[derive(Default)]
+pub(super) struct PragmaState {
+    pack: Option<u64>,
+    pack_stack: Vec<Option<u64>>,
+    endian: Option<Endian>,
+}
+
+impl PragmaState {
+    /// Apply the active packing and byte order to a struct that follows the directives
+    pub(super) fn apply_to(&self, struct_: &mut Struct) {
+        if let Some(pack) = self.pack {
+            apply_pragma_pack(struct_, pack);
+        }
+        if struct_.repr.endian.is_none() {
+            struct_.repr.endian = self.endian;
+        }
+    }
+}
+
+pub fn parse_struct<'a>(src: &'a str, tokens: &[Token]) -> Result<Struct<'a>, StructParseError> {
+    let mut toks = tokens.iter().peekable();
+    let mut pragmas = PragmaState::default();
+    while let Some(hash_tok) = toks.next_if(|tok| tok.kind == TokenKind::Hash) {
+        parse_directive(src, hash_tok, &mut toks, &mut pragmas)?;
+    }
+    let typedef = toks.peek_ident(src) == Some("typedef");
+    if typedef {
+        toks.next();
+    }
+    let mut attrs = LayoutAttrs::default();
+    parse_attrs(src, &mut toks, &mut attrs)?;
+    let kw_tok = toks.next_tok()?;
+    let kind = parse_struct_kind(src, kw_tok.clone())?;
+    parse_attrs(src, &mut toks, &mut attrs)?;
+    let tag = toks.peek_ident(src);
+    if tag.is_some() {
+        toks.next();
+    }
+    let mut struct_ = parse_body(src, &mut toks, tag.unwrap_or_default(), kind)?;
+    parse_attrs(src, &mut toks, &mut attrs)?;
+    if typedef {
+        let name_tok = toks.expect_tok(TokenKind::Ident)?;
+        struct_.name = &src[name_tok.span];
+        parse_attrs(src, &mut toks, &mut attrs)?;
+    } else if tag.is_none() {
+        return Err(StructParseError::unexpected(kw_tok));
+    }
+    toks.eat(TokenKind::Semi);
+    attrs.apply_to(&mut struct_);
+    pragmas.apply_to(&mut struct_);
+    Ok(struct_)
+}
+
+/// Apply the packing of an active `#pragma pack` to a struct and its inline structs
+fn apply_pragma_pack(struct_: &mut Struct, pack: u64) {
+    struct_.repr.packed.get_or_insert(pack);
+    for field in &mut struct_.fields {
+        let mut ty = &mut field.ty;
+        while let Ty::Array(array) = ty {
+            ty = &mut array.ty;
+        }
+        if let Ty::Inline(inner) = ty {
+            apply_pragma_pack(inner, pack);
+        }
+    }
+}
+
+/// Parse a preprocessor directive after its `#`, applying `#pragma pack` and
+/// `#pragma scalar_storage_order`, and ignoring others
+pub(super) fn parse_directive(
+    src: &str,
+    hash_tok: &Token,
+    toks: &mut Toks,
+    pragmas: &mut PragmaState,
+) -> Result<(), StructParseError> {
+    // A directive ends at the end of its line
+    let mut line = Vec::new();
+    let mut prev_end = hash_tok.span.end;
+    while let Some(&tok) = toks.peek()
+        && !src[prev_end..tok.span.start].contains('\n')
+    {
+        line.push(tok);
+        prev_end = tok.span.end;
+        toks.next();
+    }
+    let words: Vec<&str> = line.iter().map(|tok| &src[tok.span.clone()]).collect();
+    if let Some(["pragma", "scalar_storage_order", order @ ..]) = words.get(..) {
+        // The tokenizer drops the `-` of `big-endian`
+        pragmas.endian = match order.join("-").as_str() {
+            "default" => None,
+            order => Some(Endian::from_name(order).ok_or_else(|| {
+                StructParseError::unexpected(line.get(2).copied().unwrap_or(hash_tok).clone())
+            })?),
+        };
+        return Ok(());
+    }
+    let Some(["pragma", "pack", "(", args @ .., ")"]) = words.get(..) else {
+        return Ok(());
+    };
+    if args.is_empty() {
+        pragmas.pack = None;
+    }
+    for (tok, arg) in line[3..].iter().zip(args) {
+        match (tok.kind, *arg) {
+            (TokenKind::Ident, "push") => pragmas.pack_stack.push(pragmas.pack),
+            (TokenKind::Ident, "pop") => pragmas.pack = pragmas.pack_stack.pop().flatten(),
+            (TokenKind::NumLit, _) => pragmas.pack = Some(parse_c_int(src, tok)?),
+            // Identifiers naming a pushed record, and commas
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Whether `word` begins an attribute understood by [`parse_attrs`]
+fn is_attr_start(word: &str) -> bool {
+    matches!(
+        word,
+        "__attribute__" | "__attribute" | "__declspec" | "_Alignas" | "alignas"
+    )
+}
+
+/// Parse GCC `__attribute__((...))`, MSVC `__declspec(...)` and `alignas(N)` attributes
+pub(super) fn parse_attrs(
+    src: &str,
+    toks: &mut Toks,
+    attrs: &mut LayoutAttrs,
+) -> Result<(), StructParseError> {
+    while let Some(word) = toks.peek_ident(src).filter(|word| is_attr_start(word)) {
+        toks.next();
+        match word {
+            "_Alignas" | "alignas" => attrs.add_align(parse_paren_int(src, toks)?),
+            "__declspec" => {
+                toks.expect_tok(TokenKind::LParen)?;
+                parse_attr_list(src, toks, attrs)?;
+                toks.expect_tok(TokenKind::RParen)?;
+            }
+            _ => {
+                toks.expect_tok(TokenKind::LParen)?;
+                toks.expect_tok(TokenKind::LParen)?;
+                parse_attr_list(src, toks, attrs)?;
+                toks.expect_tok(TokenKind::RParen)?;
+                toks.expect_tok(TokenKind::RParen)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Parse a comma separated attribute list like `packed, aligned(16)`
+fn parse_attr_list(
+    src: &str,
+    toks: &mut Toks,
+    attrs: &mut LayoutAttrs,
+) -> Result<(), StructParseError> {
+    while toks.peek_kind() != Some(TokenKind::RParen) {
+        let tok = toks.expect_tok(TokenKind::Ident)?;
+        // GCC accepts `__packed__` as a spelling of `packed`
+        match src[tok.span.clone()].trim_matches('_') {
+            "packed" => attrs.packed = true,
+            // GCC's `aligned` without an argument means the largest alignment of the
+            // target, which the layout resolves from 0
+            "aligned" if toks.peek_kind() != Some(TokenKind::LParen) => attrs.add_align(0),
+            "aligned" | "align" => attrs.add_align(parse_paren_int(src, toks)?),
+            "scalar_storage_order" => {
+                toks.expect_tok(TokenKind::LParen)?;
+                let order_tok = toks.expect_tok(TokenKind::StrLit)?;
+                let endian = Endian::from_name(str_lit(src, &order_tok))
+                    .ok_or_else(|| StructParseError::unexpected(order_tok))?;
+                attrs.endian = Some(endian);
+                toks.expect_tok(TokenKind::RParen)?;
+            }
+            _ => {
+                if toks.eat(TokenKind::LParen) {
+                    skip_group(toks, TokenKind::RParen)?;
+                    toks.expect_tok(TokenKind::RParen)?;
+                }
+            }
+        }
+        if !toks.eat(TokenKind::Comma) {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Parse the braced body of a C struct or union
+fn parse_body<'a>(
+    src: &'a str,
+    toks: &mut Toks,
+    name: &'a str,
+    kind: StructKind,
+) -> Result<Struct<'a>, StructParseError> {
+    toks.expect_tok(TokenKind::LBrace)?;
+    let mut fields = Vec::new();
+    while toks.peek_kind() != Some(TokenKind::RBrace) {
+        parse_decl(src, toks, &mut fields, false)?;
+    }
+    toks.expect_tok(TokenKind::RBrace)?;
+    Ok(Struct {
+        name,
+        kind,
+        repr: Repr {
+            kind: ReprKind::C,
+            ..Repr::default()
+        },
+        namespace: Vec::new(),
+        attrs: Vec::new(),
+        fields,
+    })
+}
+
+/// Parse a declaration like `char *argv[8], **envp;`, pushing a field for each declarator
+///
+/// With `cpp`, member function declarations and definitions are skipped, as are
+/// default member initializers. A `packed` attribute on a single field is not supported,
+/// and is ignored.
+pub(super) fn parse_decl<'a>(
+    src: &'a str,
+    toks: &mut Toks,
+    fields: &mut Vec<Field<'a>>,
+    cpp: bool,
+) -> Result<(), StructParseError> {
+    let mut attrs = LayoutAttrs::default();
+    let start = toks.peek().map(|tok| tok.span.clone()).unwrap_or_default();
+    let base = parse_specifiers(src, toks, &mut attrs)?;
+    if toks.eat(TokenKind::Semi) {
+        // Without a declarator, an untagged struct or union is a C11 anonymous member,
+        // while a tagged one, or an enum, is a nested type declaration that doesn't
+        // declare a member
+        if matches!(&base, Ty::Inline(inner) if inner.name.is_empty()) {
+            fields.push(Field {
+                name: "",
+                ty: base,
+                align: attrs.align,
+                offset: None,
+                bits: None,
+                endian: attrs.endian,
+                span: start,
+                attrs: Vec::new(),
+            });
+        }
+        return Ok(());
+    }
+    loop {
+        // Unnamed bitfields, like `int : 3;`
+        if toks.eat(TokenKind::Colon) {
+            fields.push(Field {
+                name: "",
+                ty: base.clone(),
+                align: None,
+                offset: None,
+                bits: Some(parse_bit_width(src, toks)?),
+                endian: None,
+                span: start.clone(),
+                attrs: Vec::new(),
+            });
+            if !toks.eat(TokenKind::Comma) {
+                break;
+            }
+            continue;
+        }
+        let decl = parse_declarator(src, toks)?;
+        if cpp && decl.is_function() {
+            return skip_member(toks);
+        }
+        let name_tok = decl.name.clone().ok_or_else(|| StructParseError {
+            span: decl.span.clone(),
+            kind: StructParseErrorKind::InvalidDeclarator,
+        })?;
+        let bits = if toks.eat(TokenKind::Colon) {
+            Some(parse_bit_width(src, toks)?)
+        } else {
+            None
+        };
+        let mut field_attrs = attrs;
+        parse_attrs(src, toks, &mut field_attrs)?;
+        fields.push(Field {
+            name: &src[name_tok.span.clone()],
+            ty: decl.apply(base.clone())?,
+            align: field_attrs.align,
+            offset: None,
+            bits,
+            endian: field_attrs.endian,
+            span: name_tok.span,
+            attrs: Vec::new(),
+        });
+        if cpp {
+            skip_initializer(toks)?;
+        }
+        if !toks.eat(TokenKind::Comma) {
+            break;
+        }
+    }
+    toks.expect_tok(TokenKind::Semi)?;
+    Ok(())
+}
+
+/// Parse an integer literal, where unlike in Rust a leading `0` means octal
+fn parse_c_int(src: &str, tok: &Token) -> Result<u64, StructParseError> {
+    let text = src[tok.span.clone()].trim_end_matches(['u', 'U', 'l', 'L']);
+    match text.strip_prefix('0') {
+        Some(oct) if oct.starts_with(|c: char| c.is_ascii_digit()) => u64::from_str_radix(oct, 8)
+            .map_err(|e| StructParseError {
+                span: tok.span.clone(),
+                kind: e.into(),
+            }),
+        _ => parse_int(src, tok),
+    }
+}
+
+/// Parse the width of a bitfield after its `:`
+fn parse_bit_width(src: &str, toks: &mut Toks) -> Result<u16, StructParseError> {
+    let tok = toks.expect_tok(TokenKind::NumLit)?;
+    let width = parse_c_int(src, &tok)?;
+    u16::try_from(width).map_err(|_| StructParseError {
+        span: tok.span,
+        kind: StructParseErrorKind::Unsupported("bitfields wider than 65535 bits"),
+    })
+}
+
+/// Skip the rest of a C++ member declaration, including a function body
+pub(super) fn skip_member(toks: &mut Toks) -> Result<(), StructParseError> {
+    loop {
+        match toks.next_tok()?.kind {
+            TokenKind::Semi => return Ok(()),
+            TokenKind::LBrace => {
+                skip_group(toks, TokenKind::RBrace)?;
+                toks.next();
+                toks.eat(TokenKind::Semi);
+                return Ok(());
+            }
+            TokenKind::LParen => {
+                skip_group(toks, TokenKind::RParen)?;
+                toks.next();
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Skip a C++ default member initializer, like `= 4` or `{4}`
+fn skip_initializer(toks: &mut Toks) -> Result<(), StructParseError> {
+    if toks.eat(TokenKind::LBrace) {
+        skip_group(toks, TokenKind::RBrace)?;
+        toks.next();
+    } else if toks.eat(TokenKind::Eq) {
+        while !matches!(
+            toks.peek_kind(),
+            Some(TokenKind::Comma | TokenKind::Semi) | None
+        ) {
+            match toks.next().map(|tok| tok.kind) {
+                Some(TokenKind::LParen) => skip_group(toks, TokenKind::RParen)?,
+                Some(TokenKind::LBrace) => skip_group(toks, TokenKind::RBrace)?,
+                _ => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Parse an enum specifier after `enum`, like `E`, `class E : uint8_t` or `{ A, B }`
+///
+/// Enums defined in place aren't in the registry, so they are laid out like their
+/// underlying type, or `int` if they don't have one.
+fn parse_enum_specifier<'a>(src: &'a str, toks: &mut Toks) -> Result<Ty<'a>, StructParseError> {
+    // C++'s `enum class` and `enum struct`
+    if toks.peek_ident(src) == Some("class") || toks.peek_kind() == Some(TokenKind::KwStruct) {
+        toks.next();
+    }
+    let tag = toks.next_if(|tok| tok.kind == TokenKind::Ident);
+    let underlying = if toks.eat(TokenKind::Colon) {
+        Some(parse_specifiers(src, toks, &mut LayoutAttrs::default())?)
+    } else {
+        None
+    };
+    if toks.eat(TokenKind::LBrace) {
+        skip_group(toks, TokenKind::RBrace)?;
+        toks.expect_tok(TokenKind::RBrace)?;
+        return Ok(underlying.unwrap_or(Ty::Ident("int")));
+    }
+    match (underlying, tag) {
+        (Some(ty), _) => Ok(ty),
+        (None, Some(tag)) => Ok(Ty::Ident(&src[tag.span.clone()])),
+        (None, None) => Err(match toks.next() {
+            Some(tok) => StructParseError::unexpected(tok.clone()),
+            None => StructParseError {
+                span: src.len()..src.len(),
+                kind: StructParseErrorKind::UnexpectedEnd,
+            },
+        }),
+    }
+}
+
+/// Parse the declaration specifiers, which make up the base type of a declaration
+fn parse_specifiers<'a>(
+    src: &'a str,
+    toks: &mut Toks,
+    attrs: &mut LayoutAttrs,
+) -> Result<Ty<'a>, StructParseError> {
+    let mut builtin_span: Option<std::ops::Range<usize>> = None;
+    let mut ty = None;
+    while let Some(&tok) = toks.peek() {
+        match tok.kind {
+            _ if is_struct_kind(src, tok) && ty.is_none() && builtin_span.is_none() => {
+                let kind = parse_struct_kind(src, tok.clone())?;
+                toks.next();
+                let mut struct_attrs = LayoutAttrs::default();
+                parse_attrs(src, toks, &mut struct_attrs)?;
+                let tag = toks.peek_ident(src);
+                if tag.is_some() {
+                    toks.next();
+                }
+                ty = Some(match tag {
+                    Some(tag) if toks.peek_kind() != Some(TokenKind::LBrace) => Ty::Ident(tag),
+                    _ => {
+                        let mut inner = parse_body(src, toks, tag.unwrap_or_default(), kind)?;
+                        parse_attrs(src, toks, &mut struct_attrs)?;
+                        struct_attrs.apply_to(&mut inner);
+                        Ty::Inline(Box::new(inner))
+                    }
+                });
+            }
+            TokenKind::Ident => {
+                let word = &src[tok.span.clone()];
+                if QUALIFIERS.contains(&word) {
+                    toks.next();
+                } else if is_attr_start(word) {
+                    parse_attrs(src, toks, attrs)?;
+                } else if BUILTIN_TYPE_WORDS.contains(&word) && ty.is_none() {
+                    let span = tok.span.clone();
+                    builtin_span = Some(match builtin_span {
+                        Some(prev) => prev.start..span.end,
+                        None => span,
+                    });
+                    toks.next();
+                } else if ty.is_none() && builtin_span.is_none() {
+                    if word == "enum" {
+                        toks.next();
+                        ty = Some(parse_enum_specifier(src, toks)?);
+                    } else {
+                        ty = Some(parse_type_name(src, toks)?);
+                    }
+                } else {
+                    break;
+                }
+            }
+            _ => break,
+        }
+    }
+    match (ty, builtin_span) {
+        (Some(ty), _) => Ok(ty),
+        (None, Some(span)) => Ok(Ty::Ident(&src[span])),
+        (None, None) => Err(match toks.next() {
+            Some(tok) => StructParseError::unexpected(tok.clone()),
+            None => StructParseError {
+                span: src.len()..src.len(),
+                kind: StructParseErrorKind::UnexpectedEnd,
+            },
+        }),
+    }
+}
+
+/// Parse a typedef name, which in C++ may be qualified and have template arguments
+///
+/// `std::array<T, N>` is turned into an array. Other templates are kept by name, including
+/// their arguments.
+fn parse_type_name<'a>(src: &'a str, toks: &mut Toks) -> Result<Ty<'a>, StructParseError> {
+    let start = toks.peek().map_or(src.len(), |tok| tok.span.start);
+    let path = parse_path(src, toks)?;
+    let name = path.strip_prefix("std::").unwrap_or(path);
+    if !toks.eat(TokenKind::Lt) {
+        return Ok(Ty::Ident(name));
+    }
+    if name == "array" {
+        let base = parse_specifiers(src, toks, &mut LayoutAttrs::default())?;
+        let ty = parse_declarator(src, toks)?.apply(base)?;
+        toks.expect_tok(TokenKind::Comma)?;
+        let len = parse_c_int(src, &toks.expect_tok(TokenKind::NumLit)?)?;
+        toks.expect_tok(TokenKind::Gt)?;
+        return Ok(Ty::Array(Array {
+            ty: Box::new(ty),
+            len,
+            flexible: false,
+        }));
+    }
+    let mut depth = 0usize;
+    loop {
+        let tok = toks.next_tok()?;
+        match tok.kind {
+            TokenKind::Lt => depth += 1,
+            TokenKind::Gt if depth == 0 => return Ok(Ty::Ident(&src[start..tok.span.end])),
+            TokenKind::Gt => depth -= 1,
+            _ => {}
+        }
+    }
+}
+
+/// A type derivation applied by a declarator
+#[derive(Debug)]
+enum DeclOp<'a> {
+    Pointer,
+    /// An array, without a length for flexible array members
+    Array(Option<u64>),
+    Fn(Vec<Ty<'a>>),
+}
+
+/// A parsed (possibly abstract) declarator
+pub(super) struct Declarator<'a> {
+    name: Option<Token>,
+    /// Derivations to apply to the base type, innermost first
+    ops: Vec<DeclOp<'a>>,
+    span: std::ops::Range<usize>,
+}
+
+impl<'a> Declarator<'a> {
+    /// Whether this declares a function, rather than an object
+    fn is_function(&self) -> bool {
+        matches!(self.ops.last(), Some(DeclOp::Fn(_)))
+    }
+    /// Apply the derivations of this declarator to `base`, yielding the declared type
+    fn apply(self, base: Ty<'a>) -> Result<Ty<'a>, StructParseError> {
+        let invalid = || StructParseError {
+            span: self.span.clone(),
+            kind: StructParseErrorKind::InvalidDeclarator,
+        };
+        // A function type that is still waiting for the pointer that makes it a field type
+        let mut pending_fn: Option<Vec<Ty>> = None;
+        let mut ty = base;
+        for op in self.ops {
+            ty = match (op, pending_fn.take()) {
+                (DeclOp::Pointer, Some(params)) => Ty::FnPtr(FnPtr {
+                    params,
+                    ret: Box::new(ty),
+                }),
+                (DeclOp::Pointer, None) => Ty::Pointer(Box::new(ty)),
+                (DeclOp::Array(len), None) => Ty::Array(Array {
+                    ty: Box::new(ty),
+                    len: len.unwrap_or(0),
+                    flexible: len.is_none(),
+                }),
+                (DeclOp::Fn(params), None) => {
+                    pending_fn = Some(params);
+                    ty
+                }
+                (DeclOp::Array(_) | DeclOp::Fn(_), Some(_)) => return Err(invalid()),
+            };
+        }
+        if pending_fn.is_some() {
+            return Err(invalid());
+        }
+        Ok(ty)
+    }
+}
+
+/// Parse a declarator like `*argv[8]` or `(*fp)(int)`.
+///
+/// C declarators read inside-out: postfix array and function suffixes bind tighter than
+/// prefix pointers, and parentheses group an inner declarator that applies last.
+fn parse_declarator<'a>(src: &'a str, toks: &mut Toks) -> Result<Declarator<'a>, StructParseError> {
+    let start = toks.peek().map_or(src.len(), |tok| tok.span.start);
+    let mut n_ptrs = 0;
+    // C++ references have the same layout as pointers
+    while toks.eat(TokenKind::Star) || toks.eat(TokenKind::Amp) {
+        n_ptrs += 1;
+        while toks.peek_ident(src).is_some_and(|word| QUALIFIERS.contains(&word)) {
+            toks.next();
+        }
+    }
+    let mut name = None;
+    let mut inner = None;
+    match toks.peek_kind() {
+        Some(TokenKind::Ident) => name = toks.next().cloned(),
+        Some(TokenKind::LParen) => {
+            toks.next();
+            let decl = parse_declarator(src, toks)?;
+            toks.expect_tok(TokenKind::RParen)?;
+            name.clone_from(&decl.name);
+            inner = Some(decl.ops);
+        }
+        _ => {}
+    }
+    let mut suffixes = Vec::new();
+    loop {
+        if toks.eat(TokenKind::LSqBracket) {
+            // An empty length is a flexible array member
+            let len = match toks.peek_kind() {
+                Some(TokenKind::RSqBracket) => None,
+                _ => Some(parse_c_int(src, &toks.expect_tok(TokenKind::NumLit)?)?),
+            };
+            toks.expect_tok(TokenKind::RSqBracket)?;
+            suffixes.push(DeclOp::Array(len));
+        } else if toks.eat(TokenKind::LParen) {
+            suffixes.push(DeclOp::Fn(parse_params(src, toks)?));
+        } else {
+            break;
+        }
+    }
+    let end = toks.peek().map_or(src.len(), |tok| tok.span.start);
+    let mut ops: Vec<DeclOp> = std::iter::repeat_with(|| DeclOp::Pointer).take(n_ptrs).collect();
+    ops.extend(suffixes.into_iter().rev());
+    ops.extend(inner.into_iter().flatten());
+    Ok(Declarator {
+        name,
+        ops,
+        span: start..end,
+    })
+}
+
+/// Parse a function parameter list, after the opening parenthesis
+fn parse_params<'a>(src: &'a str, toks: &mut Toks) -> Result<Vec<Ty<'a>>, StructParseError> {
+    let mut params = Vec::new();
+    if toks.eat(TokenKind::RParen) {
+        return Ok(params);
+    }
+    loop {
+        let base = parse_specifiers(src, toks, &mut LayoutAttrs::default())?;
+        let ty = parse_declarator(src, toks)?.apply(base)?;
+        // `(void)` means no parameters
+        if !(params.is_empty()
+            && ty == Ty::Ident("void")
+            && toks.peek_kind() == Some(TokenKind::RParen))
+        {
+            params.push(ty);
+        }
+        if !toks.eat(TokenKind::Comma) {
+            break;
+        }
+    }
+    toks.expect_tok(TokenKind::RParen)?;
+    Ok(params)
+}
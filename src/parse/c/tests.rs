@@ -0,0 +1,302 @@
+#![expect(clippy::unwrap_used)]
+
+use {
+    crate::{Array, Endian, Field, FnPtr, Repr, ReprKind, Struct, StructKind, Ty},
+    pretty_assertions::assert_eq,
+};
+
+fn array(ty: Ty<'static>, len: u64) -> Ty<'static> {
+    Ty::Array(Array {
+        ty: Box::new(ty),
+        len,
+        flexible: false,
+    })
+}
+
+#[test]
+fn parse_c_struct_simple() {
+    assert_eq!(
+        Struct::parse_c("struct Foo { unsigned int a; uint8_t b, c; };").unwrap(),
+        Struct {
+            name: "Foo",
+            kind: StructKind::Struct,
+            repr: Repr {
+                kind: ReprKind::C,
+                ..Repr::default()
+            },
+            namespace: vec![],
+            attrs: vec![],
+            fields: vec![
+                Field {
+                    name: "a",
+                    ty: Ty::Ident("unsigned int"),
+                    align: None,
+                    offset: None,
+                    bits: None,
+                    endian: None,
+                    span: 26..27,
+                    attrs: vec![],
+                },
+                Field {
+                    name: "b",
+                    ty: Ty::Ident("uint8_t"),
+                    align: None,
+                    offset: None,
+                    bits: None,
+                    endian: None,
+                    span: 37..38,
+                    attrs: vec![],
+                },
+                Field {
+                    name: "c",
+                    ty: Ty::Ident("uint8_t"),
+                    align: None,
+                    offset: None,
+                    bits: None,
+                    endian: None,
+                    span: 40..41,
+                    attrs: vec![],
+                },
+            ]
+        }
+    );
+}
+
+#[test]
+fn parse_c_typedef_struct() {
+    let s = Struct::parse_c(
+        "typedef struct {
+            /* The magic */
+            const char magic[4];
+            struct Node *next;
+        } Header;",
+    )
+    .unwrap();
+    assert_eq!(s.name, "Header");
+    assert_eq!(s.fields[0].ty, array(Ty::Ident("char"), 4));
+    assert_eq!(s.fields[1].ty, Ty::Pointer(Box::new(Ty::Ident("Node"))));
+}
+
+#[test]
+fn parse_c_multi_dimensional_array() {
+    let s = Struct::parse_c("struct M { int m[3][4]; };").unwrap();
+    // Same nesting as Rust's `[[i32; 4]; 3]`
+    assert_eq!(s.fields[0].ty, array(array(Ty::Ident("int"), 4), 3));
+}
+
+#[test]
+fn parse_c_octal_array_len() {
+    let s = Struct::parse_c("struct A { int a[010]; char b[0]; char c[0x10]; };").unwrap();
+    assert_eq!(s.fields[0].ty, array(Ty::Ident("int"), 8));
+    assert_eq!(s.fields[1].ty, array(Ty::Ident("char"), 0));
+    assert_eq!(s.fields[2].ty, array(Ty::Ident("char"), 16));
+    assert!(Struct::parse_c("struct A { int a[08]; };").is_err());
+}
+
+#[test]
+fn parse_c_flexible_array_member() {
+    let s = Struct::parse_c("struct Msg { int len; char old[0]; char data[]; };").unwrap();
+    let Ty::Array(old) = &s.fields[1].ty else {
+        panic!("not an array: {:?}", s.fields[1].ty);
+    };
+    assert_eq!((old.len, old.flexible), (0, false));
+    let Ty::Array(data) = &s.fields[2].ty else {
+        panic!("not an array: {:?}", s.fields[2].ty);
+    };
+    assert_eq!((data.len, data.flexible), (0, true));
+    assert_eq!(s.fields[2].ty.to_string(), "[char]");
+}
+
+#[test]
+fn parse_c_array_of_pointers() {
+    let s = Struct::parse_c("struct Args { char *argv[8]; char (*grid)[0x10]; };").unwrap();
+    assert_eq!(
+        s.fields[0].ty,
+        array(Ty::Pointer(Box::new(Ty::Ident("char"))), 8)
+    );
+    assert_eq!(
+        s.fields[1].ty,
+        Ty::Pointer(Box::new(array(Ty::Ident("char"), 16)))
+    );
+}
+
+#[test]
+fn parse_c_fn_pointer() {
+    let s = Struct::parse_c("struct Vtable { int (*fp)(int); void (*free)(void *, size_t); };")
+        .unwrap();
+    assert_eq!(
+        s.fields[0].ty,
+        Ty::FnPtr(FnPtr {
+            params: vec![Ty::Ident("int")],
+            ret: Box::new(Ty::Ident("int")),
+        })
+    );
+    assert_eq!(
+        s.fields[1].ty,
+        Ty::FnPtr(FnPtr {
+            params: vec![
+                Ty::Pointer(Box::new(Ty::Ident("void"))),
+                Ty::Ident("size_t")
+            ],
+            ret: Box::new(Ty::Ident("void")),
+        })
+    );
+}
+
+#[test]
+fn parse_c_function_field_is_invalid() {
+    assert!(Struct::parse_c("struct Bad { int f(int); };").is_err());
+}
+
+#[test]
+fn parse_c_anonymous_members() {
+    let s = Struct::parse_c(
+        "struct Outer {
+            struct {
+                union { int a; float b; };
+                int c;
+            };
+            int d;
+        };",
+    )
+    .unwrap();
+    assert_eq!(s.fields.len(), 2);
+    assert!(s.fields[0].is_anonymous());
+    let Ty::Inline(inner) = &s.fields[0].ty else {
+        panic!("expected inline struct");
+    };
+    let Ty::Inline(union_) = &inner.fields[0].ty else {
+        panic!("expected inline union");
+    };
+    assert_eq!(union_.kind, StructKind::Union);
+    assert_eq!(s.field("b").unwrap().ty, Ty::Ident("float"));
+    assert_eq!(s.field_path("b").unwrap(), [0, 0, 1]);
+    assert_eq!(s.field_path("c").unwrap(), [0, 1]);
+    assert_eq!(s.field_path("d").unwrap(), [1]);
+    assert!(s.field("e").is_none());
+}
+
+#[test]
+fn parse_c_nested_named_struct() {
+    let s = Struct::parse_c(
+        "struct Outer {
+            struct Inner { int x; } inner, pair[2];
+            struct Decl { int y; };
+            struct Inner *next;
+        };",
+    )
+    .unwrap();
+    let names: Vec<_> = s.fields.iter().map(|f| f.name).collect();
+    assert_eq!(names, ["inner", "pair", "next"]);
+    let Ty::Inline(inner) = &s.fields[0].ty else {
+        panic!("expected inline struct");
+    };
+    assert_eq!(inner.name, "Inner");
+    // Members of named nested structs are not flattened
+    assert!(s.field("x").is_none());
+    assert_eq!(s.fields[2].ty, Ty::Pointer(Box::new(Ty::Ident("Inner"))));
+}
+
+#[test]
+fn parse_c_gcc_attributes() {
+    let s = Struct::parse_c(
+        "struct __attribute__((packed)) Foo {
+            char c;
+            int x __attribute__((aligned(16)));
+            _Alignas(8) short s;
+        } __attribute__((__aligned__(32)));",
+    )
+    .unwrap();
+    assert_eq!(
+        s.repr,
+        Repr {
+            kind: ReprKind::C,
+            packed: Some(1),
+            align: Some(32),
+            endian: None,
+        }
+    );
+    let aligns: Vec<_> = s.fields.iter().map(|f| f.align).collect();
+    assert_eq!(aligns, [None, Some(16), Some(8)]);
+}
+
+#[test]
+fn parse_c_msvc_attributes() {
+    let s = Struct::parse_c("typedef __declspec(align(16)) struct { alignas(4) char c; } Vec4;")
+        .unwrap();
+    assert_eq!(s.name, "Vec4");
+    assert_eq!(s.repr.align, Some(16));
+    assert_eq!(s.fields[0].align, Some(4));
+}
+
+#[test]
+fn parse_c_pragma_pack() {
+    let s = Struct::parse_c(
+        "#include <stdint.h>
+        #pragma pack(push, 1)
+        #pragma pack(push, 4)
+        #pragma pack(pop)
+        struct Header {
+            uint8_t tag;
+            struct { uint32_t len; } inner;
+        };",
+    )
+    .unwrap();
+    assert_eq!(s.repr.packed, Some(1));
+    let Ty::Inline(inner) = &s.fields[1].ty else {
+        panic!("expected inline struct");
+    };
+    assert_eq!(inner.repr.packed, Some(1));
+    let s = Struct::parse_c(
+        "#pragma pack(push, 2)
+        #pragma pack()
+        struct Unpacked { int a; };",
+    )
+    .unwrap();
+    assert_eq!(s.repr.packed, None);
+}
+
+#[test]
+fn parse_c_scalar_storage_order() {
+    let s = Struct::parse_c(
+        "#pragma scalar_storage_order big-endian
+        struct Header {
+            uint32_t magic;
+            uint16_t len __attribute__((scalar_storage_order(\"little-endian\")));
+        };",
+    )
+    .unwrap();
+    assert_eq!(s.repr.endian, Some(Endian::Big));
+    assert_eq!(s.fields[0].endian, None);
+    assert_eq!(s.fields[1].endian, Some(Endian::Little));
+    let s = Struct::parse_c(
+        "#pragma scalar_storage_order big-endian
+        #pragma scalar_storage_order default
+        struct __attribute__((scalar_storage_order(\"little-endian\"))) S { int a; };",
+    )
+    .unwrap();
+    assert_eq!(s.repr.endian, Some(Endian::Little));
+    assert!(
+        Struct::parse_c("#pragma scalar_storage_order sideways\nstruct S { int a; };").is_err()
+    );
+}
+
+#[test]
+fn parse_c_bitfields() {
+    let s = Struct::parse_c(
+        "struct Flags { unsigned int a : 3, b : 5; int : 0; unsigned char c : 1; long d; };",
+    )
+    .unwrap();
+    let bits: Vec<_> = s.fields.iter().map(|f| (f.name, f.bits)).collect();
+    assert_eq!(
+        bits,
+        [
+            ("a", Some(3)),
+            ("b", Some(5)),
+            ("", Some(0)),
+            ("c", Some(1)),
+            ("d", None)
+        ]
+    );
+    assert_eq!(s.fields[2].ty, Ty::Ident("int"));
+}
@@ -0,0 +1,252 @@
+//! C++ frontend, for the subset of C++ that describes plain aggregates
+
+use {
+    super::{
+        PeekExt, TokIterExt, Toks,
+        c::{LayoutAttrs, PragmaState, parse_attrs, parse_decl, parse_directive, skip_member},
+        parse_path,
+    },
+    crate::{
+        Attr, Field, Repr, ReprKind, Struct, StructKind, StructParseError, StructParseErrorKind,
+        Ty,
+        tokenize::{Token, TokenKind},
+    },
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Members that don't take up storage in an object, and are skipped
+const NON_DATA_MEMBERS: &[&str] = &[
+    "static",
+    "using",
+    "typedef",
+    "friend",
+    "template",
+    "static_assert",
+    "explicit",
+    "inline",
+    "constexpr",
+    "operator",
+];
+
+pub fn parse_struct<'a>(src: &'a str, tokens: &[Token]) -> Result<Struct<'a>, StructParseError> {
+    let mut toks = tokens.iter().peekable();
+    let mut pragmas = PragmaState::default();
+    let mut namespace = Vec::new();
+    // The length of `namespace` outside of each open brace
+    let mut scopes = Vec::new();
+    loop {
+        if let Some(hash_tok) = toks.next_if(|tok| tok.kind == TokenKind::Hash) {
+            parse_directive(src, hash_tok, &mut toks, &mut pragmas)?;
+            continue;
+        }
+        let tok = toks.peek().copied().cloned();
+        let word = toks.peek_ident(src);
+        match (tok.map(|tok| tok.kind), word) {
+            (Some(TokenKind::Ident), Some("namespace")) => {
+                toks.next();
+                let path = parse_path(src, &mut toks)?;
+                toks.expect_tok(TokenKind::LBrace)?;
+                scopes.push(namespace.len());
+                namespace.extend(path.split("::").map(str::trim));
+            }
+            (Some(TokenKind::Ident), Some("extern"))
+                if toks.clone().nth(1).map(|tok| tok.kind) == Some(TokenKind::StrLit) =>
+            {
+                toks.next();
+                toks.next();
+                if toks.eat(TokenKind::LBrace) {
+                    scopes.push(namespace.len());
+                }
+            }
+            (Some(TokenKind::KwStruct), _) | (Some(TokenKind::Ident), Some("class" | "union")) => {
+                if let Some(mut struct_) = parse_class(src, &mut toks)? {
+                    struct_.namespace = namespace;
+                    pragmas.apply_to(&mut struct_);
+                    return Ok(struct_);
+                }
+            }
+            // The end of a namespace or `extern "C"` block
+            (Some(TokenKind::RBrace), _) => {
+                toks.next();
+                namespace.truncate(scopes.pop().unwrap_or_default());
+            }
+            // Other declarations, like `using` or forward declarations of other types
+            (Some(_), _) => skip_member(&mut toks)?,
+            (None, _) => {
+                return Err(StructParseError {
+                    span: src.len()..src.len(),
+                    kind: StructParseErrorKind::UnexpectedEnd,
+                });
+            }
+        }
+    }
+}
+
+/// Parse a class, struct or union definition
+///
+/// Returns `None` for forward declarations.
+fn parse_class<'a>(src: &'a str, toks: &mut Toks) -> Result<Option<Struct<'a>>, StructParseError> {
+    let kw_tok = toks.next_tok()?;
+    let (kind, default_access) = match &src[kw_tok.span.clone()] {
+        "struct" => (StructKind::Struct, "public"),
+        "union" => (StructKind::Union, "public"),
+        _ => (StructKind::Struct, "private"),
+    };
+    let mut attrs = LayoutAttrs::default();
+    parse_attrs(src, toks, &mut attrs)?;
+    let name_tok = toks.expect_tok(TokenKind::Ident)?;
+    let name = &src[name_tok.span.clone()];
+    if toks.peek_ident(src) == Some("final") {
+        toks.next();
+    }
+    if toks.eat(TokenKind::Semi) {
+        return Ok(None);
+    }
+    let mut fields = Vec::new();
+    if toks.eat(TokenKind::Colon) {
+        parse_bases(src, toks, default_access, &mut fields)?;
+    }
+    toks.expect_tok(TokenKind::LBrace)?;
+    parse_members(src, toks, name, default_access, &mut fields)?;
+    parse_attrs(src, toks, &mut attrs)?;
+    toks.eat(TokenKind::Semi);
+    let mut struct_ = Struct {
+        name,
+        kind,
+        repr: Repr {
+            kind: ReprKind::C,
+            ..Repr::default()
+        },
+        namespace: Vec::new(),
+        attrs: Vec::new(),
+        fields,
+    };
+    attrs.apply_to(&mut struct_);
+    Ok(Some(struct_))
+}
+
+/// Parse a base class list, after the colon
+///
+/// Non-virtual base classes are laid out as leading subobjects, so they become fields
+/// named after the base class, with a `base` attribute.
+fn parse_bases<'a>(
+    src: &'a str,
+    toks: &mut Toks,
+    default_access: &'static str,
+    fields: &mut Vec<Field<'a>>,
+) -> Result<(), StructParseError> {
+    loop {
+        let mut access = default_access;
+        while let Some(word) = toks.peek_ident(src) {
+            match word {
+                "public" | "protected" | "private" => access = word,
+                "virtual" => {
+                    return Err(StructParseError {
+                        span: toks.next_tok()?.span,
+                        kind: StructParseErrorKind::Unsupported("virtual inheritance"),
+                    });
+                }
+                _ => break,
+            }
+            toks.next();
+        }
+        let start = toks.peek().map_or(src.len(), |tok| tok.span.start);
+        let path = parse_path(src, toks)?;
+        if toks.eat(TokenKind::Lt) {
+            return Err(StructParseError {
+                span: toks.next_tok()?.span,
+                kind: StructParseErrorKind::Unsupported("template base classes"),
+            });
+        }
+        fields.push(Field {
+            name: path.rsplit("::").next().unwrap_or(path).trim(),
+            ty: Ty::Ident(path),
+            align: None,
+            offset: None,
+            bits: None,
+            endian: None,
+            span: start..start + path.len(),
+            attrs: vec![
+                Attr {
+                    name: "base",
+                    value: None,
+                },
+                Attr {
+                    name: "access",
+                    value: Some(access),
+                },
+            ],
+        });
+        if !toks.eat(TokenKind::Comma) {
+            return Ok(());
+        }
+    }
+}
+
+/// Parse the members of a class body, after the opening brace
+///
+/// Data members become fields with an `access` attribute. Member functions, static
+/// members and nested type declarations are skipped.
+fn parse_members<'a>(
+    src: &'a str,
+    toks: &mut Toks,
+    class_name: &str,
+    default_access: &'a str,
+    fields: &mut Vec<Field<'a>>,
+) -> Result<(), StructParseError> {
+    let mut access = default_access;
+    loop {
+        let tok = toks.peek().copied().cloned().ok_or(StructParseError {
+            span: src.len()..src.len(),
+            kind: StructParseErrorKind::UnexpectedEnd,
+        })?;
+        let word = toks.peek_ident(src);
+        match tok.kind {
+            TokenKind::RBrace => {
+                toks.next();
+                return Ok(());
+            }
+            TokenKind::Semi => {
+                toks.next();
+            }
+            TokenKind::Hash => {
+                toks.next();
+                parse_directive(src, &tok, toks, &mut PragmaState::default())?;
+            }
+            TokenKind::Ident
+                if matches!(word, Some("public" | "protected" | "private"))
+                    && toks.clone().nth(1).map(|tok| tok.kind) == Some(TokenKind::Colon) =>
+            {
+                access = word.unwrap_or(access);
+                toks.next();
+                toks.next();
+            }
+            TokenKind::Ident if word == Some("virtual") => {
+                return Err(StructParseError {
+                    span: tok.span,
+                    kind: StructParseErrorKind::Unsupported("virtual functions"),
+                });
+            }
+            TokenKind::Ident
+                if word.is_some_and(|word| NON_DATA_MEMBERS.contains(&word))
+                    // Constructors
+                    || word == Some(class_name)
+                        && toks.clone().nth(1).map(|tok| tok.kind) == Some(TokenKind::LParen) =>
+            {
+                skip_member(toks)?;
+            }
+            _ => {
+                let first_new = fields.len();
+                parse_decl(src, toks, fields, true)?;
+                for field in &mut fields[first_new..] {
+                    field.attrs.push(Attr {
+                        name: "access",
+                        value: Some(access),
+                    });
+                }
+            }
+        }
+    }
+}
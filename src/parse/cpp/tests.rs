@@ -0,0 +1,113 @@
+#![expect(clippy::unwrap_used)]
+
+use {
+    crate::{Array, Attr, Struct, Ty},
+    pretty_assertions::assert_eq,
+};
+
+fn access<'s>(s: &'s Struct, field: &str) -> Option<&'s str> {
+    s.field(field)?.attr("access")?.value
+}
+
+#[test]
+fn parse_cpp_class_with_access_specifiers() {
+    let s = Struct::parse_cpp(
+        "class Foo {
+            uint32_t implicit;
+        public:
+            Foo() : implicit(0) {}
+            ~Foo();
+            uint32_t x = 4;
+            uint32_t get_x() const { return x; }
+            static int instances;
+        private:
+            std::array<uint8_t, 4> y{};
+            int& ref;
+        };",
+    )
+    .unwrap();
+    assert_eq!(s.name, "Foo");
+    let names: Vec<_> = s.fields.iter().map(|f| f.name).collect();
+    assert_eq!(names, ["implicit", "x", "y", "ref"]);
+    assert_eq!(access(&s, "implicit"), Some("private"));
+    assert_eq!(access(&s, "x"), Some("public"));
+    assert_eq!(access(&s, "y"), Some("private"));
+    assert_eq!(
+        s.field("y").unwrap().ty,
+        Ty::Array(Array {
+            ty: Box::new(Ty::Ident("uint8_t")),
+            len: 4,
+            flexible: false,
+        })
+    );
+    assert_eq!(
+        s.field("ref").unwrap().ty,
+        Ty::Pointer(Box::new(Ty::Ident("int")))
+    );
+}
+
+#[test]
+fn parse_cpp_base_classes_and_namespaces() {
+    let s = Struct::parse_cpp(
+        "#include <cstdint>
+        namespace fmt::v1 {
+        struct Base;
+        namespace detail {
+        struct alignas(16) Derived : Base, private other::Mixin {
+            std::uint64_t tail;
+        };
+        }
+        }",
+    )
+    .unwrap();
+    assert_eq!(s.namespace, ["fmt", "v1", "detail"]);
+    assert_eq!(s.repr.align, Some(16));
+    let names: Vec<_> = s.fields.iter().map(|f| f.name).collect();
+    assert_eq!(names, ["Base", "Mixin", "tail"]);
+    assert_eq!(s.fields[1].ty, Ty::Ident("other::Mixin"));
+    assert_eq!(
+        s.fields[1].attrs,
+        [
+            Attr {
+                name: "base",
+                value: None
+            },
+            Attr {
+                name: "access",
+                value: Some("private")
+            }
+        ]
+    );
+    assert_eq!(s.fields[2].ty, Ty::Ident("uint64_t"));
+}
+
+#[test]
+fn parse_cpp_enum_members() {
+    let s = Struct::parse_cpp(
+        "struct S {
+            enum Kind { A, B } kind;
+            enum class Mode : uint8_t { X, Y };
+            Mode mode;
+            enum : unsigned short { Z } z, zs[2];
+            enum Kind other;
+        };",
+    )
+    .unwrap();
+    let fields: Vec<_> = s.fields.iter().map(|f| (f.name, f.ty.to_string())).collect();
+    assert_eq!(
+        fields,
+        [
+            ("kind", "int".to_owned()),
+            ("mode", "Mode".to_owned()),
+            ("z", "unsigned short".to_owned()),
+            ("zs", "[unsigned short; 2]".to_owned()),
+            ("other", "Kind".to_owned()),
+        ]
+    );
+}
+
+#[test]
+fn parse_cpp_rejects_virtual() {
+    assert!(Struct::parse_cpp("class A : virtual public B {};").is_err());
+    assert!(Struct::parse_cpp("class A { virtual void f(); };").is_err());
+}
@@ -0,0 +1,459 @@
+//! C# frontend, for interop structs described with `StructLayout` and `MarshalAs`
+
+use {
+    super::{
+        PeekExt, TokIterExt, Toks, c::skip_member, parse_dotted, parse_int, parse_int_text,
+        skip_group,
+    },
+    crate::{
+        Array, Attr, Field, Repr, ReprKind, Struct, StructKind, StructParseError,
+        StructParseErrorKind, Ty,
+        tokenize::{Token, TokenKind},
+    },
+    std::ops::Range,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Modifiers of types and members, which have no effect on layout
+const MODIFIERS: &[&str] = &[
+    "public",
+    "private",
+    "protected",
+    "internal",
+    "unsafe",
+    "readonly",
+    "volatile",
+    "partial",
+    "ref",
+    "new",
+    "file",
+    "required",
+];
+
+/// Words that start a member which isn't an instance field
+const NON_FIELD_MEMBERS: &[&str] = &[
+    "static",
+    "const",
+    "class",
+    "interface",
+    "enum",
+    "record",
+    "delegate",
+    "event",
+    "operator",
+    "implicit",
+    "explicit",
+    "override",
+    "abstract",
+    "virtual",
+    "extern",
+    "using",
+];
+
+/// An attribute, like `[FieldOffset(4)]`, with the span of its name for errors
+struct CsAttr<'a> {
+    attr: Attr<'a>,
+    span: Range<usize>,
+}
+
+impl CsAttr<'_> {
+    /// The arguments of the attribute, with the names of named arguments
+    ///
+    /// For example, `LayoutKind.Sequential, Pack = 1` has the arguments
+    /// `(None, "LayoutKind.Sequential")` and `(Some("Pack"), "1")`.
+    fn args(&self) -> impl Iterator<Item = (Option<&str>, &str)> {
+        self.attr
+            .value
+            .unwrap_or_default()
+            .split(',')
+            .map(|arg| match arg.split_once('=') {
+                Some((name, value)) => (Some(name.trim()), value.trim()),
+                None => (None, arg.trim()),
+            })
+            .filter(|(_, value)| !value.is_empty())
+    }
+    /// Look up a named argument
+    fn arg(&self, name: &str) -> Option<&str> {
+        self.args().find_map(|(arg, value)| (arg == Some(name)).then_some(value))
+    }
+    /// Parse an integer argument, like `16` or `0x10`
+    fn int(&self, value: &str) -> Result<u64, StructParseError> {
+        parse_int_text(value, self.span.clone())
+    }
+    /// The last segment of the first argument, like `Explicit` in `LayoutKind.Explicit`
+    fn first_enum_arg(&self) -> Option<&str> {
+        let (None, value) = self.args().next()? else {
+            return None;
+        };
+        value.rsplit('.').next()
+    }
+}
+
+pub fn parse_struct<'a>(src: &'a str, tokens: &[Token]) -> Result<Struct<'a>, StructParseError> {
+    let mut toks = tokens.iter().peekable();
+    let mut namespace = Vec::new();
+    // The length of `namespace` outside of each open brace
+    let mut scopes = Vec::new();
+    let mut attrs = Vec::new();
+    loop {
+        let Some(tok) = toks.peek().copied().cloned() else {
+            return Err(StructParseError {
+                span: src.len()..src.len(),
+                kind: StructParseErrorKind::UnexpectedEnd,
+            });
+        };
+        match (tok.kind, toks.peek_ident(src)) {
+            (TokenKind::LSqBracket, _) => {
+                toks.next();
+                parse_attrs(src, &mut toks, &mut attrs)?;
+            }
+            (TokenKind::Ident, Some("namespace")) => {
+                toks.next();
+                let first = toks.expect_tok(TokenKind::Ident)?;
+                let path = parse_dotted(src, &first, &mut toks);
+                // File-scoped namespaces end with a semicolon instead of a block
+                if !toks.eat(TokenKind::Semi) {
+                    toks.expect_tok(TokenKind::LBrace)?;
+                    scopes.push(namespace.len());
+                }
+                namespace.extend(path.split('.'));
+            }
+            (TokenKind::Ident, Some(word)) if MODIFIERS.contains(&word) => {
+                toks.next();
+            }
+            (TokenKind::KwStruct, _) => {
+                toks.next();
+                let mut struct_ = parse_body(src, &mut toks, &attrs)?;
+                struct_.namespace = namespace;
+                return Ok(struct_);
+            }
+            (TokenKind::RBrace, _) => {
+                toks.next();
+                namespace.truncate(scopes.pop().unwrap_or_default());
+            }
+            // `using` directives, classes, enums and other declarations
+            _ => {
+                skip_member(&mut toks)?;
+                attrs.clear();
+            }
+        }
+    }
+}
+
+/// Parse a list of attributes after its `[`, like `StructLayout(LayoutKind.Sequential)]`
+fn parse_attrs<'a>(
+    src: &'a str,
+    toks: &mut Toks,
+    attrs: &mut Vec<CsAttr<'a>>,
+) -> Result<(), StructParseError> {
+    // Attribute targets, like `field:`
+    let mut ahead = toks.clone();
+    if ahead.eat(TokenKind::Ident) && ahead.eat(TokenKind::Colon) {
+        *toks = ahead;
+    }
+    loop {
+        let first = toks.expect_tok(TokenKind::Ident)?;
+        let path = parse_dotted(src, &first, toks);
+        let name = path.rsplit('.').next().unwrap_or(path);
+        let name = name.strip_suffix("Attribute").unwrap_or(name);
+        let value = if let Some(open) = toks.next_if(|tok| tok.kind == TokenKind::LParen) {
+            skip_group(toks, TokenKind::RParen)?;
+            let close = toks.next_tok()?;
+            Some(src[open.span.end..close.span.start].trim())
+        } else {
+            None
+        };
+        attrs.push(CsAttr {
+            attr: Attr { name, value },
+            span: first.span.start..toks.peek().map_or(src.len(), |tok| tok.span.start),
+        });
+        if !toks.eat(TokenKind::Comma) {
+            toks.expect_tok(TokenKind::RSqBracket)?;
+            return Ok(());
+        }
+    }
+}
+
+/// Whether `attrs` ask for `char` to be marshaled as a 2 byte character
+fn is_unicode(attrs: &[CsAttr]) -> bool {
+    attrs
+        .iter()
+        .filter(|attr| attr.attr.name == "StructLayout")
+        .filter_map(|attr| attr.arg("CharSet"))
+        .any(|charset| charset.ends_with("Unicode"))
+}
+
+/// Parse a struct after its keyword, with the attributes preceding it
+///
+/// `StructLayout` sets the representation: `Sequential` maps to C, `Auto` to Rust and
+/// `Explicit` to [`ReprKind::Explicit`]. All attributes are also kept as they are.
+fn parse_body<'a>(
+    src: &'a str,
+    toks: &mut Toks,
+    attrs: &[CsAttr<'a>],
+) -> Result<Struct<'a>, StructParseError> {
+    let name = &src[toks.expect_tok(TokenKind::Ident)?.span];
+    // Base interfaces and generic constraints
+    while !matches!(toks.peek_kind(), Some(TokenKind::LBrace) | None) {
+        toks.next();
+    }
+    toks.expect_tok(TokenKind::LBrace)?;
+    // C# structs are sequential unless specified otherwise
+    let mut repr = Repr {
+        kind: ReprKind::C,
+        ..Repr::default()
+    };
+    for attr in attrs.iter().filter(|attr| attr.attr.name == "StructLayout") {
+        match attr.first_enum_arg() {
+            Some("Auto") => repr.kind = ReprKind::Rust,
+            Some("Explicit") => repr.kind = ReprKind::Explicit,
+            _ => {}
+        }
+        // `Pack = 0` stands for the default packing
+        if let Some(pack) = attr.arg("Pack") {
+            repr.packed = Some(attr.int(pack)?).filter(|&pack| pack != 0);
+        }
+    }
+    let unicode = is_unicode(attrs);
+    let mut fields = Vec::new();
+    let mut field_attrs = Vec::new();
+    loop {
+        let tok = toks.next_tok()?;
+        match (tok.kind, &src[tok.span.clone()]) {
+            (TokenKind::RBrace, _) => break,
+            (TokenKind::Semi, _) => {}
+            (TokenKind::LSqBracket, _) => parse_attrs(src, toks, &mut field_attrs)?,
+            _ => {
+                parse_member(src, &tok, toks, &field_attrs, unicode, &mut fields)?;
+                field_attrs.clear();
+            }
+        }
+    }
+    Ok(Struct {
+        name,
+        kind: StructKind::Struct,
+        repr,
+        namespace: Vec::new(),
+        attrs: attrs.iter().map(|attr| attr.attr.clone()).collect(),
+        fields,
+    })
+}
+
+/// Parse a member, starting at `first`, pushing it to `fields` if it's an instance field
+fn parse_member<'a>(
+    src: &'a str,
+    first: &Token,
+    toks: &mut Toks,
+    attrs: &[CsAttr<'a>],
+    unicode: bool,
+    fields: &mut Vec<Field<'a>>,
+) -> Result<(), StructParseError> {
+    let mut access = "private";
+    let mut fixed = false;
+    let mut tok = first.clone();
+    loop {
+        match (tok.kind, &src[tok.span.clone()]) {
+            (TokenKind::Ident, word @ ("public" | "private" | "protected" | "internal")) => {
+                access = word;
+            }
+            (TokenKind::Ident, "fixed") => fixed = true,
+            (TokenKind::Ident, word) if MODIFIERS.contains(&word) => {}
+            (TokenKind::Ident, word) if NON_FIELD_MEMBERS.contains(&word) => {
+                return skip_member(toks);
+            }
+            (TokenKind::KwStruct, _) => return skip_member(toks),
+            (TokenKind::Ident, _) => break,
+            _ => return Err(StructParseError::unexpected(tok)),
+        }
+        tok = toks.next_tok()?;
+    }
+    let ty = parse_ty(src, &tok, toks, unicode)?;
+    // Managed arrays, like `byte[]`
+    let managed_array = toks.peek_kind() == Some(TokenKind::LSqBracket)
+        && toks.clone().nth(1).map(|tok| tok.kind) == Some(TokenKind::RSqBracket);
+    if managed_array {
+        toks.nth(1);
+    }
+    // Constructors, like `Foo(int a) { ... }`
+    if toks.peek_kind() == Some(TokenKind::LParen) {
+        return skip_member(toks);
+    }
+    loop {
+        let name_tok = toks.expect_tok(TokenKind::Ident)?;
+        match toks.peek_kind() {
+            // Methods and properties
+            Some(TokenKind::LParen | TokenKind::LBrace) => return skip_member(toks),
+            Some(TokenKind::Eq)
+                if toks.clone().nth(1).map(|tok| tok.kind) == Some(TokenKind::Gt) =>
+            {
+                return skip_member(toks);
+            }
+            _ => {}
+        }
+        let mut ty = ty.clone();
+        if fixed {
+            ty = parse_fixed_len(src, toks, ty)?;
+        }
+        let mut offset = None;
+        let mut by_val_array = false;
+        for attr in attrs {
+            match attr.attr.name {
+                "FieldOffset" => {
+                    let (_, value) = attr.args().next().unwrap_or_default();
+                    offset = Some(attr.int(value)?);
+                }
+                "MarshalAs" => {
+                    by_val_array |= attr.first_enum_arg() == Some("ByValArray");
+                    ty = marshal_as(ty, attr, unicode)?;
+                }
+                _ => {}
+            }
+        }
+        if managed_array && !by_val_array {
+            return Err(StructParseError {
+                span: name_tok.span,
+                kind: StructParseErrorKind::Unsupported(
+                    "managed arrays not marshaled with `UnmanagedType.ByValArray`",
+                ),
+            });
+        }
+        let mut field_attrs: Vec<_> = attrs.iter().map(|attr| attr.attr.clone()).collect();
+        field_attrs.push(Attr {
+            name: "access",
+            value: Some(access),
+        });
+        fields.push(Field {
+            name: &src[name_tok.span.clone()],
+            ty,
+            align: None,
+            offset,
+            bits: None,
+            endian: None,
+            span: name_tok.span,
+            attrs: field_attrs,
+        });
+        // Field initializers
+        if toks.eat(TokenKind::Eq) {
+            while !matches!(
+                toks.peek_kind(),
+                Some(TokenKind::Comma | TokenKind::Semi) | None
+            ) {
+                toks.next();
+            }
+        }
+        if !toks.eat(TokenKind::Comma) {
+            toks.expect_tok(TokenKind::Semi)?;
+            return Ok(());
+        }
+    }
+}
+
+/// Parse the `[N]` after the name of a fixed size buffer of elements of type `ty`
+fn parse_fixed_len<'a>(src: &str, toks: &mut Toks, ty: Ty<'a>) -> Result<Ty<'a>, StructParseError> {
+    toks.expect_tok(TokenKind::LSqBracket)?;
+    let len = parse_int(src, &toks.expect_tok(TokenKind::NumLit)?)?;
+    toks.expect_tok(TokenKind::RSqBracket)?;
+    Ok(Ty::Array(Array {
+        ty: Box::new(ty),
+        len,
+        flexible: false,
+    }))
+}
+
+/// The type a field of type `ty` is marshaled as with a `MarshalAs` attribute
+///
+/// For `ByValArray`, `ty` is the element type of the managed array.
+fn marshal_as<'a>(ty: Ty<'a>, attr: &CsAttr, unicode: bool) -> Result<Ty<'a>, StructParseError> {
+    let size_const = attr.arg("SizeConst").map(|size| attr.int(size)).transpose()?.unwrap_or(1);
+    Ok(match attr.first_enum_arg() {
+        Some("ByValArray") => Ty::Array(Array {
+            ty: Box::new(attr.arg("ArraySubType").and_then(unmanaged_type).map_or(ty, Ty::Ident)),
+            len: size_const,
+            flexible: false,
+        }),
+        Some("ByValTStr") => Ty::Array(Array {
+            ty: Box::new(Ty::Ident(if unicode { "u16" } else { "u8" })),
+            len: size_const,
+            flexible: false,
+        }),
+        Some("LPStr" | "LPUTF8Str") => Ty::Pointer(Box::new(Ty::Ident("u8"))),
+        Some("LPWStr" | "BStr") => Ty::Pointer(Box::new(Ty::Ident("u16"))),
+        Some(unmanaged) => unmanaged_type(unmanaged).map_or(ty, Ty::Ident),
+        None => ty,
+    })
+}
+
+/// The Rust spelling of an `UnmanagedType`, for the ones that are plain primitives
+fn unmanaged_type(name: &str) -> Option<&'static str> {
+    Some(match name.rsplit('.').next()? {
+        "I1" => "i8",
+        "U1" => "u8",
+        "I2" => "i16",
+        "U2" | "VariantBool" => "u16",
+        "I4" | "Error" => "i32",
+        "U4" => "u32",
+        "I8" => "i64",
+        "U8" => "u64",
+        "R4" => "f32",
+        "R8" => "f64",
+        "SysInt" => "isize",
+        "SysUInt" => "usize",
+        "Bool" => "BOOL",
+        _ => return None,
+    })
+}
+
+/// The Rust spelling of a C# builtin type, as it is marshaled by default
+///
+/// `bool` is marshaled as a 4 byte Win32 `BOOL`, and `char` as a 1 byte character
+/// unless the struct uses `CharSet.Unicode`.
+fn builtin_type(name: &str, unicode: bool) -> Option<&'static str> {
+    Some(match name {
+        "sbyte" | "SByte" => "i8",
+        "short" | "Int16" => "i16",
+        "ushort" | "UInt16" => "u16",
+        "int" | "Int32" => "i32",
+        "uint" | "UInt32" => "u32",
+        "long" | "Int64" => "i64",
+        "ulong" | "UInt64" => "u64",
+        "nint" | "IntPtr" => "isize",
+        "nuint" | "UIntPtr" => "usize",
+        "float" | "Single" => "f32",
+        "double" | "Double" => "f64",
+        "Half" => "f16",
+        "bool" | "Boolean" => "BOOL",
+        "char" | "Char" if unicode => "u16",
+        "byte" | "Byte" | "char" | "Char" => "u8",
+        "void" => "void",
+        _ => return None,
+    })
+}
+
+/// Parse a type, starting at `first`
+///
+/// `string` is marshaled as a pointer to a null-terminated string by default.
+fn parse_ty<'a>(
+    src: &'a str,
+    first: &Token,
+    toks: &mut Toks,
+    unicode: bool,
+) -> Result<Ty<'a>, StructParseError> {
+    let path = parse_dotted(src, first, toks);
+    let name = path.strip_prefix("System.").unwrap_or(path);
+    if toks.peek_kind() == Some(TokenKind::Lt) {
+        return Err(StructParseError {
+            span: first.span.clone(),
+            kind: StructParseErrorKind::Unsupported("generic types"),
+        });
+    }
+    let mut ty = match name {
+        "string" | "String" => Ty::Pointer(Box::new(Ty::Ident(if unicode { "u16" } else { "u8" }))),
+        _ => Ty::Ident(builtin_type(name, unicode).unwrap_or(path)),
+    };
+    while toks.eat(TokenKind::Star) {
+        ty = Ty::Pointer(Box::new(ty));
+    }
+    Ok(ty)
+}
@@ -0,0 +1,135 @@
+#![expect(clippy::unwrap_used)]
+
+use {
+    crate::{Array, ReprKind, Struct, StructParseErrorKind, Ty},
+    pretty_assertions::assert_eq,
+};
+
+fn array(ty: &'static str, len: u64) -> Ty<'static> {
+    Ty::Array(Array {
+        ty: Box::new(Ty::Ident(ty)),
+        len,
+        flexible: false,
+    })
+}
+
+#[test]
+fn parse_csharp_sequential() {
+    let s = Struct::parse_csharp(
+        "using System;
+        using System.Runtime.InteropServices;
+
+        namespace Tools.Interop
+        {
+            class Helper { int x; }
+
+            [StructLayout(LayoutKind.Sequential, Pack = 1)]
+            public unsafe struct Foo
+            {
+                public uint A;
+                [MarshalAs(UnmanagedType.ByValArray, SizeConst = 8)]
+                public byte[] B;
+                public fixed byte buf[16];
+                [MarshalAs(UnmanagedType.U1)] public bool Flag;
+                internal bool Wide;
+                public IntPtr Next, Prev;
+                [MarshalAs(UnmanagedType.ByValTStr, SizeConst = 32)]
+                public string Name;
+                public const int Max = 4;
+                public static int Count;
+                public int Sum => (int)A;
+                public Foo(uint a) { A = a; }
+                public override string ToString() { return \"\"; }
+            }
+        }",
+    )
+    .unwrap();
+    assert_eq!(s.name, "Foo");
+    assert_eq!(s.namespace, ["Tools", "Interop"]);
+    assert_eq!(s.repr.kind, ReprKind::C);
+    assert_eq!(s.repr.packed, Some(1));
+    let names: Vec<_> = s.fields.iter().map(|f| f.name).collect();
+    assert_eq!(
+        names,
+        ["A", "B", "buf", "Flag", "Wide", "Next", "Prev", "Name"]
+    );
+    assert_eq!(s.field("A").unwrap().ty, Ty::Ident("u32"));
+    assert_eq!(s.field("B").unwrap().ty, array("u8", 8));
+    assert_eq!(s.field("buf").unwrap().ty, array("u8", 16));
+    assert_eq!(s.field("Flag").unwrap().ty, Ty::Ident("u8"));
+    // `bool` is marshaled as a 4 byte `BOOL` by default
+    assert_eq!(s.field("Wide").unwrap().ty, Ty::Ident("BOOL"));
+    assert_eq!(s.field("Prev").unwrap().ty, Ty::Ident("isize"));
+    assert_eq!(s.field("Name").unwrap().ty, array("u8", 32));
+    assert_eq!(
+        s.field("Wide").unwrap().attr("access").unwrap().value,
+        Some("internal")
+    );
+    assert_eq!(
+        s.field("B").unwrap().attr("MarshalAs").unwrap().value,
+        Some("UnmanagedType.ByValArray, SizeConst = 8")
+    );
+}
+
+#[test]
+fn parse_csharp_explicit() {
+    let s = Struct::parse_csharp(
+        "[StructLayout(LayoutKind.Explicit, Size = 8, CharSet = CharSet.Unicode)]
+        struct Value {
+            [FieldOffset(0)] public long Int;
+            [FieldOffset(0)] public double Float;
+            [FieldOffset(4)] public char Tag;
+            [field: FieldOffset(6)] ushort Extra;
+        }",
+    )
+    .unwrap();
+    assert_eq!(s.repr.kind, ReprKind::Explicit);
+    assert_eq!(s.repr.packed, None);
+    let offsets: Vec<_> = s.fields.iter().map(|f| f.offset).collect();
+    assert_eq!(offsets, [Some(0), Some(0), Some(4), Some(6)]);
+    assert_eq!(s.field("Int").unwrap().ty, Ty::Ident("i64"));
+    assert_eq!(s.field("Tag").unwrap().ty, Ty::Ident("u16"));
+    assert_eq!(
+        s.field("Extra").unwrap().attr("access").unwrap().value,
+        Some("private")
+    );
+    assert_eq!(
+        s.attr("StructLayout").unwrap().value,
+        Some("LayoutKind.Explicit, Size = 8, CharSet = CharSet.Unicode")
+    );
+}
+
+#[test]
+fn parse_csharp_hex_arguments() {
+    let s = Struct::parse_csharp(
+        "[StructLayout(LayoutKind.Explicit, Size = 0x40)]
+        struct Packet {
+            [FieldOffset(0x10)] public uint Len;
+            [FieldOffset(0x14), MarshalAs(UnmanagedType.ByValArray, SizeConst = 0x20)]
+            public byte[] Data;
+        }",
+    )
+    .unwrap();
+    let offsets: Vec<_> = s.fields.iter().map(|f| f.offset).collect();
+    assert_eq!(offsets, [Some(0x10), Some(0x14)]);
+    assert_eq!(
+        s.field("Data").unwrap().ty,
+        Ty::Array(Array {
+            ty: Box::new(Ty::Ident("u8")),
+            len: 0x20,
+            flexible: false,
+        })
+    );
+}
+
+#[test]
+fn parse_csharp_errors() {
+    // Managed arrays are references unless marshaled by value
+    let err = Struct::parse_csharp("struct Foo { public byte[] Data; }").unwrap_err();
+    assert!(matches!(err.kind, StructParseErrorKind::Unsupported(_)));
+    let err = Struct::parse_csharp(
+        "[StructLayout(LayoutKind.Sequential, Pack = x)] struct Foo { int A; }",
+    )
+    .unwrap_err();
+    assert!(matches!(err.kind, StructParseErrorKind::NumParse(_)));
+}
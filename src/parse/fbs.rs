@@ -0,0 +1,259 @@
+//! FlatBuffers schema frontend
+
+use {
+    super::{
+        PeekExt, TokIterExt, Toks, c::skip_member, parse_dotted, parse_int, parse_signed, str_lit,
+    },
+    crate::{
+        Array, Attr, Enum, Field, Item, Repr, ReprKind, Struct, StructKind, StructParseError,
+        StructParseErrorKind, Ty, Variant,
+        tokenize::{Token, TokenKind},
+    },
+};
+
+#[cfg(test)]
+mod tests;
+
+pub fn parse_items<'a>(src: &'a str, tokens: &[Token]) -> Result<Vec<Item<'a>>, StructParseError> {
+    let mut toks = tokens.iter().peekable();
+    let mut namespace = Vec::new();
+    let mut items = Vec::new();
+    while let Some(tok) = toks.next() {
+        match (tok.kind, &src[tok.span.clone()]) {
+            (TokenKind::Ident, "namespace") => {
+                let first = toks.expect_tok(TokenKind::Ident)?;
+                namespace = parse_dotted(src, &first, &mut toks).split('.').collect();
+                toks.expect_tok(TokenKind::Semi)?;
+            }
+            (TokenKind::KwStruct, _) | (TokenKind::Ident, "table") => {
+                let mut struct_ = parse_struct(src, &mut toks, tok.kind == TokenKind::KwStruct)?;
+                struct_.namespace.clone_from(&namespace);
+                items.push(Item::Struct(struct_));
+            }
+            (TokenKind::Ident, word @ ("union" | "enum")) => {
+                let mut enum_ = parse_enum(src, &mut toks, word == "union")?;
+                enum_.namespace.clone_from(&namespace);
+                items.push(Item::Enum(enum_));
+            }
+            // `include`, `attribute`, `root_type`, `rpc_service` and other declarations
+            _ => skip_member(&mut toks)?,
+        }
+    }
+    Ok(items)
+}
+
+/// Parse metadata in parentheses, like `(id: 3, deprecated)`, if there is any
+fn parse_metadata<'a>(src: &'a str, toks: &mut Toks) -> Result<Vec<Attr<'a>>, StructParseError> {
+    let mut attrs = Vec::new();
+    if !toks.eat(TokenKind::LParen) {
+        return Ok(attrs);
+    }
+    while !toks.eat(TokenKind::RParen) {
+        let name = &src[toks.expect_tok(TokenKind::Ident)?.span];
+        let value = if toks.eat(TokenKind::Colon) {
+            let tok = toks.next_tok()?;
+            Some(match tok.kind {
+                TokenKind::StrLit => str_lit(src, &tok),
+                _ => &src[tok.span],
+            })
+        } else {
+            None
+        };
+        attrs.push(Attr { name, value });
+        toks.eat(TokenKind::Comma);
+    }
+    Ok(attrs)
+}
+
+/// Parse a struct or table after its keyword
+///
+/// Structs get the C representation, with `force_align` applied to it.
+fn parse_struct<'a>(
+    src: &'a str,
+    toks: &mut Toks,
+    fixed: bool,
+) -> Result<Struct<'a>, StructParseError> {
+    let name_tok = toks.expect_tok(TokenKind::Ident)?;
+    let name = &src[name_tok.span.clone()];
+    let mut attrs = parse_metadata(src, toks)?;
+    let mut repr = Repr::default();
+    if fixed {
+        repr.kind = ReprKind::C;
+        if let Some(pos) = attrs.iter().position(|attr| attr.name == "force_align")
+            && let Some(align) = attrs[pos].value
+        {
+            repr.align = Some(align.parse().map_err(|e| StructParseError {
+                span: name_tok.span.clone(),
+                kind: StructParseErrorKind::NumParse(e),
+            })?);
+            attrs.remove(pos);
+        }
+    } else {
+        attrs.insert(
+            0,
+            Attr {
+                name: "table",
+                value: None,
+            },
+        );
+    }
+    toks.expect_tok(TokenKind::LBrace)?;
+    let mut fields = Vec::new();
+    while !toks.eat(TokenKind::RBrace) {
+        fields.push(parse_field(src, toks)?);
+    }
+    Ok(Struct {
+        name,
+        kind: StructKind::Struct,
+        repr,
+        namespace: Vec::new(),
+        attrs,
+        fields,
+    })
+}
+
+/// Parse a field, like `hp: short = 100 (deprecated);`
+fn parse_field<'a>(src: &'a str, toks: &mut Toks) -> Result<Field<'a>, StructParseError> {
+    let span = toks.expect_tok(TokenKind::Ident)?.span;
+    let name = &src[span.clone()];
+    toks.expect_tok(TokenKind::Colon)?;
+    let mut attrs = Vec::new();
+    let ty = parse_ty(src, toks, &mut attrs)?;
+    if let Some(eq) = toks.next_if(|tok| tok.kind == TokenKind::Eq) {
+        while !matches!(
+            toks.peek_kind(),
+            Some(TokenKind::LParen | TokenKind::Semi) | None
+        ) {
+            toks.next();
+        }
+        let end = toks.peek().map_or(src.len(), |tok| tok.span.start);
+        attrs.push(Attr {
+            name: "default",
+            value: Some(src[eq.span.end..end].trim()),
+        });
+    }
+    attrs.extend(parse_metadata(src, toks)?);
+    toks.expect_tok(TokenKind::Semi)?;
+    Ok(Field {
+        name,
+        ty,
+        align: None,
+        offset: None,
+        bits: None,
+        endian: None,
+        span,
+        attrs,
+    })
+}
+
+/// Parse a field type
+///
+/// Fixed-length arrays like `[int:4]` become arrays, and vectors like `[ubyte]` their
+/// element type, with a `vector` attribute added to `attrs`.
+fn parse_ty<'a>(
+    src: &'a str,
+    toks: &mut Toks,
+    attrs: &mut Vec<Attr<'a>>,
+) -> Result<Ty<'a>, StructParseError> {
+    if !toks.eat(TokenKind::LSqBracket) {
+        let first = toks.expect_tok(TokenKind::Ident)?;
+        return Ok(Ty::Ident(parse_dotted(src, &first, toks)));
+    }
+    let ty = parse_ty(src, toks, attrs)?;
+    let ty = if toks.eat(TokenKind::Colon) {
+        Ty::Array(Array {
+            ty: Box::new(ty),
+            len: parse_int(src, &toks.expect_tok(TokenKind::NumLit)?)?,
+            flexible: false,
+        })
+    } else {
+        attrs.push(Attr {
+            name: "vector",
+            value: None,
+        });
+        ty
+    };
+    toks.expect_tok(TokenKind::RSqBracket)?;
+    Ok(ty)
+}
+
+/// Parse an enum or union after its keyword
+///
+/// Union variants are numbered from 1, since 0 stands for no value. The values of
+/// `bit_flags` enums are bit positions, which are turned into masks.
+fn parse_enum<'a>(
+    src: &'a str,
+    toks: &mut Toks,
+    union: bool,
+) -> Result<Enum<'a>, StructParseError> {
+    let name = &src[toks.expect_tok(TokenKind::Ident)?.span];
+    let ty = if toks.eat(TokenKind::Colon) {
+        let first = toks.expect_tok(TokenKind::Ident)?;
+        Some(Ty::Ident(parse_dotted(src, &first, toks)))
+    } else if union {
+        Some(Ty::Ident("ubyte"))
+    } else {
+        None
+    };
+    let mut attrs = parse_metadata(src, toks)?;
+    if union {
+        attrs.insert(
+            0,
+            Attr {
+                name: "union",
+                value: None,
+            },
+        );
+    }
+    let bit_flags = attrs.iter().any(|attr| attr.name == "bit_flags");
+    toks.expect_tok(TokenKind::LBrace)?;
+    let mut variants = Vec::new();
+    let mut next = i64::from(union);
+    while !toks.eat(TokenKind::RBrace) {
+        let first = toks.expect_tok(TokenKind::Ident)?;
+        let mut name = parse_dotted(src, &first, toks);
+        let mut variant_attrs = Vec::new();
+        // Union members with an alias, like `Sword: Weapon`
+        if union && toks.eat(TokenKind::Colon) {
+            let first = toks.expect_tok(TokenKind::Ident)?;
+            variant_attrs.push(Attr {
+                name: "type",
+                value: Some(parse_dotted(src, &first, toks)),
+            });
+        } else if union {
+            variant_attrs.push(Attr {
+                name: "type",
+                value: Some(name),
+            });
+            name = name.rsplit('.').next().unwrap_or(name);
+        }
+        if toks.eat(TokenKind::Eq) {
+            next = parse_signed(src, &toks.expect_tok(TokenKind::NumLit)?)?;
+        }
+        variant_attrs.extend(parse_metadata(src, toks)?);
+        let value = if bit_flags {
+            u32::try_from(next).ok().and_then(|bit| 1i64.checked_shl(bit)).ok_or(
+                StructParseError {
+                    span: first.span,
+                    kind: StructParseErrorKind::Unsupported("bit flags outside of 64 bits"),
+                },
+            )?
+        } else {
+            next
+        };
+        variants.push(Variant {
+            name,
+            value,
+            attrs: variant_attrs,
+        });
+        next += 1;
+        toks.eat(TokenKind::Comma);
+    }
+    Ok(Enum {
+        name,
+        ty,
+        namespace: Vec::new(),
+        attrs,
+        variants,
+    })
+}
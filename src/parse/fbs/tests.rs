@@ -0,0 +1,94 @@
+#![expect(clippy::unwrap_used)]
+
+use {
+    crate::{Array, Item, ReprKind, Struct, Ty},
+    pretty_assertions::assert_eq,
+};
+
+const MONSTER: &str = r#"include "weapons.fbs";
+namespace MyGame.Sample;
+
+attribute "priority";
+
+enum Color : byte { Red = -1, Green, Blue = 2 }
+
+enum Flags : ubyte (bit_flags) { A, B, C = 4 }
+
+union Equipment { Weapon, Sword: MyGame.Weapon }
+
+struct Vec3 (force_align: 16) {
+  x: float;
+  y: float;
+  z: float;
+  pad: [ubyte:4];
+}
+
+table Monster {
+  pos: Vec3;
+  mana: short = 150;
+  hp: short = -1 (deprecated);
+  name: string (id: 3, required);
+  inventory: [ubyte];
+  color: Color = Blue;
+}
+
+root_type Monster;
+"#;
+
+#[test]
+fn parse_fbs_schema() {
+    let items = Item::parse_fbs(MONSTER).unwrap();
+    assert_eq!(items.len(), 5);
+    let Item::Enum(color) = &items[0] else {
+        panic!("expected an enum");
+    };
+    assert_eq!(color.name, "Color");
+    assert_eq!(color.namespace, ["MyGame", "Sample"]);
+    assert_eq!(color.ty, Some(Ty::Ident("byte")));
+    let values: Vec<_> = color.variants.iter().map(|v| v.value).collect();
+    assert_eq!(values, [-1, 0, 2]);
+    let Item::Enum(flags) = &items[1] else {
+        panic!("expected an enum");
+    };
+    let values: Vec<_> = flags.variants.iter().map(|v| v.value).collect();
+    assert_eq!(values, [1, 2, 16]);
+    let Item::Enum(equipment) = &items[2] else {
+        panic!("expected an enum");
+    };
+    assert!(equipment.attr("union").is_some());
+    let sword = equipment.variant("Sword").unwrap();
+    assert_eq!(sword.value, 2);
+    assert_eq!(sword.attrs[0].value, Some("MyGame.Weapon"));
+}
+
+#[test]
+fn parse_fbs_struct_and_table() {
+    let vec3 = Struct::parse_fbs(MONSTER).unwrap();
+    assert_eq!(vec3.name, "Vec3");
+    assert_eq!(vec3.repr.kind, ReprKind::C);
+    assert_eq!(vec3.repr.align, Some(16));
+    assert_eq!(
+        vec3.field("pad").unwrap().ty,
+        Ty::Array(Array {
+            ty: Box::new(Ty::Ident("ubyte")),
+            len: 4,
+            flexible: false,
+        })
+    );
+    let items = Item::parse_fbs(MONSTER).unwrap();
+    let Item::Struct(monster) = &items[4] else {
+        panic!("expected a table");
+    };
+    assert!(monster.attr("table").is_some());
+    assert_eq!(monster.repr.kind, ReprKind::Rust);
+    let hp = monster.field("hp").unwrap();
+    assert_eq!(hp.attr("default").unwrap().value, Some("-1"));
+    assert!(hp.attr("deprecated").is_some());
+    assert_eq!(
+        monster.field("name").unwrap().attr("id").unwrap().value,
+        Some("3")
+    );
+    let inventory = monster.field("inventory").unwrap();
+    assert_eq!(inventory.ty, Ty::Ident("ubyte"));
+    assert!(inventory.attr("vector").is_some());
+}
@@ -0,0 +1,293 @@
+//! Go frontend, for struct type declarations
+
+use {
+    super::{PeekExt, TokIterExt, Toks, parse_dotted, parse_int, skip_group, str_lit},
+    crate::{
+        Array, Attr, Field, Repr, ReprKind, Struct, StructKind, StructParseError,
+        StructParseErrorKind, Ty,
+        tokenize::{Token, TokenKind},
+    },
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Options for the Go frontend
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GoOptions {
+    /// The width of `int` and `uint` in bits, which is 64 on 64-bit platforms
+    ///
+    /// Go only allows 32 and 64.
+    pub int_bits: u16,
+}
+
+impl Default for GoOptions {
+    fn default() -> Self {
+        Self { int_bits: 64 }
+    }
+}
+
+pub fn parse_struct<'a>(
+    src: &'a str,
+    tokens: &[Token],
+    opts: GoOptions,
+) -> Result<Struct<'a>, StructParseError> {
+    let mut toks = tokens.iter().peekable();
+    loop {
+        if toks.peek().is_none() {
+            return Err(StructParseError {
+                span: src.len()..src.len(),
+                kind: StructParseErrorKind::UnexpectedEnd,
+            });
+        }
+        if toks.peek_ident(src) != Some("type") {
+            // `package`, imports, functions and other declarations
+            skip_line(src, &mut toks)?;
+            continue;
+        }
+        toks.next();
+        // `type ( A struct { ... }; B uint32 )`
+        let grouped = toks.eat(TokenKind::LParen);
+        loop {
+            if grouped && toks.eat(TokenKind::RParen) {
+                break;
+            }
+            let name_tok = toks.expect_tok(TokenKind::Ident)?;
+            if let Some(tok) = toks.next_if(|tok| tok.kind == TokenKind::LSqBracket) {
+                return Err(StructParseError {
+                    span: tok.span.clone(),
+                    kind: StructParseErrorKind::Unsupported("generic types"),
+                });
+            }
+            // Type aliases, like `type A = B`
+            toks.eat(TokenKind::Eq);
+            if toks.eat(TokenKind::KwStruct) {
+                let mut struct_ = parse_body(src, &mut toks, opts)?;
+                struct_.name = &src[name_tok.span];
+                return Ok(struct_);
+            }
+            skip_line(src, &mut toks)?;
+            if !grouped {
+                break;
+            }
+        }
+    }
+}
+
+/// Whether `tok` is the first token on a line, after a token ending at `prev_end`
+fn starts_line(src: &str, prev_end: usize, tok: &Token) -> bool {
+    src.get(prev_end..tok.span.start).is_some_and(|between| between.contains('\n'))
+}
+
+/// Skip a declaration, which ends at a semicolon or the end of a line outside of brackets
+fn skip_line(src: &str, toks: &mut Toks) -> Result<(), StructParseError> {
+    loop {
+        let tok = toks.next_tok()?;
+        let close = match tok.kind {
+            TokenKind::Semi => return Ok(()),
+            TokenKind::LParen => Some(TokenKind::RParen),
+            TokenKind::LBrace => Some(TokenKind::RBrace),
+            TokenKind::LSqBracket => Some(TokenKind::RSqBracket),
+            _ => None,
+        };
+        let mut end = tok.span.end;
+        if let Some(close) = close {
+            skip_group(toks, close)?;
+            end = toks.next_tok()?.span.end;
+        }
+        match toks.peek() {
+            Some(next) if !starts_line(src, end, next) => {}
+            _ => return Ok(()),
+        }
+    }
+}
+
+/// Parse the fields of a struct type, after the `struct` keyword
+///
+/// Go lays out fields in declaration order with their natural alignment, like C does.
+fn parse_body<'a>(
+    src: &'a str,
+    toks: &mut Toks,
+    opts: GoOptions,
+) -> Result<Struct<'a>, StructParseError> {
+    toks.expect_tok(TokenKind::LBrace)?;
+    let mut fields = Vec::new();
+    loop {
+        match toks.peek_kind() {
+            Some(TokenKind::RBrace) => {
+                toks.next();
+                break;
+            }
+            Some(TokenKind::Semi) => {
+                toks.next();
+            }
+            _ => parse_field_decl(src, toks, &mut fields, opts)?,
+        }
+    }
+    Ok(Struct {
+        name: "",
+        kind: StructKind::Struct,
+        repr: Repr {
+            kind: ReprKind::C,
+            ..Repr::default()
+        },
+        namespace: Vec::new(),
+        attrs: Vec::new(),
+        fields,
+    })
+}
+
+/// Parse a field declaration with its tag, like `A, B uint32`, or an embedded field
+///
+/// Embedded fields are named after their type, and get an `embedded` attribute. Struct
+/// tags following the conventional `key:"value"` format become attributes.
+fn parse_field_decl<'a>(
+    src: &'a str,
+    toks: &mut Toks,
+    fields: &mut Vec<Field<'a>>,
+    opts: GoOptions,
+) -> Result<(), StructParseError> {
+    let mut names = Vec::new();
+    let mut embedded = true;
+    let ty = if toks.eat(TokenKind::Star) {
+        let first = toks.expect_tok(TokenKind::Ident)?;
+        let path = parse_dotted(src, &first, toks);
+        names.push((
+            last_segment(path),
+            first.span.start..first.span.start + path.len(),
+        ));
+        Ty::Pointer(Box::new(Ty::Ident(path)))
+    } else {
+        let first = toks.expect_tok(TokenKind::Ident)?;
+        let path = parse_dotted(src, &first, toks);
+        embedded = path.contains('.')
+            || toks.peek().is_none_or(|next| {
+                starts_line(src, first.span.start + path.len(), next)
+                    || matches!(
+                        next.kind,
+                        TokenKind::Semi | TokenKind::RBrace | TokenKind::StrLit
+                    )
+            });
+        let span = first.span.start..first.span.start + path.len();
+        if embedded {
+            names.push((last_segment(path), span));
+            Ty::Ident(path)
+        } else {
+            names.push((path, span));
+            while toks.eat(TokenKind::Comma) {
+                let span = toks.expect_tok(TokenKind::Ident)?.span;
+                names.push((&src[span.clone()], span));
+            }
+            parse_ty(src, toks, opts)?
+        }
+    };
+    let mut attrs = Vec::new();
+    if embedded {
+        attrs.push(Attr {
+            name: "embedded",
+            value: None,
+        });
+    }
+    if let Some(tag) = toks.next_if(|tok| tok.kind == TokenKind::StrLit) {
+        attrs.extend(parse_tag(str_lit(src, tag)));
+    }
+    for (name, span) in names {
+        fields.push(Field {
+            // Blank fields, which are used for padding
+            name: if name == "_" { "" } else { name },
+            ty: ty.clone(),
+            align: None,
+            offset: None,
+            bits: None,
+            endian: None,
+            span,
+            attrs: attrs.clone(),
+        });
+    }
+    Ok(())
+}
+
+/// The last segment of a dotted path, which names embedded fields
+fn last_segment(path: &str) -> &str {
+    path.rsplit('.').next().unwrap_or(path)
+}
+
+/// Split a struct tag like `json:"name" bin:"le"` into attributes
+///
+/// Tags that don't follow this convention are kept whole, as a `tag` attribute.
+fn parse_tag(tag: &str) -> Vec<Attr<'_>> {
+    split_tag(tag).unwrap_or_else(|| {
+        vec![Attr {
+            name: "tag",
+            value: Some(tag),
+        }]
+    })
+}
+
+fn split_tag(tag: &str) -> Option<Vec<Attr<'_>>> {
+    let mut attrs = Vec::new();
+    let mut rest = tag.trim_start();
+    while !rest.is_empty() {
+        let (key, after) = rest.split_once(":\"")?;
+        let end = after.find('"')?;
+        if key.is_empty() || key.contains(char::is_whitespace) {
+            return None;
+        }
+        attrs.push(Attr {
+            name: key,
+            value: Some(&after[..end]),
+        });
+        rest = after[end + 1..].trim_start();
+    }
+    Some(attrs)
+}
+
+fn parse_ty<'a>(
+    src: &'a str,
+    toks: &mut Toks,
+    opts: GoOptions,
+) -> Result<Ty<'a>, StructParseError> {
+    let tok = toks.next_tok()?;
+    match tok.kind {
+        TokenKind::KwStruct => Ok(Ty::Inline(Box::new(parse_body(src, toks, opts)?))),
+        TokenKind::Star => Ok(Ty::Pointer(Box::new(parse_ty(src, toks, opts)?))),
+        TokenKind::LSqBracket if toks.eat(TokenKind::RSqBracket) => Err(StructParseError {
+            span: tok.span,
+            kind: StructParseErrorKind::Unsupported("slices"),
+        }),
+        TokenKind::LSqBracket => {
+            let len = parse_int(src, &toks.expect_tok(TokenKind::NumLit)?)?;
+            toks.expect_tok(TokenKind::RSqBracket)?;
+            Ok(Ty::Array(Array {
+                ty: Box::new(parse_ty(src, toks, opts)?),
+                len,
+                flexible: false,
+            }))
+        }
+        TokenKind::Ident => match &src[tok.span.clone()] {
+            "int" | "uint" => int_ty(&tok, src.as_bytes()[tok.span.start] == b'i', opts),
+            "map" | "chan" | "func" | "interface" => Err(StructParseError {
+                span: tok.span,
+                kind: StructParseErrorKind::Unsupported("reference types"),
+            }),
+            _ => Ok(Ty::Ident(parse_dotted(src, &tok, toks))),
+        },
+        _ => Err(StructParseError::unexpected(tok)),
+    }
+}
+
+/// The sized integer type that `int` or `uint` stands for
+fn int_ty(tok: &Token, signed: bool, opts: GoOptions) -> Result<Ty<'static>, StructParseError> {
+    Ok(Ty::Ident(match (signed, opts.int_bits) {
+        (true, 32) => "i32",
+        (true, 64) => "i64",
+        (false, 32) => "u32",
+        (false, 64) => "u64",
+        _ => {
+            return Err(StructParseError {
+                span: tok.span.clone(),
+                kind: StructParseErrorKind::Unsupported("int widths other than 32 and 64"),
+            });
+        }
+    }))
+}
@@ -0,0 +1,94 @@
+#![expect(clippy::unwrap_used)]
+
+use {
+    crate::{Array, GoOptions, Struct, Ty},
+    pretty_assertions::assert_eq,
+};
+
+fn tag<'s>(s: &'s Struct, field: &str, key: &str) -> Option<&'s str> {
+    s.field(field)?.attr(key)?.value
+}
+
+#[test]
+fn parse_go_struct_with_tags() {
+    let s = Struct::parse_go(
+        r#"package wire
+
+        import (
+            "encoding/binary"
+        )
+
+        // Header starts every message
+        type Header struct {
+            Magic uint32 `bin:"le" json:"magic,omitempty"`
+            Name  [16]byte
+            X, Y  int16
+            _     [2]byte
+            Count int
+            Next  *Header "json:\"next\""
+        }
+
+        func (h *Header) Len() int { return 0 }"#,
+    )
+    .unwrap();
+    assert_eq!(s.name, "Header");
+    let names: Vec<_> = s.fields.iter().map(|f| f.name).collect();
+    assert_eq!(names, ["Magic", "Name", "X", "Y", "", "Count", "Next"]);
+    assert_eq!(tag(&s, "Magic", "bin"), Some("le"));
+    assert_eq!(tag(&s, "Magic", "json"), Some("magic,omitempty"));
+    assert_eq!(
+        s.field("Name").unwrap().ty,
+        Ty::Array(Array {
+            ty: Box::new(Ty::Ident("byte")),
+            len: 16,
+            flexible: false,
+        })
+    );
+    assert_eq!(s.field("Y").unwrap().ty, Ty::Ident("int16"));
+    assert_eq!(s.field("Count").unwrap().ty, Ty::Ident("i64"));
+    assert_eq!(
+        s.field("Next").unwrap().ty,
+        Ty::Pointer(Box::new(Ty::Ident("Header")))
+    );
+    // Unconventional tags are kept whole
+    assert_eq!(tag(&s, "Next", "tag"), Some(r#"json:\"next\""#));
+}
+
+#[test]
+fn parse_go_embedded_fields() {
+    let s = Struct::parse_go(
+        "type (
+            ID uint64
+            Msg struct {
+                Header
+                *binary.ByteOrder
+                Inner struct { A, B uint8 }
+            }
+        )",
+    )
+    .unwrap();
+    assert_eq!(s.name, "Msg");
+    let names: Vec<_> = s.fields.iter().map(|f| f.name).collect();
+    assert_eq!(names, ["Header", "ByteOrder", "Inner"]);
+    assert!(s.field("Header").unwrap().attr("embedded").is_some());
+    assert_eq!(
+        s.field("ByteOrder").unwrap().ty,
+        Ty::Pointer(Box::new(Ty::Ident("binary.ByteOrder")))
+    );
+    assert!(s.field("Inner").unwrap().attr("embedded").is_none());
+    let Ty::Inline(inner) = &s.field("Inner").unwrap().ty else {
+        panic!("expected an inline struct");
+    };
+    assert_eq!(inner.field("B").unwrap().ty, Ty::Ident("uint8"));
+}
+
+#[test]
+fn parse_go_int_width() {
+    let input = "type T struct { A int; B uint }";
+    let s = Struct::parse_go_with(input, GoOptions { int_bits: 32 }).unwrap();
+    assert_eq!(s.field("A").unwrap().ty, Ty::Ident("i32"));
+    assert_eq!(s.field("B").unwrap().ty, Ty::Ident("u32"));
+    assert!(Struct::parse_go_with(input, GoOptions { int_bits: 16 }).is_err());
+    assert!(Struct::parse_go("type T struct { A []byte }").is_err());
+    assert!(Struct::parse_go("type T struct { M map[string]int }").is_err());
+}
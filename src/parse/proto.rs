@@ -0,0 +1,228 @@
+//! Protobuf schema frontend
+
+use {
+    super::{
+        PeekExt, TokIterExt, Toks, c::skip_member, parse_dotted, parse_signed, shader::paren_text,
+        skip_group, str_lit,
+    },
+    crate::{
+        Attr, Enum, Field, Item, Repr, Struct, StructKind, StructParseError, Ty, Variant,
+        tokenize::{Token, TokenKind},
+    },
+};
+
+#[cfg(test)]
+mod tests;
+
+pub fn parse_items<'a>(src: &'a str, tokens: &[Token]) -> Result<Vec<Item<'a>>, StructParseError> {
+    let mut toks = tokens.iter().peekable();
+    let mut namespace = Vec::new();
+    let mut items = Vec::new();
+    while let Some(tok) = toks.next() {
+        match &src[tok.span.clone()] {
+            "package" => {
+                let first = toks.expect_tok(TokenKind::Ident)?;
+                namespace = parse_dotted(src, &first, &mut toks).split('.').collect();
+                toks.expect_tok(TokenKind::Semi)?;
+            }
+            "message" => parse_message(src, &mut toks, &namespace, &mut items)?,
+            "enum" => items.push(Item::Enum(parse_enum(src, &mut toks, &namespace)?)),
+            // `syntax`, `import`, `option`, `service` and other declarations
+            _ => skip_member(&mut toks)?,
+        }
+    }
+    Ok(items)
+}
+
+/// Parse a message after its keyword, pushing it and the types nested in it to `items`
+fn parse_message<'a>(
+    src: &'a str,
+    toks: &mut Toks,
+    namespace: &[&'a str],
+    items: &mut Vec<Item<'a>>,
+) -> Result<(), StructParseError> {
+    let name = &src[toks.expect_tok(TokenKind::Ident)?.span];
+    let nested_namespace = [namespace, &[name]].concat();
+    let mut nested = Vec::new();
+    let mut fields = Vec::new();
+    toks.expect_tok(TokenKind::LBrace)?;
+    loop {
+        let tok = toks.next_tok()?;
+        match (tok.kind, &src[tok.span.clone()]) {
+            (TokenKind::RBrace, _) => break,
+            (TokenKind::Semi, _) => {}
+            (TokenKind::Ident, "message") => {
+                parse_message(src, toks, &nested_namespace, &mut nested)?;
+            }
+            (TokenKind::Ident, "enum") => {
+                nested.push(Item::Enum(parse_enum(src, toks, &nested_namespace)?));
+            }
+            (TokenKind::Ident, "oneof") => {
+                let oneof = &src[toks.expect_tok(TokenKind::Ident)?.span];
+                toks.expect_tok(TokenKind::LBrace)?;
+                while !toks.eat(TokenKind::RBrace) {
+                    if toks.peek_ident(src) == Some("option") {
+                        skip_member(toks)?;
+                        continue;
+                    }
+                    let first = toks.next_tok()?;
+                    let mut field = parse_field(src, &first, toks)?;
+                    field.attrs.insert(
+                        0,
+                        Attr {
+                            name: "oneof",
+                            value: Some(oneof),
+                        },
+                    );
+                    fields.push(field);
+                }
+            }
+            (TokenKind::Ident, "option" | "reserved" | "extensions" | "extend") => {
+                skip_member(toks)?;
+            }
+            _ => fields.push(parse_field(src, &tok, toks)?),
+        }
+    }
+    items.push(Item::Struct(Struct {
+        name,
+        kind: StructKind::Struct,
+        repr: Repr::default(),
+        namespace: namespace.to_vec(),
+        attrs: vec![Attr {
+            name: "message",
+            value: None,
+        }],
+        fields,
+    }));
+    items.extend(nested);
+    Ok(())
+}
+
+/// Parse a field, like `repeated int32 ids = 4 [packed = true];`, starting at `first`
+fn parse_field<'a>(
+    src: &'a str,
+    first: &Token,
+    toks: &mut Toks,
+) -> Result<Field<'a>, StructParseError> {
+    let mut attrs = Vec::new();
+    let mut first = first.clone();
+    if first.kind != TokenKind::Ident {
+        return Err(StructParseError::unexpected(first));
+    }
+    if let label @ ("repeated" | "optional" | "required") = &src[first.span.clone()] {
+        attrs.push(Attr {
+            name: label,
+            value: None,
+        });
+        first = toks.expect_tok(TokenKind::Ident)?;
+    }
+    let ty = if &src[first.span.clone()] == "map" && toks.eat(TokenKind::Lt) {
+        skip_group(toks, TokenKind::Gt)?;
+        let end = toks.next_tok()?.span.end;
+        Ty::Ident(&src[first.span.start..end])
+    } else {
+        Ty::Ident(parse_dotted(src, &first, toks))
+    };
+    let span = toks.expect_tok(TokenKind::Ident)?.span;
+    let name = &src[span.clone()];
+    toks.expect_tok(TokenKind::Eq)?;
+    attrs.push(Attr {
+        name: "number",
+        value: Some(&src[toks.expect_tok(TokenKind::NumLit)?.span]),
+    });
+    attrs.extend(parse_options(src, toks)?);
+    toks.expect_tok(TokenKind::Semi)?;
+    Ok(Field {
+        name,
+        ty,
+        align: None,
+        offset: None,
+        bits: None,
+        endian: None,
+        span,
+        attrs,
+    })
+}
+
+/// Parse options in brackets, like `[deprecated = true, json_name = "id"]`, if there
+/// are any
+fn parse_options<'a>(src: &'a str, toks: &mut Toks) -> Result<Vec<Attr<'a>>, StructParseError> {
+    let mut attrs = Vec::new();
+    if !toks.eat(TokenKind::LSqBracket) {
+        return Ok(attrs);
+    }
+    while !toks.eat(TokenKind::RSqBracket) {
+        // Custom options, like `(my.option) = 1`
+        let name = if toks.peek_kind() == Some(TokenKind::LParen) {
+            paren_text(src, toks)?
+        } else {
+            let first = toks.expect_tok(TokenKind::Ident)?;
+            parse_dotted(src, &first, toks)
+        };
+        toks.expect_tok(TokenKind::Eq)?;
+        let tok = toks.next_tok()?;
+        let value = match tok.kind {
+            TokenKind::StrLit => str_lit(src, &tok),
+            _ => &src[tok.span],
+        };
+        // Skip the rest of aggregate values, like `{ a: 1 }`
+        while !matches!(
+            toks.peek_kind(),
+            Some(TokenKind::Comma | TokenKind::RSqBracket) | None
+        ) {
+            toks.next();
+        }
+        attrs.push(Attr {
+            name,
+            value: Some(value),
+        });
+        toks.eat(TokenKind::Comma);
+    }
+    Ok(attrs)
+}
+
+/// Parse an enum after its keyword
+fn parse_enum<'a>(
+    src: &'a str,
+    toks: &mut Toks,
+    namespace: &[&'a str],
+) -> Result<Enum<'a>, StructParseError> {
+    let name = &src[toks.expect_tok(TokenKind::Ident)?.span];
+    toks.expect_tok(TokenKind::LBrace)?;
+    let mut attrs = Vec::new();
+    let mut variants = Vec::new();
+    loop {
+        let tok = toks.next_tok()?;
+        match (tok.kind, &src[tok.span.clone()]) {
+            (TokenKind::RBrace, _) => break,
+            (TokenKind::Semi, _) => {}
+            (TokenKind::Ident, "option") => {
+                let first = toks.expect_tok(TokenKind::Ident)?;
+                let name = parse_dotted(src, &first, toks);
+                toks.expect_tok(TokenKind::Eq)?;
+                let value = toks.next_tok()?;
+                attrs.push(Attr {
+                    name,
+                    value: Some(&src[value.span]),
+                });
+                toks.expect_tok(TokenKind::Semi)?;
+            }
+            (TokenKind::Ident, "reserved") => skip_member(toks)?,
+            (TokenKind::Ident, name) => {
+                toks.expect_tok(TokenKind::Eq)?;
+                let value = parse_signed(src, &toks.expect_tok(TokenKind::NumLit)?)?;
+                let attrs = parse_options(src, toks)?;
+                toks.expect_tok(TokenKind::Semi)?;
+                variants.push(Variant { name, value, attrs });
+            }
+            _ => return Err(StructParseError::unexpected(tok)),
+        }
+    }
+    Ok(Enum {
+        name,
+        ty: None,
+        namespace: namespace.to_vec(),
+        attrs,
+        variants,
+    })
+}
@@ -0,0 +1,84 @@
+#![expect(clippy::unwrap_used)]
+
+use {
+    crate::{Item, Struct, Ty},
+    pretty_assertions::assert_eq,
+};
+
+#[test]
+fn parse_proto_schema() {
+    let items = Item::parse_proto(
+        r#"syntax = "proto3";
+        package tutorial.people;
+        import "google/protobuf/timestamp.proto";
+        option go_package = "example.com/people";
+
+        message Person {
+          string name = 1;
+          int32 id = 2 [deprecated = true, json_name = "ID"];
+          repeated PhoneNumber phones = 4;
+          optional google.protobuf.Timestamp updated = 5;
+          map<string, int32> scores = 6;
+          reserved 7, 9 to 11;
+          oneof contact {
+            string email = 8;
+            string pager = 12;
+          }
+
+          enum PhoneType {
+            option allow_alias = true;
+            UNKNOWN = 0;
+            MOBILE = 1;
+            LEGACY = -1 [deprecated = true];
+          }
+          message PhoneNumber {
+            string number = 1;
+            PhoneType type = 2;
+          }
+        }
+
+        service Directory { rpc Find (Person) returns (Person); }"#,
+    )
+    .unwrap();
+    assert_eq!(items.len(), 3);
+    let Item::Struct(person) = &items[0] else {
+        panic!("expected a message");
+    };
+    assert_eq!(person.name, "Person");
+    assert_eq!(person.namespace, ["tutorial", "people"]);
+    assert!(person.attr("message").is_some());
+    let names: Vec<_> = person.fields.iter().map(|f| f.name).collect();
+    assert_eq!(
+        names,
+        [
+            "name", "id", "phones", "updated", "scores", "email", "pager"
+        ]
+    );
+    let id = person.field("id").unwrap();
+    assert_eq!(id.attr("number").unwrap().value, Some("2"));
+    assert_eq!(id.attr("json_name").unwrap().value, Some("ID"));
+    assert!(person.field("phones").unwrap().attr("repeated").is_some());
+    assert_eq!(
+        person.field("updated").unwrap().ty,
+        Ty::Ident("google.protobuf.Timestamp")
+    );
+    assert_eq!(
+        person.field("scores").unwrap().ty,
+        Ty::Ident("map<string, int32>")
+    );
+    assert_eq!(
+        person.field("pager").unwrap().attr("oneof").unwrap().value,
+        Some("contact")
+    );
+    let Item::Enum(phone_type) = &items[1] else {
+        panic!("expected an enum");
+    };
+    assert_eq!(phone_type.namespace, ["tutorial", "people", "Person"]);
+    assert_eq!(phone_type.attr("allow_alias").unwrap().value, Some("true"));
+    assert_eq!(phone_type.variant("LEGACY").unwrap().value, -1);
+    let Item::Struct(number) = &items[2] else {
+        panic!("expected a message");
+    };
+    assert_eq!(number.name, "PhoneNumber");
+    assert_eq!(Struct::parse_proto("message Empty {}").unwrap().fields, []);
+}
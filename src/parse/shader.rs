@@ -0,0 +1,486 @@
+//! GLSL and HLSL frontends, for uniform blocks, constant buffers and structs
+
+use {
+    super::{
+        PeekExt, TokIterExt, Toks,
+        c::{PragmaState, parse_directive, skip_member},
+        parse_int, skip_group,
+    },
+    crate::{
+        Array, Attr, Field, Matrix, Repr, Struct, StructKind, StructParseError,
+        StructParseErrorKind, Ty, Vector,
+        tokenize::{Token, TokenKind},
+    },
+};
+
+#[cfg(test)]
+mod tests;
+
+/// A C-like shading language
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Dialect {
+    Glsl,
+    Hlsl,
+}
+
+/// Prefixes of GLSL vector and matrix names, and the component types they stand for
+const GLSL_PREFIXES: &[(&str, &str)] = &[
+    ("", "float"),
+    ("d", "double"),
+    ("i", "int"),
+    ("u", "uint"),
+    ("b", "bool"),
+];
+
+/// Scalar types that have HLSL vector and matrix forms, like `float4` and `float4x4`
+const HLSL_SCALARS: &[&str] = &[
+    "float",
+    "double",
+    "half",
+    "int",
+    "uint",
+    "dword",
+    "bool",
+    "min16float",
+    "min10float",
+    "min16int",
+    "min12int",
+    "min16uint",
+];
+
+/// Qualifiers of block members, which have no effect on layout
+const MEMBER_QUALIFIERS: &[&str] = &[
+    "highp",
+    "mediump",
+    "lowp",
+    "precise",
+    "invariant",
+    "flat",
+    "smooth",
+    "noperspective",
+    "coherent",
+    "volatile",
+    "restrict",
+    "readonly",
+    "writeonly",
+    "const",
+    "linear",
+    "nointerpolation",
+    "snorm",
+    "unorm",
+];
+
+pub fn parse_glsl<'a>(src: &'a str, tokens: &[Token]) -> Result<Struct<'a>, StructParseError> {
+    let mut toks = tokens.iter().peekable();
+    let mut attrs = Vec::new();
+    loop {
+        let Some(tok) = toks.peek().copied().cloned() else {
+            return Err(StructParseError {
+                span: src.len()..src.len(),
+                kind: StructParseErrorKind::UnexpectedEnd,
+            });
+        };
+        let word = toks.peek_ident(src);
+        match (tok.kind, word) {
+            // `#version` and other directives
+            (TokenKind::Hash, _) => {
+                toks.next();
+                parse_directive(src, &tok, &mut toks, &mut PragmaState::default())?;
+            }
+            (TokenKind::Ident, Some("layout")) => {
+                toks.next();
+                parse_layout(src, &mut toks, &mut attrs)?;
+            }
+            // Storage qualifiers of uniform and shader storage blocks
+            (TokenKind::Ident, Some(word @ ("uniform" | "buffer"))) => {
+                toks.next();
+                attrs.push(Attr {
+                    name: word,
+                    value: None,
+                });
+            }
+            (TokenKind::Ident, Some(word)) if MEMBER_QUALIFIERS.contains(&word) => {
+                toks.next();
+            }
+            (TokenKind::KwStruct, _) => {
+                toks.next();
+                let name = &src[toks.expect_tok(TokenKind::Ident)?.span];
+                let struct_ = finish_struct(src, &mut toks, name, attrs, Dialect::Glsl)?;
+                toks.eat(TokenKind::Semi);
+                return Ok(struct_);
+            }
+            (TokenKind::Ident, Some(name))
+                if attrs.iter().any(|attr| matches!(attr.name, "uniform" | "buffer"))
+                    && toks.clone().nth(1).map(|tok| tok.kind) == Some(TokenKind::LBrace) =>
+            {
+                toks.next();
+                let mut struct_ = finish_struct(src, &mut toks, name, attrs, Dialect::Glsl)?;
+                // The instance name, like `ubo` in `uniform Block { ... } ubo;`
+                if let Some(instance) = toks.next_if(|tok| tok.kind == TokenKind::Ident) {
+                    struct_.attrs.push(Attr {
+                        name: "instance",
+                        value: Some(&src[instance.span.clone()]),
+                    });
+                    if toks.eat(TokenKind::LSqBracket) {
+                        skip_group(&mut toks, TokenKind::RSqBracket)?;
+                        toks.next();
+                    }
+                }
+                toks.eat(TokenKind::Semi);
+                return Ok(struct_);
+            }
+            // Functions, inputs, outputs and opaque uniforms, like `uniform sampler2D tex;`
+            _ => {
+                skip_member(&mut toks)?;
+                attrs.clear();
+            }
+        }
+    }
+}
+
+pub fn parse_hlsl<'a>(src: &'a str, tokens: &[Token]) -> Result<Struct<'a>, StructParseError> {
+    let mut toks = tokens.iter().peekable();
+    loop {
+        let Some(tok) = toks.peek().copied().cloned() else {
+            return Err(StructParseError {
+                span: src.len()..src.len(),
+                kind: StructParseErrorKind::UnexpectedEnd,
+            });
+        };
+        match (tok.kind, toks.peek_ident(src)) {
+            (TokenKind::Hash, _) => {
+                toks.next();
+                parse_directive(src, &tok, &mut toks, &mut PragmaState::default())?;
+            }
+            (TokenKind::Ident, Some(word @ ("cbuffer" | "tbuffer"))) => {
+                toks.next();
+                let name = &src[toks.expect_tok(TokenKind::Ident)?.span];
+                let mut attrs = vec![Attr {
+                    name: word,
+                    value: None,
+                }];
+                // `: register(b0)`
+                if toks.eat(TokenKind::Colon) {
+                    let tok = toks.expect_tok(TokenKind::Ident)?;
+                    attrs.push(Attr {
+                        name: &src[tok.span],
+                        value: Some(paren_text(src, &mut toks)?),
+                    });
+                }
+                let struct_ = finish_struct(src, &mut toks, name, attrs, Dialect::Hlsl)?;
+                toks.eat(TokenKind::Semi);
+                return Ok(struct_);
+            }
+            (TokenKind::KwStruct, _) => {
+                toks.next();
+                let name = &src[toks.expect_tok(TokenKind::Ident)?.span];
+                let struct_ = finish_struct(src, &mut toks, name, Vec::new(), Dialect::Hlsl)?;
+                toks.eat(TokenKind::Semi);
+                return Ok(struct_);
+            }
+            // Functions, resources and other declarations
+            _ => skip_member(&mut toks)?,
+        }
+    }
+}
+
+/// Parse the body of a block or struct, after its name
+fn finish_struct<'a>(
+    src: &'a str,
+    toks: &mut Toks,
+    name: &'a str,
+    attrs: Vec<Attr<'a>>,
+    dialect: Dialect,
+) -> Result<Struct<'a>, StructParseError> {
+    let row_major = attrs.iter().any(|attr| attr.name == "row_major");
+    toks.expect_tok(TokenKind::LBrace)?;
+    let fields = parse_members(src, toks, dialect, row_major)?;
+    Ok(Struct {
+        name,
+        kind: StructKind::Struct,
+        repr: Repr::default(),
+        namespace: Vec::new(),
+        attrs,
+        fields,
+    })
+}
+
+/// Parse the text inside of parentheses, like the `b0` of `register(b0)`
+pub(super) fn paren_text<'a>(src: &'a str, toks: &mut Toks) -> Result<&'a str, StructParseError> {
+    let open = toks.expect_tok(TokenKind::LParen)?;
+    skip_group(toks, TokenKind::RParen)?;
+    let close = toks.next_tok()?;
+    Ok(src[open.span.end..close.span.start].trim())
+}
+
+/// Parse a GLSL layout qualifier after `layout`, like `(std140, binding = 0)`
+fn parse_layout<'a>(
+    src: &'a str,
+    toks: &mut Toks,
+    attrs: &mut Vec<Attr<'a>>,
+) -> Result<(), StructParseError> {
+    toks.expect_tok(TokenKind::LParen)?;
+    while !toks.eat(TokenKind::RParen) {
+        let name = &src[toks.expect_tok(TokenKind::Ident)?.span];
+        let value = if toks.eat(TokenKind::Eq) {
+            Some(&src[toks.next_tok()?.span])
+        } else {
+            None
+        };
+        attrs.push(Attr { name, value });
+        toks.eat(TokenKind::Comma);
+    }
+    Ok(())
+}
+
+/// Parse the members of a block or struct, after the opening brace
+///
+/// Layout qualifiers and HLSL semantics, like `packoffset(c0)`, become attributes.
+fn parse_members<'a>(
+    src: &'a str,
+    toks: &mut Toks,
+    dialect: Dialect,
+    row_major: bool,
+) -> Result<Vec<Field<'a>>, StructParseError> {
+    let mut fields = Vec::new();
+    loop {
+        if toks.eat(TokenKind::RBrace) {
+            return Ok(fields);
+        }
+        if !toks.eat(TokenKind::Semi) {
+            parse_member(src, toks, dialect, row_major, &mut fields)?;
+        }
+    }
+}
+
+fn parse_member<'a>(
+    src: &'a str,
+    toks: &mut Toks,
+    dialect: Dialect,
+    mut row_major: bool,
+    fields: &mut Vec<Field<'a>>,
+) -> Result<(), StructParseError> {
+    let mut attrs = Vec::new();
+    loop {
+        match toks.peek_ident(src) {
+            Some("layout") => {
+                toks.next();
+                parse_layout(src, toks, &mut attrs)?;
+            }
+            Some("row_major") => {
+                toks.next();
+                row_major = true;
+            }
+            Some("column_major") => {
+                toks.next();
+                row_major = false;
+            }
+            Some(word) if MEMBER_QUALIFIERS.contains(&word) => {
+                toks.next();
+            }
+            _ => break,
+        }
+    }
+    for attr in &attrs {
+        match attr.name {
+            "row_major" => row_major = true,
+            "column_major" => row_major = false,
+            _ => {}
+        }
+    }
+    let ty = if toks.eat(TokenKind::KwStruct) {
+        let name = toks.next_if(|tok| tok.kind == TokenKind::Ident);
+        toks.expect_tok(TokenKind::LBrace)?;
+        let mut inner = Struct {
+            name: name.map_or("", |tok| &src[tok.span.clone()]),
+            kind: StructKind::Struct,
+            repr: Repr::default(),
+            namespace: Vec::new(),
+            attrs: Vec::new(),
+            fields: Vec::new(),
+        };
+        inner.fields = parse_members(src, toks, dialect, row_major)?;
+        Ty::Inline(Box::new(inner))
+    } else {
+        parse_ty(src, toks, dialect, row_major)?
+    };
+    loop {
+        let span = toks.expect_tok(TokenKind::Ident)?.span;
+        let name = &src[span.clone()];
+        let lens = parse_array_lens(src, toks)?;
+        let mut field_attrs = attrs.clone();
+        // HLSL semantics and register bindings, like `: packoffset(c0.y)`
+        while toks.eat(TokenKind::Colon) {
+            let tok = toks.expect_tok(TokenKind::Ident)?;
+            field_attrs.push(if toks.peek_kind() == Some(TokenKind::LParen) {
+                Attr {
+                    name: &src[tok.span],
+                    value: Some(paren_text(src, toks)?),
+                }
+            } else {
+                Attr {
+                    name: "semantic",
+                    value: Some(&src[tok.span]),
+                }
+            });
+        }
+        // Default values of HLSL constants
+        if toks.eat(TokenKind::Eq) {
+            while !matches!(
+                toks.peek_kind(),
+                Some(TokenKind::Comma | TokenKind::Semi) | None
+            ) {
+                toks.next();
+            }
+        }
+        let ty = lens.iter().rev().fold(ty.clone(), |ty, &len| {
+            Ty::Array(Array {
+                ty: Box::new(ty),
+                len: len.unwrap_or(0),
+                flexible: len.is_none(),
+            })
+        });
+        fields.push(Field {
+            name,
+            ty,
+            align: None,
+            offset: None,
+            bits: None,
+            endian: None,
+            span,
+            attrs: field_attrs,
+        });
+        if !toks.eat(TokenKind::Comma) {
+            toks.expect_tok(TokenKind::Semi)?;
+            return Ok(());
+        }
+    }
+}
+
+/// Parse the array lengths after the name of a member, like the `[2][]` of `a[2][]`
+///
+/// Runtime-sized arrays, at the end of shader storage blocks, have no length.
+fn parse_array_lens(src: &str, toks: &mut Toks) -> Result<Vec<Option<u64>>, StructParseError> {
+    let mut lens = Vec::new();
+    while toks.eat(TokenKind::LSqBracket) {
+        if toks.eat(TokenKind::RSqBracket) {
+            lens.push(None);
+            continue;
+        }
+        lens.push(Some(parse_int(src, &toks.expect_tok(TokenKind::NumLit)?)?));
+        toks.expect_tok(TokenKind::RSqBracket)?;
+    }
+    Ok(lens)
+}
+
+/// Parse a type name, decoding vector and matrix types
+fn parse_ty<'a>(
+    src: &'a str,
+    toks: &mut Toks,
+    dialect: Dialect,
+    row_major: bool,
+) -> Result<Ty<'a>, StructParseError> {
+    let tok = toks.expect_tok(TokenKind::Ident)?;
+    let name = &src[tok.span.clone()];
+    let ty = match dialect {
+        Dialect::Glsl => glsl_vector_or_matrix(name),
+        // `vector<float, 4>` and `matrix<float, 4, 4>`
+        Dialect::Hlsl if matches!(name, "vector" | "matrix") && toks.eat(TokenKind::Lt) => {
+            let scalar = &src[toks.expect_tok(TokenKind::Ident)?.span];
+            let mut dims = Vec::new();
+            while toks.eat(TokenKind::Comma) {
+                let tok = toks.expect_tok(TokenKind::NumLit)?;
+                dims.push(dim(&src[tok.span.clone()]).ok_or(StructParseError::unexpected(tok))?);
+            }
+            let tok = toks.expect_tok(TokenKind::Gt)?;
+            match *dims.as_slice() {
+                [len] => Some(hlsl_vector(scalar, len)),
+                [rows, columns] if rows > 1 && columns > 1 => Some(matrix(scalar, columns, rows)),
+                _ => return Err(StructParseError::unexpected(tok)),
+            }
+        }
+        Dialect::Hlsl => hlsl_vector_or_matrix(name),
+    };
+    Ok(match ty {
+        Some(Ty::Matrix(matrix)) => Ty::Matrix(Matrix {
+            row_major,
+            ..matrix
+        }),
+        Some(ty) => ty,
+        None => Ty::Ident(name),
+    })
+}
+
+fn dim(text: &str) -> Option<u8> {
+    match text {
+        "1" => Some(1),
+        "2" => Some(2),
+        "3" => Some(3),
+        "4" => Some(4),
+        _ => None,
+    }
+}
+
+fn vector(scalar: &str, len: u8) -> Ty<'_> {
+    Ty::Vector(Vector {
+        ty: Box::new(Ty::Ident(scalar)),
+        len,
+    })
+}
+
+/// An HLSL vector, where one component vectors like `float1` are laid out like
+/// their scalar
+fn hlsl_vector(scalar: &str, len: u8) -> Ty<'_> {
+    match len {
+        1 => Ty::Ident(scalar),
+        _ => vector(scalar, len),
+    }
+}
+
+fn matrix(scalar: &str, columns: u8, rows: u8) -> Ty<'_> {
+    Ty::Matrix(Matrix {
+        ty: Box::new(Ty::Ident(scalar)),
+        columns,
+        rows,
+        row_major: false,
+    })
+}
+
+/// Decode a GLSL vector or matrix type name, like `vec4`, `uvec2` or `dmat4x3`
+///
+/// GLSL matrix names give the number of columns first.
+fn glsl_vector_or_matrix(name: &str) -> Option<Ty<'static>> {
+    GLSL_PREFIXES.iter().find_map(|&(prefix, scalar)| {
+        let rest = name.strip_prefix(prefix)?;
+        if let Some(len) = rest.strip_prefix("vec") {
+            return Some(vector(scalar, dim(len).filter(|&len| len > 1)?));
+        }
+        let dims = rest.strip_prefix("mat")?;
+        if !matches!(scalar, "float" | "double") {
+            return None;
+        }
+        let (columns, rows) = dims.split_once('x').unwrap_or((dims, dims));
+        Some(matrix(
+            scalar,
+            dim(columns).filter(|&len| len > 1)?,
+            dim(rows).filter(|&len| len > 1)?,
+        ))
+    })
+}
+
+/// Decode an HLSL vector or matrix type name, like `float4` or `half3x4`
+///
+/// HLSL matrix names give the number of rows first.
+fn hlsl_vector_or_matrix(name: &str) -> Option<Ty<'static>> {
+    HLSL_SCALARS.iter().find_map(|&scalar| {
+        let dims = name.strip_prefix(scalar)?;
+        match dims.split_once('x') {
+            Some((rows, columns)) => Some(matrix(
+                scalar,
+                dim(columns).filter(|&len| len > 1)?,
+                dim(rows).filter(|&len| len > 1)?,
+            )),
+            None => Some(hlsl_vector(scalar, dim(dims)?)),
+        }
+    })
+}
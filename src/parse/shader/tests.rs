@@ -0,0 +1,117 @@
+#![expect(clippy::unwrap_used)]
+
+use {
+    crate::{Array, Matrix, Struct, Ty, Vector},
+    pretty_assertions::assert_eq,
+};
+
+fn vector(ty: &str, len: u8) -> Ty<'_> {
+    Ty::Vector(Vector {
+        ty: Box::new(Ty::Ident(ty)),
+        len,
+    })
+}
+
+fn matrix(ty: &str, columns: u8, rows: u8, row_major: bool) -> Ty<'_> {
+    Ty::Matrix(Matrix {
+        ty: Box::new(Ty::Ident(ty)),
+        columns,
+        rows,
+        row_major,
+    })
+}
+
+#[test]
+fn parse_glsl_uniform_block() {
+    let s = Struct::parse_glsl(
+        "#version 450 core
+        precision highp float;
+        layout(location = 0) in vec3 pos;
+        uniform sampler2D tex;
+
+        layout(std140, binding = 0) uniform Block {
+            vec4 a;
+            mat4 m;
+            layout(row_major) dmat2x3 d;
+            ivec2 i, j;
+            float weights[4][2];
+        } ubo;",
+    )
+    .unwrap();
+    assert_eq!(s.name, "Block");
+    assert!(s.attr("std140").is_some());
+    assert_eq!(s.attr("binding").unwrap().value, Some("0"));
+    assert_eq!(s.attr("instance").unwrap().value, Some("ubo"));
+    assert_eq!(s.field("a").unwrap().ty, vector("float", 4));
+    assert_eq!(s.field("m").unwrap().ty, matrix("float", 4, 4, false));
+    assert_eq!(s.field("d").unwrap().ty, matrix("double", 2, 3, true));
+    assert_eq!(s.field("j").unwrap().ty, vector("int", 2));
+    assert_eq!(
+        s.field("weights").unwrap().ty,
+        Ty::Array(Array {
+            ty: Box::new(Ty::Array(Array {
+                ty: Box::new(Ty::Ident("float")),
+                len: 2,
+                flexible: false,
+            })),
+            len: 4,
+            flexible: false,
+        })
+    );
+}
+
+#[test]
+fn parse_glsl_struct_and_storage_block() {
+    let s = Struct::parse_glsl("struct Light { vec3 pos; float radius; };").unwrap();
+    assert_eq!(s.name, "Light");
+    assert_eq!(s.fields.len(), 2);
+    let s = Struct::parse_glsl(
+        "layout(std430, row_major) readonly buffer Lights { mat3x4 m; Light lights[]; };",
+    )
+    .unwrap();
+    assert!(s.attr("buffer").is_some());
+    assert_eq!(s.field("m").unwrap().ty, matrix("float", 3, 4, true));
+    assert_eq!(
+        s.field("lights").unwrap().ty,
+        Ty::Array(Array {
+            ty: Box::new(Ty::Ident("Light")),
+            len: 0,
+            flexible: true,
+        })
+    );
+}
+
+#[test]
+fn parse_hlsl_cbuffer() {
+    let s = Struct::parse_hlsl(
+        "Texture2D tex : register(t0);
+        float4 main() : SV_Target { return 0; }
+
+        cbuffer PerFrame : register(b0)
+        {
+            float4x4 view;
+            row_major float3x4 model : packoffset(c4);
+            matrix<float, 2, 3> small;
+            float3 light_dir;
+            half2 uv = 0;
+            uint flags[2];
+            float1 scale;
+            vector<int, 1> index;
+        }",
+    )
+    .unwrap();
+    assert_eq!(s.name, "PerFrame");
+    assert!(s.attr("cbuffer").is_some());
+    assert_eq!(s.attr("register").unwrap().value, Some("b0"));
+    assert_eq!(s.field("view").unwrap().ty, matrix("float", 4, 4, false));
+    let model = s.field("model").unwrap();
+    // HLSL gives the rows first
+    assert_eq!(model.ty, matrix("float", 4, 3, true));
+    assert_eq!(model.attr("packoffset").unwrap().value, Some("c4"));
+    assert_eq!(s.field("small").unwrap().ty, matrix("float", 3, 2, false));
+    assert_eq!(s.field("light_dir").unwrap().ty, vector("float", 3));
+    assert_eq!(s.field("uv").unwrap().ty, vector("half", 2));
+    assert_eq!(s.field("scale").unwrap().ty, Ty::Ident("float"));
+    assert_eq!(s.field("index").unwrap().ty, Ty::Ident("int"));
+    assert_eq!(s.fields.len(), 8);
+}
@@ -1,7 +1,7 @@
 #![expect(clippy::unwrap_used)]
 
 use {
-    crate::{Array, Field, Struct, Ty},
+    crate::{Array, Attr, Endian, Field, Repr, ReprKind, Struct, StructKind, Ty},
     pretty_assertions::assert_eq,
 };
 
@@ -11,7 +11,11 @@ fn parse_struct_empty() {
         Struct::parse("struct Empty { }").unwrap(),
         Struct {
             fields: vec![],
-            name: "Empty"
+            name: "Empty",
+            kind: StructKind::Struct,
+            repr: Repr::default(),
+            namespace: vec![],
+            attrs: vec![],
         }
     );
 }
@@ -26,7 +30,11 @@ fn parse_struct_empty_multiline() {
         .unwrap(),
         Struct {
             fields: vec![],
-            name: "Foo"
+            name: "Foo",
+            kind: StructKind::Struct,
+            repr: Repr::default(),
+            namespace: vec![],
+            attrs: vec![],
         }
     );
 }
@@ -38,9 +46,19 @@ fn parse_struct_with_single_field() {
         Struct {
             fields: vec![Field {
                 name: "field",
-                ty: Ty::Ident("u32")
+                ty: Ty::Ident("u32"),
+                align: None,
+                offset: None,
+                bits: None,
+                endian: None,
+                span: 16..21,
+                attrs: vec![],
             }],
-            name: "Single"
+            name: "Single",
+            kind: StructKind::Struct,
+            repr: Repr::default(),
+            namespace: vec![],
+            attrs: vec![],
         }
     );
 }
@@ -54,10 +72,21 @@ fn parse_struct_with_single_array_field() {
                 name: "field",
                 ty: Ty::Array(Array {
                     ty: Box::new(Ty::Ident("u32")),
-                    len: 10
+                    len: 10,
+                    flexible: false,
                 }),
+                align: None,
+                offset: None,
+                bits: None,
+                endian: None,
+                span: 18..23,
+                attrs: vec![],
             }],
-            name: "HasArray"
+            name: "HasArray",
+            kind: StructKind::Struct,
+            repr: Repr::default(),
+            namespace: vec![],
+            attrs: vec![],
         }
     );
 }
@@ -71,12 +100,23 @@ fn field_with_trailing_comma() {
         Struct::parse(input).unwrap(),
         Struct {
             name: "Foo",
+            kind: StructKind::Struct,
+            repr: Repr::default(),
+            namespace: vec![],
+            attrs: vec![],
             fields: vec![Field {
                 name: "field",
                 ty: Ty::Array(Array {
                     ty: Box::new(Ty::Ident("u8")),
-                    len: 10
-                })
+                    len: 10,
+                    flexible: false,
+                }),
+                align: None,
+                offset: None,
+                bits: None,
+                endian: None,
+                span: 17..22,
+                attrs: vec![],
             }]
         }
     );
@@ -92,18 +132,36 @@ fn parse_struct_with_multi_array_field_singleline() {
                     name: "field",
                     ty: Ty::Array(Array {
                         ty: Box::new(Ty::Ident("u32")),
-                        len: 10
+                        len: 10,
+                        flexible: false,
                     }),
+                    align: None,
+                    offset: None,
+                    bits: None,
+                    endian: None,
+                    span: 26..31,
+                    attrs: vec![],
                 },
                 Field {
                     name: "field2",
                     ty: Ty::Array(Array {
                         ty: Box::new(Ty::Ident("u64")),
-                        len: 32
+                        len: 32,
+                        flexible: false,
                     }),
+                    align: None,
+                    offset: None,
+                    bits: None,
+                    endian: None,
+                    span: 44..50,
+                    attrs: vec![],
                 }
             ],
-            name: "IHaveArrayFields"
+            name: "IHaveArrayFields",
+            kind: StructKind::Struct,
+            repr: Repr::default(),
+            namespace: vec![],
+            attrs: vec![],
         }
     );
 }
@@ -125,18 +183,36 @@ fn parse_struct_with_multi_array_field_multiline() {
                     name: "field",
                     ty: Ty::Array(Array {
                         ty: Box::new(Ty::Ident("u32")),
-                        len: 10
+                        len: 10,
+                        flexible: false,
                     }),
+                    align: None,
+                    offset: None,
+                    bits: None,
+                    endian: None,
+                    span: 47..52,
+                    attrs: vec![],
                 },
                 Field {
                     name: "field2",
                     ty: Ty::Array(Array {
                         ty: Box::new(Ty::Ident("u64")),
-                        len: 32
+                        len: 32,
+                        flexible: false,
                     }),
+                    align: None,
+                    offset: None,
+                    bits: None,
+                    endian: None,
+                    span: 77..83,
+                    attrs: vec![],
                 }
             ],
-            name: "IHaveArrayFields"
+            name: "IHaveArrayFields",
+            kind: StructKind::Struct,
+            repr: Repr::default(),
+            namespace: vec![],
+            attrs: vec![],
         }
     );
 }
@@ -149,14 +225,30 @@ fn parse_struct_with_multi_fields_singleline() {
             fields: vec![
                 Field {
                     name: "field",
-                    ty: Ty::Ident("u32")
+                    ty: Ty::Ident("u32"),
+                    align: None,
+                    offset: None,
+                    bits: None,
+                    endian: None,
+                    span: 17..22,
+                    attrs: vec![],
                 },
                 Field {
                     name: "field2",
-                    ty: Ty::Ident("u32")
+                    ty: Ty::Ident("u32"),
+                    align: None,
+                    offset: None,
+                    bits: None,
+                    endian: None,
+                    span: 30..36,
+                    attrs: vec![],
                 }
             ],
-            name: "MultiSl"
+            name: "MultiSl",
+            kind: StructKind::Struct,
+            repr: Repr::default(),
+            namespace: vec![],
+            attrs: vec![],
         }
     );
 }
@@ -175,14 +267,207 @@ fn parse_struct_with_multi_fields_multiline() {
             fields: vec![
                 Field {
                     name: "field",
-                    ty: Ty::Ident("u32")
+                    ty: Ty::Ident("u32"),
+                    align: None,
+                    offset: None,
+                    bits: None,
+                    endian: None,
+                    span: 25..30,
+                    attrs: vec![],
                 },
                 Field {
                     name: "field2",
-                    ty: Ty::Ident("u32")
+                    ty: Ty::Ident("u32"),
+                    align: None,
+                    offset: None,
+                    bits: None,
+                    endian: None,
+                    span: 49..55,
+                    attrs: vec![],
                 }
             ],
-            name: "Foo"
+            name: "Foo",
+            kind: StructKind::Struct,
+            repr: Repr::default(),
+            namespace: vec![],
+            attrs: vec![],
+        }
+    );
+}
+
+#[test]
+fn parse_struct_with_pointer_fields() {
+    assert_eq!(
+        Struct::parse("struct Ptrs { a: *const u8, b: *mut [u32; 0x10] }").unwrap(),
+        Struct {
+            fields: vec![
+                Field {
+                    name: "a",
+                    ty: Ty::Pointer(Box::new(Ty::Ident("u8"))),
+                    align: None,
+                    offset: None,
+                    bits: None,
+                    endian: None,
+                    span: 14..15,
+                    attrs: vec![],
+                },
+                Field {
+                    name: "b",
+                    ty: Ty::Pointer(Box::new(Ty::Array(Array {
+                        ty: Box::new(Ty::Ident("u32")),
+                        len: 16,
+                        flexible: false,
+                    }))),
+                    align: None,
+                    offset: None,
+                    bits: None,
+                    endian: None,
+                    span: 28..29,
+                    attrs: vec![],
+                }
+            ],
+            name: "Ptrs",
+            kind: StructKind::Struct,
+            repr: Repr::default(),
+            namespace: vec![],
+            attrs: vec![],
+        }
+    );
+}
+
+#[test]
+fn parse_union_is_a_contextual_keyword() {
+    let s = Struct::parse("struct S { union: u8, u: union { a: u8, b: u16 } }").unwrap();
+    assert_eq!(s.kind, StructKind::Struct);
+    assert_eq!(
+        (s.fields[0].name, &s.fields[0].ty),
+        ("union", &Ty::Ident("u8"))
+    );
+    let Ty::Inline(inner) = &s.fields[1].ty else {
+        panic!("not inline: {:?}", s.fields[1].ty);
+    };
+    assert_eq!(inner.kind, StructKind::Union);
+}
+
+#[test]
+fn parse_union_with_unnamed_struct_field() {
+    let u = Struct::parse("union Reg { raw: u32, _: struct { lo: u16, hi: u16 } }").unwrap();
+    assert_eq!(u.kind, StructKind::Union);
+    assert_eq!(
+        u.fields[1],
+        Field {
+            name: "",
+            ty: Ty::Inline(Box::new(Struct {
+                name: "",
+                kind: StructKind::Struct,
+                repr: Repr::default(),
+                namespace: vec![],
+                attrs: vec![],
+                fields: vec![
+                    Field {
+                        name: "lo",
+                        ty: Ty::Ident("u16"),
+                        align: None,
+                        offset: None,
+                        bits: None,
+                        endian: None,
+                        span: 34..36,
+                        attrs: vec![],
+                    },
+                    Field {
+                        name: "hi",
+                        ty: Ty::Ident("u16"),
+                        align: None,
+                        offset: None,
+                        bits: None,
+                        endian: None,
+                        span: 43..45,
+                        attrs: vec![],
+                    }
+                ]
+            })),
+            align: None,
+            offset: None,
+            bits: None,
+            endian: None,
+            span: 22..23,
+            attrs: vec![],
         }
     );
+    assert_eq!(u.field("hi").unwrap().ty, Ty::Ident("u16"));
+    assert_eq!(u.field_path("hi").unwrap(), [1, 1]);
+}
+
+#[test]
+fn parse_struct_with_endian_attributes() {
+    let s = Struct::parse(
+        "#[endian(big)]
+        #[repr(C)]
+        struct Header { magic: u32, #[endian = \"little\"] len: u16, #[doc = \"x\"] crc: u16 }",
+    )
+    .unwrap();
+    assert_eq!(s.repr.endian, Some(Endian::Big));
+    assert_eq!(s.attrs, []);
+    let endians: Vec<_> = s.fields.iter().map(|f| f.endian).collect();
+    assert_eq!(endians, [None, Some(Endian::Little), None]);
+    assert_eq!(s.fields[2].attrs.len(), 1);
+    assert!(Struct::parse("#[endian(middle)] struct S {}").is_err());
+}
+
+#[test]
+fn parse_struct_with_repr_attributes() {
+    let s = Struct::parse(
+        "#[derive(Debug, Clone)]
+        #[repr(C, packed(2))]
+        #[repr(align(8))]
+        struct Packed { a: u8, b: u32 }",
+    )
+    .unwrap();
+    assert_eq!(
+        s.repr,
+        Repr {
+            kind: ReprKind::C,
+            packed: Some(2),
+            align: Some(8),
+            endian: None,
+        }
+    );
+    assert_eq!(
+        Struct::parse("#[repr(packed)] struct P {}").unwrap().repr.packed,
+        Some(1)
+    );
+    assert!(Struct::parse("#[repr(simd)] struct P {}").is_err());
+}
+
+#[test]
+fn parse_struct_keeps_other_attributes() {
+    let s = Struct::parse(
+        r#"#[derive(Debug, Clone)]
+        #[doc = "A header"]
+        struct Header {
+            #[serde(rename = "m")]
+            magic: u32,
+            #[deprecated]
+            len: u32,
+        }"#,
+    )
+    .unwrap();
+    assert_eq!(
+        s.attrs,
+        [
+            Attr {
+                name: "derive",
+                value: Some("Debug, Clone")
+            },
+            Attr {
+                name: "doc",
+                value: Some("A header")
+            }
+        ]
+    );
+    assert_eq!(
+        s.fields[0].attr("serde").unwrap().value,
+        Some(r#"rename = "m""#)
+    );
+    assert_eq!(s.fields[1].attr("deprecated").unwrap().value, None);
 }
@@ -0,0 +1,187 @@
+//! WGSL frontend, for struct declarations
+
+use {
+    super::{
+        PeekExt, TokIterExt, Toks, c::skip_member, parse_int, parse_paren_int, shader::paren_text,
+    },
+    crate::{
+        Array, Attr, Field, Matrix, Repr, Struct, StructKind, StructParseError,
+        StructParseErrorKind, Ty, Vector,
+        tokenize::{Token, TokenKind},
+    },
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Suffixes of shorthand vector and matrix names, like the `f` of `vec4f`
+const SHORTHAND_SUFFIXES: &[(&str, &str)] =
+    &[("f", "f32"), ("h", "f16"), ("i", "i32"), ("u", "u32")];
+
+pub fn parse_struct<'a>(src: &'a str, tokens: &[Token]) -> Result<Struct<'a>, StructParseError> {
+    let mut toks = tokens.iter().peekable();
+    loop {
+        match toks.peek_kind() {
+            Some(TokenKind::KwStruct) => {
+                toks.next();
+                let name = &src[toks.expect_tok(TokenKind::Ident)?.span];
+                toks.expect_tok(TokenKind::LBrace)?;
+                let fields = parse_fields(src, &mut toks)?;
+                toks.eat(TokenKind::Semi);
+                return Ok(Struct {
+                    name,
+                    kind: StructKind::Struct,
+                    repr: Repr::default(),
+                    namespace: Vec::new(),
+                    attrs: Vec::new(),
+                    fields,
+                });
+            }
+            // Functions, bindings like `@group(0) @binding(0) var<uniform> u: U;` and
+            // other declarations
+            Some(_) => skip_member(&mut toks)?,
+            None => {
+                return Err(StructParseError {
+                    span: src.len()..src.len(),
+                    kind: StructParseErrorKind::UnexpectedEnd,
+                });
+            }
+        }
+    }
+}
+
+/// Whether `tok` is the name of an attribute, like `align` in `@align(16)`
+///
+/// The `@` is ignored by the tokenizer, so this checks the source text.
+fn is_attr(src: &str, tok: &Token) -> bool {
+    tok.span.start > 0 && src.as_bytes()[tok.span.start - 1] == b'@'
+}
+
+/// Parse the members of a struct, after the opening brace
+///
+/// `@align(N)` sets the alignment of a member, other attributes like `@size(N)` are
+/// kept as field attributes.
+fn parse_fields<'a>(src: &'a str, toks: &mut Toks) -> Result<Vec<Field<'a>>, StructParseError> {
+    let mut fields = Vec::new();
+    let mut align = None;
+    let mut attrs = Vec::new();
+    loop {
+        let tok = toks.next_tok()?;
+        match tok.kind {
+            TokenKind::RBrace => return Ok(fields),
+            TokenKind::Comma => {}
+            TokenKind::Ident if is_attr(src, &tok) => match &src[tok.span.clone()] {
+                "align" => align = Some(parse_paren_int(src, toks)?),
+                name => {
+                    let value = if toks.peek_kind() == Some(TokenKind::LParen) {
+                        Some(paren_text(src, toks)?)
+                    } else {
+                        None
+                    };
+                    attrs.push(Attr { name, value });
+                }
+            },
+            TokenKind::Ident => {
+                toks.expect_tok(TokenKind::Colon)?;
+                fields.push(Field {
+                    name: &src[tok.span.clone()],
+                    ty: parse_ty(src, toks)?,
+                    align: align.take(),
+                    offset: None,
+                    bits: None,
+                    endian: None,
+                    span: tok.span,
+                    attrs: std::mem::take(&mut attrs),
+                });
+            }
+            _ => return Err(StructParseError::unexpected(tok)),
+        }
+    }
+}
+
+fn parse_ty<'a>(src: &'a str, toks: &mut Toks) -> Result<Ty<'a>, StructParseError> {
+    let tok = toks.expect_tok(TokenKind::Ident)?;
+    let name = &src[tok.span.clone()];
+    if toks.peek_kind() != Some(TokenKind::Lt) {
+        return Ok(shorthand(name).unwrap_or(Ty::Ident(name)));
+    }
+    toks.next();
+    let ty = if let Some(len) = name.strip_prefix("vec") {
+        let len = dim(len).ok_or(StructParseError::unexpected(tok))?;
+        Ty::Vector(Vector {
+            ty: Box::new(parse_ty(src, toks)?),
+            len,
+        })
+    } else if let Some(dims) = name.strip_prefix("mat") {
+        let (columns, rows) = dims
+            .split_once('x')
+            .and_then(|(columns, rows)| Some((dim(columns)?, dim(rows)?)))
+            .ok_or(StructParseError::unexpected(tok))?;
+        Ty::Matrix(Matrix {
+            ty: Box::new(parse_ty(src, toks)?),
+            columns,
+            rows,
+            row_major: false,
+        })
+    } else {
+        match name {
+            // Runtime-sized arrays have no length
+            "array" => {
+                let ty = parse_ty(src, toks)?;
+                let len = if toks.eat(TokenKind::Comma) {
+                    Some(parse_int(src, &toks.expect_tok(TokenKind::NumLit)?)?)
+                } else {
+                    None
+                };
+                Ty::Array(Array {
+                    ty: Box::new(ty),
+                    len: len.unwrap_or(0),
+                    flexible: len.is_none(),
+                })
+            }
+            // Atomics have the same layout as the integer they wrap
+            "atomic" => parse_ty(src, toks)?,
+            _ => {
+                return Err(StructParseError {
+                    span: tok.span,
+                    kind: StructParseErrorKind::Unsupported("generic types"),
+                });
+            }
+        }
+    };
+    toks.expect_tok(TokenKind::Gt)?;
+    Ok(ty)
+}
+
+fn dim(text: &str) -> Option<u8> {
+    match text {
+        "2" => Some(2),
+        "3" => Some(3),
+        "4" => Some(4),
+        _ => None,
+    }
+}
+
+/// Decode a predeclared vector or matrix alias, like `vec4f` or `mat4x4h`
+fn shorthand(name: &str) -> Option<Ty<'static>> {
+    SHORTHAND_SUFFIXES.iter().find_map(|&(suffix, scalar)| {
+        let name = name.strip_suffix(suffix)?;
+        let scalar = Box::new(Ty::Ident(scalar));
+        if let Some(len) = name.strip_prefix("vec") {
+            return Some(Ty::Vector(Vector {
+                ty: scalar,
+                len: dim(len)?,
+            }));
+        }
+        let (columns, rows) = name.strip_prefix("mat")?.split_once('x')?;
+        if !scalar.primitive().is_some_and(crate::Primitive::is_float) {
+            return None;
+        }
+        Some(Ty::Matrix(Matrix {
+            ty: scalar,
+            columns: dim(columns)?,
+            rows: dim(rows)?,
+            row_major: false,
+        }))
+    })
+}
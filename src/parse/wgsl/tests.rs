@@ -0,0 +1,73 @@
+#![expect(clippy::unwrap_used)]
+
+use {
+    crate::{Array, Matrix, Struct, Ty, Vector},
+    pretty_assertions::assert_eq,
+};
+
+#[test]
+fn parse_wgsl_struct() {
+    let s = Struct::parse_wgsl(
+        "@group(0) @binding(0) var<uniform> u: Uniforms;
+
+        struct Uniforms {
+            a: vec4<f32>,
+            @align(16) b: f32,
+            @size(32) m: mat4x3<f32>,
+            c: vec2u,
+            lights: array<vec4f, 4>,
+            counter: atomic<u32>,
+            rest: array<f32>,
+        }
+
+        @vertex fn main() -> @builtin(position) vec4f { return vec4f(); }",
+    )
+    .unwrap();
+    assert_eq!(s.name, "Uniforms");
+    assert_eq!(
+        s.field("a").unwrap().ty,
+        Ty::Vector(Vector {
+            ty: Box::new(Ty::Ident("f32")),
+            len: 4
+        })
+    );
+    assert_eq!(s.field("b").unwrap().align, Some(16));
+    let m = s.field("m").unwrap();
+    assert_eq!(m.attr("size").unwrap().value, Some("32"));
+    assert_eq!(
+        m.ty,
+        Ty::Matrix(Matrix {
+            ty: Box::new(Ty::Ident("f32")),
+            columns: 4,
+            rows: 3,
+            row_major: false,
+        })
+    );
+    assert_eq!(
+        s.field("c").unwrap().ty,
+        Ty::Vector(Vector {
+            ty: Box::new(Ty::Ident("u32")),
+            len: 2
+        })
+    );
+    assert_eq!(
+        s.field("lights").unwrap().ty,
+        Ty::Array(Array {
+            ty: Box::new(Ty::Vector(Vector {
+                ty: Box::new(Ty::Ident("f32")),
+                len: 4,
+            })),
+            len: 4,
+            flexible: false,
+        })
+    );
+    assert_eq!(s.field("counter").unwrap().ty, Ty::Ident("u32"));
+    assert_eq!(
+        s.field("rest").unwrap().ty,
+        Ty::Array(Array {
+            ty: Box::new(Ty::Ident("f32")),
+            len: 0,
+            flexible: true,
+        })
+    );
+}
@@ -0,0 +1,272 @@
+//! Zig frontend, for `extern`, `packed` and plain container declarations
+
+use {
+    super::{
+        PeekExt, TokIterExt, Toks, c::skip_member, parse_dotted, parse_int, skip_group, str_lit,
+    },
+    crate::{
+        Array, Field, FnPtr, Primitive, Repr, ReprKind, Struct, StructParseError,
+        StructParseErrorKind, Ty,
+        tokenize::{Token, TokenKind},
+    },
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Words that start a declaration inside of a container, instead of a field
+const DECL_WORDS: &[&str] = &[
+    "pub",
+    "fn",
+    "const",
+    "var",
+    "comptime",
+    "usingnamespace",
+    "test",
+    "export",
+    "inline",
+];
+
+pub fn parse_struct<'a>(src: &'a str, tokens: &[Token]) -> Result<Struct<'a>, StructParseError> {
+    let mut toks = tokens.iter().peekable();
+    loop {
+        if toks.peek().is_none() {
+            return Err(StructParseError {
+                span: src.len()..src.len(),
+                kind: StructParseErrorKind::UnexpectedEnd,
+            });
+        }
+        if toks.peek_ident(src) == Some("pub") {
+            toks.next();
+        }
+        let mut ahead = toks.clone();
+        if ahead.peek_ident(src) == Some("const")
+            && ahead.next().is_some()
+            && let Some(name) = ahead.peek_ident(src)
+            && ahead.next().is_some()
+            && ahead.eat(TokenKind::Eq)
+            && is_container_start(src, &mut ahead.clone())
+        {
+            let mut struct_ = parse_container(src, &mut ahead)?;
+            struct_.name = name;
+            return Ok(struct_);
+        }
+        // Imports, functions and other declarations
+        skip_member(&mut toks)?;
+    }
+}
+
+/// Whether the next tokens are `struct`, `union` or one of those with a layout keyword
+fn is_container_start(src: &str, toks: &mut Toks) -> bool {
+    if matches!(toks.peek_ident(src), Some("extern" | "packed")) {
+        toks.next();
+    }
+    toks.peek().is_some_and(|tok| super::is_struct_kind(src, tok))
+}
+
+/// Parse an anonymous container type, like `extern struct { ... }`
+fn parse_container<'a>(src: &'a str, toks: &mut Toks) -> Result<Struct<'a>, StructParseError> {
+    let layout = toks.peek_ident(src).filter(|word| matches!(*word, "extern" | "packed"));
+    if layout.is_some() {
+        toks.next();
+    }
+    let kind = super::parse_struct_kind(src, toks.next_tok()?)?;
+    let mut backing = None;
+    if toks.eat(TokenKind::LParen) {
+        let tok = toks.expect_tok(TokenKind::Ident)?;
+        match (layout, &src[tok.span.clone()]) {
+            (Some("packed"), name) => {
+                backing =
+                    Some(Primitive::from_name(name).ok_or(StructParseError::unexpected(tok))?);
+            }
+            (_, "enum") => {
+                return Err(StructParseError {
+                    span: tok.span,
+                    kind: StructParseErrorKind::Unsupported("tagged unions"),
+                });
+            }
+            _ => return Err(StructParseError::unexpected(tok)),
+        }
+        toks.expect_tok(TokenKind::RParen)?;
+    }
+    let repr_kind = match layout {
+        Some("extern") => ReprKind::C,
+        Some(_) => ReprKind::BitPacked { backing },
+        None => ReprKind::Rust,
+    };
+    toks.expect_tok(TokenKind::LBrace)?;
+    let fields = parse_fields(src, toks)?;
+    toks.eat(TokenKind::Semi);
+    Ok(Struct {
+        name: "",
+        kind,
+        repr: Repr {
+            kind: repr_kind,
+            ..Repr::default()
+        },
+        namespace: Vec::new(),
+        attrs: Vec::new(),
+        fields,
+    })
+}
+
+/// Parse the fields of a container, after the opening brace
+///
+/// Declarations mixed in between the fields, like methods and constants, are skipped.
+fn parse_fields<'a>(src: &'a str, toks: &mut Toks) -> Result<Vec<Field<'a>>, StructParseError> {
+    let mut fields = Vec::new();
+    loop {
+        let tok = toks.next_tok()?;
+        let name = match tok.kind {
+            TokenKind::RBrace => return Ok(fields),
+            TokenKind::Comma => continue,
+            TokenKind::Ident
+                if DECL_WORDS.contains(&&src[tok.span.clone()])
+                    && toks.peek_kind() != Some(TokenKind::Colon) =>
+            {
+                skip_member(toks)?;
+                continue;
+            }
+            TokenKind::Ident => &src[tok.span.clone()],
+            // `@"name"` identifiers, with the `@` ignored by the tokenizer
+            TokenKind::StrLit => str_lit(src, &tok),
+            _ => return Err(StructParseError::unexpected(tok)),
+        };
+        toks.expect_tok(TokenKind::Colon)?;
+        let ty = parse_ty(src, toks)?;
+        let mut align = None;
+        if toks.peek_ident(src) == Some("align") {
+            toks.next();
+            align = Some(super::parse_paren_int(src, toks)?);
+        }
+        if toks.eat(TokenKind::Eq) {
+            skip_default(toks)?;
+        }
+        fields.push(Field {
+            name,
+            ty,
+            align,
+            offset: None,
+            bits: None,
+            endian: None,
+            span: tok.span,
+            attrs: Vec::new(),
+        });
+    }
+}
+
+/// Skip a default field value, up to the comma or brace that ends the field
+fn skip_default(toks: &mut Toks) -> Result<(), StructParseError> {
+    loop {
+        let close = match toks.peek_kind() {
+            Some(TokenKind::Comma | TokenKind::RBrace) | None => return Ok(()),
+            Some(TokenKind::LParen) => TokenKind::RParen,
+            Some(TokenKind::LBrace) => TokenKind::RBrace,
+            Some(TokenKind::LSqBracket) => TokenKind::RSqBracket,
+            Some(_) => {
+                toks.next();
+                continue;
+            }
+        };
+        toks.next();
+        skip_group(toks, close)?;
+        toks.next();
+    }
+}
+
+fn parse_ty<'a>(src: &'a str, toks: &mut Toks) -> Result<Ty<'a>, StructParseError> {
+    if is_container_start(src, &mut toks.clone()) {
+        return Ok(Ty::Inline(Box::new(parse_container(src, toks)?)));
+    }
+    // The `?` of optional types is ignored by the tokenizer, and optional pointers have
+    // the same layout as plain ones
+    let tok = toks.next_tok()?;
+    match tok.kind {
+        TokenKind::Ident if &src[tok.span.clone()] == "fn" => Ok(Ty::FnPtr(parse_fn(src, toks)?)),
+        TokenKind::Ident => Ok(Ty::Ident(parse_dotted(src, &tok, toks))),
+        TokenKind::Star => parse_pointer(src, toks),
+        TokenKind::LSqBracket => parse_brackets(src, toks),
+        _ => Err(StructParseError::unexpected(tok)),
+    }
+}
+
+/// Parse a pointer type after its `*`, skipping qualifiers like `const` and `align(4)`
+fn parse_pointer<'a>(src: &'a str, toks: &mut Toks) -> Result<Ty<'a>, StructParseError> {
+    loop {
+        match toks.peek_ident(src) {
+            Some("const" | "volatile" | "allowzero") => {
+                toks.next();
+            }
+            Some("align" | "addrspace") => {
+                toks.next();
+                toks.expect_tok(TokenKind::LParen)?;
+                skip_group(toks, TokenKind::RParen)?;
+                toks.next();
+            }
+            _ => break,
+        }
+    }
+    match parse_ty(src, toks)? {
+        // Zig function types are only usable behind a pointer
+        Ty::FnPtr(fn_ptr) => Ok(Ty::FnPtr(fn_ptr)),
+        ty => Ok(Ty::Pointer(Box::new(ty))),
+    }
+}
+
+/// Parse a type starting with `[`, which is an array, a many-item pointer or a slice
+fn parse_brackets<'a>(src: &'a str, toks: &mut Toks) -> Result<Ty<'a>, StructParseError> {
+    let tok = toks.next_tok()?;
+    match tok.kind {
+        // `[*]T` and `[*c]T`, with an optional sentinel like `[*:0]T`
+        TokenKind::Star => {
+            skip_group(toks, TokenKind::RSqBracket)?;
+            toks.next();
+            parse_pointer(src, toks)
+        }
+        TokenKind::NumLit => {
+            let mut len = parse_int(src, &tok)?;
+            // Sentinel-terminated arrays, like `[4:0]u8`, store the sentinel after the
+            // elements
+            if toks.eat(TokenKind::Colon) {
+                skip_group(toks, TokenKind::RSqBracket)?;
+                len += 1;
+            }
+            toks.expect_tok(TokenKind::RSqBracket)?;
+            Ok(Ty::Array(Array {
+                ty: Box::new(parse_ty(src, toks)?),
+                len,
+                flexible: false,
+            }))
+        }
+        TokenKind::RSqBracket | TokenKind::Colon => Err(StructParseError {
+            span: tok.span,
+            kind: StructParseErrorKind::Unsupported("slices"),
+        }),
+        _ => Err(StructParseError::unexpected(tok)),
+    }
+}
+
+/// Parse a function type after its `fn`, like `fn (u32) callconv(.C) void`
+fn parse_fn<'a>(src: &'a str, toks: &mut Toks) -> Result<FnPtr<'a>, StructParseError> {
+    toks.expect_tok(TokenKind::LParen)?;
+    let mut params = Vec::new();
+    while !toks.eat(TokenKind::RParen) {
+        // Named parameters
+        let mut ahead = toks.clone();
+        if ahead.eat(TokenKind::Ident) && ahead.eat(TokenKind::Colon) {
+            *toks = ahead;
+        }
+        params.push(parse_ty(src, toks)?);
+        toks.eat(TokenKind::Comma);
+    }
+    if toks.peek_ident(src) == Some("callconv") {
+        toks.next();
+        toks.expect_tok(TokenKind::LParen)?;
+        skip_group(toks, TokenKind::RParen)?;
+        toks.next();
+    }
+    Ok(FnPtr {
+        params,
+        ret: Box::new(parse_ty(src, toks)?),
+    })
+}
@@ -0,0 +1,116 @@
+#![expect(clippy::unwrap_used)]
+
+use {
+    crate::{Array, FnPtr, Primitive, ReprKind, Struct, StructKind, Ty},
+    pretty_assertions::assert_eq,
+};
+
+#[test]
+fn parse_zig_extern_struct() {
+    let s = Struct::parse_zig(
+        r#"const std = @import("std");
+
+        pub const Foo = extern struct {
+            a: u32,
+            b: [4]u8 = .{ 0, 0, 0, 0 },
+            next: ?*const Foo,
+            bytes: [*c]u8,
+            callback: ?*const fn (ctx: *anyopaque, u32) callconv(.C) void,
+            time: std.os.linux.timespec align(16),
+            name: [3:0]u8,
+
+            pub fn init() Foo {
+                return .{};
+            }
+        };"#,
+    )
+    .unwrap();
+    assert_eq!(s.name, "Foo");
+    assert_eq!(s.repr.kind, ReprKind::C);
+    let names: Vec<_> = s.fields.iter().map(|f| f.name).collect();
+    assert_eq!(
+        names,
+        ["a", "b", "next", "bytes", "callback", "time", "name"]
+    );
+    assert_eq!(
+        s.field("b").unwrap().ty,
+        Ty::Array(Array {
+            ty: Box::new(Ty::Ident("u8")),
+            len: 4,
+            flexible: false,
+        })
+    );
+    assert_eq!(
+        s.field("next").unwrap().ty,
+        Ty::Pointer(Box::new(Ty::Ident("Foo")))
+    );
+    assert_eq!(
+        s.field("bytes").unwrap().ty,
+        Ty::Pointer(Box::new(Ty::Ident("u8")))
+    );
+    assert_eq!(
+        s.field("callback").unwrap().ty,
+        Ty::FnPtr(FnPtr {
+            params: vec![
+                Ty::Pointer(Box::new(Ty::Ident("anyopaque"))),
+                Ty::Ident("u32")
+            ],
+            ret: Box::new(Ty::Ident("void")),
+        })
+    );
+    let time = s.field("time").unwrap();
+    assert_eq!(time.ty, Ty::Ident("std.os.linux.timespec"));
+    assert_eq!(time.align, Some(16));
+    // The sentinel is stored after the elements
+    assert_eq!(
+        s.field("name").unwrap().ty,
+        Ty::Array(Array {
+            ty: Box::new(Ty::Ident("u8")),
+            len: 4,
+            flexible: false,
+        })
+    );
+}
+
+#[test]
+fn parse_zig_packed_struct() {
+    let s = Struct::parse_zig(
+        "const Flags = packed struct(u32) {
+            mode: u3,
+            enabled: bool,
+            inner: packed struct { lo: u4, hi: u4 },
+            _reserved: u20 = 0,
+        };",
+    )
+    .unwrap();
+    assert_eq!(
+        s.repr.kind,
+        ReprKind::BitPacked {
+            backing: Some(Primitive::U32)
+        }
+    );
+    assert_eq!(
+        s.field("mode").unwrap().ty.primitive(),
+        Some(Primitive::BitInt {
+            signed: false,
+            bits: 3
+        })
+    );
+    let Ty::Inline(inner) = &s.field("inner").unwrap().ty else {
+        panic!("expected an inline struct");
+    };
+    assert_eq!(inner.repr.kind, ReprKind::BitPacked { backing: None });
+    assert_eq!(inner.fields.len(), 2);
+    assert_eq!(s.fields.len(), 4);
+}
+
+#[test]
+fn parse_zig_auto_layout_and_unions() {
+    let s = Struct::parse_zig("const Foo = struct { a: u8, b: u64 };").unwrap();
+    assert_eq!(s.repr.kind, ReprKind::Rust);
+    let u = Struct::parse_zig("const U = extern union { i: i32, f: f32 };").unwrap();
+    assert_eq!(u.kind, StructKind::Union);
+    assert_eq!(u.repr.kind, ReprKind::C);
+    assert!(Struct::parse_zig("const U = union(enum) { i: i32, f: f32 };").is_err());
+    assert!(Struct::parse_zig("const S = struct { items: []u8 };").is_err());
+}
@@ -0,0 +1,411 @@
+//! Addressing nested fields and elements by paths like `header.entries[3].len`
+
+use {
+    crate::{BitRange, Definition, LayoutError, Registry, Struct, Target, Ty, Value, Vector},
+    std::fmt,
+    thiserror::Error,
+};
+
+/// A path to a nested field or array element, like `header.entries[3].len`
+///
+/// Paths start at a struct, and consist of field names separated by dots, and array
+/// indices in brackets. Fields of anonymous members are named as if they were fields
+/// of the containing struct, like with [`Struct::field`].
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct FieldPath<'p> {
+    /// The steps of the path, outermost first
+    pub segments: Vec<PathSegment<'p>>,
+}
+
+/// A step of a [`FieldPath`]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum PathSegment<'p> {
+    /// A field of a struct, like `.len`
+    Field(&'p str),
+    /// An element of an array, vector or matrix, like `[3]`
+    ///
+    /// The elements of matrices are their column vectors, or row vectors if they are
+    /// row major, like in [`Value::Array`].
+    Index(u64),
+}
+
+/// Error that can happen while parsing a [`FieldPath`]
+#[derive(Debug, PartialEq, Eq, Clone, Error)]
+pub enum PathParseError {
+    /// A missing field name, like after the `.` of `a.`
+    #[error("Expected a field name at offset {0}")]
+    ExpectedName(usize),
+    /// An index that isn't a decimal number, or that isn't closed by `]`
+    #[error("Invalid index at offset {0}")]
+    InvalidIndex(usize),
+    /// A character that can't continue the path
+    #[error("Unexpected character {ch:?} at offset {offset}")]
+    Unexpected {
+        /// The unexpected character
+        ch: char,
+        /// Byte offset of the character in the input
+        offset: usize,
+    },
+}
+
+/// Error that can happen while resolving a [`FieldPath`]
+#[derive(Debug, PartialEq, Eq, Clone, Error)]
+pub enum PathError {
+    /// A field name that the struct doesn't have
+    #[error("No field named {0}")]
+    NoField(String),
+    /// A field name applied to a type that isn't a struct
+    #[error("Type {0} has no fields")]
+    NotAStruct(String),
+    /// An index applied to a type that isn't an array, vector or matrix
+    #[error("Type {0} can't be indexed")]
+    NotAnArray(String),
+    /// An index past the end of an array
+    #[error("Index {index} out of bounds of {len} elements")]
+    OutOfBounds {
+        /// The index
+        index: u64,
+        /// The number of elements
+        len: u64,
+    },
+    /// The layout of a struct on the path couldn't be computed
+    #[error(transparent)]
+    Layout(#[from] LayoutError),
+}
+
+/// Where a [`FieldPath`] leads within a struct
+#[derive(Debug, PartialEq, Clone)]
+pub struct ResolvedPath<'s> {
+    /// The type of the field or element
+    pub ty: Ty<'s>,
+    /// Offset from the start of the outermost struct in bytes
+    pub offset: u64,
+    /// Size in bytes, including the padding of array elements
+    pub size: u64,
+    /// The bits of a bitfield, with the offset counted from the start of the outermost
+    /// struct
+    ///
+    /// The offset and size of a bitfield are those of the bytes it overlaps.
+    pub bits: Option<BitRange>,
+}
+
+impl<'p> FieldPath<'p> {
+    /// Parse a path, like `header.entries[3].len`
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the path doesn't start with a field name, if a `.` isn't
+    /// followed by a field name, or if an index isn't a decimal number in brackets.
+    pub fn parse(input: &'p str) -> Result<Self, PathParseError> {
+        let mut segments = Vec::new();
+        let mut pos = 0;
+        let name_len = |pos: usize| input[pos..].find(|c: char| !c.is_alphanumeric() && c != '_');
+        loop {
+            // A field name, at the start or after a `.`
+            let len = name_len(pos).unwrap_or(input.len() - pos);
+            if len == 0 {
+                return Err(PathParseError::ExpectedName(pos));
+            }
+            segments.push(PathSegment::Field(&input[pos..pos + len]));
+            pos += len;
+            // Any indices, then the end or a `.`
+            loop {
+                match input[pos..].chars().next() {
+                    None => return Ok(Self { segments }),
+                    Some('.') => {
+                        pos += 1;
+                        break;
+                    }
+                    Some('[') => {
+                        let start = pos + 1;
+                        let end = input[start..]
+                            .find(']')
+                            .map(|len| start + len)
+                            .ok_or(PathParseError::InvalidIndex(start))?;
+                        let digits = &input[start..end];
+                        if !digits.bytes().all(|b| b.is_ascii_digit()) {
+                            return Err(PathParseError::InvalidIndex(start));
+                        }
+                        let index =
+                            digits.parse().map_err(|_| PathParseError::InvalidIndex(start))?;
+                        segments.push(PathSegment::Index(index));
+                        pos = end + 1;
+                    }
+                    Some(ch) => return Err(PathParseError::Unexpected { ch, offset: pos }),
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for FieldPath<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, segment) in self.segments.iter().enumerate() {
+            match segment {
+                PathSegment::Field(name) if i == 0 => f.write_str(name)?,
+                PathSegment::Field(name) => write!(f, ".{name}")?,
+                PathSegment::Index(index) => write!(f, "[{index}]")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<'s> Struct<'s> {
+    /// Find the type, offset and size of the field or element that `path` leads to
+    ///
+    /// Structs are laid out with [`Struct::layout_for`], looking up named types in
+    /// `registry`. Aliases are followed. An empty path leads to the struct itself, as
+    /// an inline struct type.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a field doesn't exist, if a type on the path isn't a struct
+    /// where a field name follows or an array where an index follows, if an index is
+    /// out of bounds, or if the layout of a struct can't be computed.
+    pub fn resolve_path(
+        &self,
+        path: &FieldPath,
+        registry: &Registry<'s>,
+        target: &Target,
+    ) -> Result<ResolvedPath<'s>, PathError> {
+        let layout = self.layout_for(registry, target)?;
+        let mut resolved = ResolvedPath {
+            ty: Ty::Inline(Box::new(self.clone())),
+            offset: 0,
+            size: layout.size,
+            bits: None,
+        };
+        for segment in &path.segments {
+            let ty = follow_aliases(&resolved.ty, registry);
+            resolved = match *segment {
+                PathSegment::Field(name) => {
+                    let struct_ = match ty {
+                        Ty::Inline(inner) => inner,
+                        Ty::Ident(name) => match registry.resolve(name) {
+                            Some(Definition::Struct(struct_)) => struct_,
+                            _ => return Err(PathError::NotAStruct(ty.to_string())),
+                        },
+                        _ => return Err(PathError::NotAStruct(ty.to_string())),
+                    };
+                    field_in(struct_, name, resolved.offset, registry, target)?
+                }
+                PathSegment::Index(index) => {
+                    let (elem, len) = match ty {
+                        Ty::Array(array) => ((*array.ty).clone(), array.len),
+                        Ty::Vector(vector) => ((*vector.ty).clone(), vector.len.into()),
+                        Ty::Matrix(matrix) => {
+                            let (len, count) = if matrix.row_major {
+                                (matrix.columns, matrix.rows)
+                            } else {
+                                (matrix.rows, matrix.columns)
+                            };
+                            let vector = Ty::Vector(Vector {
+                                ty: matrix.ty.clone(),
+                                len,
+                            });
+                            (vector, count.into())
+                        }
+                        _ => return Err(PathError::NotAnArray(ty.to_string())),
+                    };
+                    if index >= len {
+                        return Err(PathError::OutOfBounds { index, len });
+                    }
+                    let stride = resolved.size / len;
+                    ResolvedPath {
+                        ty: elem,
+                        offset: resolved.offset + index * stride,
+                        size: stride,
+                        bits: None,
+                    }
+                }
+            };
+        }
+        Ok(resolved)
+    }
+}
+
+/// Follow aliases until a type that isn't one, stopping at alias cycles
+fn follow_aliases<'a, 's>(mut ty: &'a Ty<'s>, registry: &'a Registry<'s>) -> &'a Ty<'s> {
+    let mut seen = Vec::new();
+    while let Ty::Ident(name) = ty
+        && let Some(Definition::Alias { name, ty: aliased }) = registry.resolve(name)
+        && !seen.contains(&name)
+    {
+        seen.push(name);
+        ty = aliased;
+    }
+    ty
+}
+
+/// Place the field `name` of `struct_`, which starts at `base`, descending into
+/// anonymous members
+fn field_in<'s>(
+    mut struct_: &Struct<'s>,
+    name: &str,
+    base: u64,
+    registry: &Registry<'s>,
+    target: &Target,
+) -> Result<ResolvedPath<'s>, PathError> {
+    let indices = struct_.field_path(name).ok_or_else(|| PathError::NoField(name.to_owned()))?;
+    let mut offset = base;
+    for (depth, &i) in indices.iter().enumerate() {
+        let layout = struct_.layout_for(registry, target)?;
+        let placed = &layout.fields[i];
+        let field = &struct_.fields[i];
+        if depth + 1 == indices.len() {
+            return Ok(ResolvedPath {
+                ty: field.ty.clone(),
+                offset: offset + placed.offset,
+                size: placed.size,
+                bits: placed.bits.map(|bits| BitRange {
+                    offset: offset * 8 + bits.offset,
+                    width: bits.width,
+                }),
+            });
+        }
+        offset += placed.offset;
+        if let Ty::Inline(inner) = &field.ty {
+            struct_ = inner;
+        }
+    }
+    Err(PathError::NoField(name.to_owned()))
+}
+
+impl Value<'_> {
+    /// Look up the value that `path` leads to, like `header.entries[3].len`
+    #[must_use]
+    pub fn at(&self, path: &FieldPath) -> Option<&Self> {
+        path.segments.iter().try_fold(self, |value, segment| match *segment {
+            PathSegment::Field(name) => value.field(name),
+            PathSegment::Index(index) => value.index(usize::try_from(index).ok()?),
+        })
+    }
+    /// Look up the value that `path` leads to, for modifying it
+    pub fn at_mut(&mut self, path: &FieldPath) -> Option<&mut Self> {
+        path.segments.iter().try_fold(self, |value, segment| match *segment {
+            PathSegment::Field(name) => value.field_mut(name),
+            PathSegment::Index(index) => value.index_mut(usize::try_from(index).ok()?),
+        })
+    }
+    /// Replace the value that `path` leads to, returning the previous value
+    ///
+    /// Returns `None`, and leaves this value unchanged, if the path leads nowhere.
+    pub fn replace_at(&mut self, path: &FieldPath, value: Self) -> Option<Self> {
+        self.at_mut(path).map(|old| std::mem::replace(old, value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![expect(clippy::unwrap_used)]
+
+    use {super::*, pretty_assertions::assert_eq};
+
+    #[test]
+    fn test_parse_path() {
+        let path = FieldPath::parse("header.entries[3][1].len").unwrap();
+        assert_eq!(
+            path.segments,
+            [
+                PathSegment::Field("header"),
+                PathSegment::Field("entries"),
+                PathSegment::Index(3),
+                PathSegment::Index(1),
+                PathSegment::Field("len"),
+            ]
+        );
+        assert_eq!(path.to_string(), "header.entries[3][1].len");
+        for (input, err) in [
+            ("", PathParseError::ExpectedName(0)),
+            ("a.", PathParseError::ExpectedName(2)),
+            ("[0]", PathParseError::ExpectedName(0)),
+            ("a[x]", PathParseError::InvalidIndex(2)),
+            ("a[1", PathParseError::InvalidIndex(2)),
+            ("a[-1]", PathParseError::InvalidIndex(2)),
+            ("a b", PathParseError::Unexpected { ch: ' ', offset: 1 }),
+        ] {
+            assert_eq!(FieldPath::parse(input), Err(err), "{input}");
+        }
+    }
+
+    #[test]
+    fn test_resolve_path() {
+        let mut registry: Registry = [
+            Struct::parse_c("struct Entry { short tag; unsigned len : 12, kind : 4; };").unwrap(),
+            Struct::parse_c(
+                "struct Header { int magic; Entries entries; union { char raw[4]; int word; }; };",
+            )
+            .unwrap(),
+        ]
+        .into_iter()
+        .collect();
+        registry.insert_alias(
+            "Entries",
+            Ty::Array(crate::Array {
+                ty: Box::new(Ty::Ident("Entry")),
+                len: 4,
+                flexible: false,
+            }),
+        );
+        let s = Struct::parse_c("struct File { char kind; struct Header header; };").unwrap();
+        let target = Target::lp64();
+        let resolve = |path| s.resolve_path(&FieldPath::parse(path).unwrap(), &registry, &target);
+        let header = resolve("header").unwrap();
+        assert_eq!(
+            (header.ty, header.offset, header.size),
+            (Ty::Ident("Header"), 4, 24)
+        );
+        let entry = resolve("header.entries[3]").unwrap();
+        assert_eq!(
+            (entry.ty, entry.offset, entry.size),
+            (Ty::Ident("Entry"), 20, 4)
+        );
+        let kind = resolve("header.entries[3].kind").unwrap();
+        assert_eq!((kind.offset, kind.size), (23, 1));
+        assert_eq!(
+            kind.bits,
+            Some(BitRange {
+                offset: 20 * 8 + 28,
+                width: 4
+            })
+        );
+        let raw = resolve("header.raw[2]").unwrap();
+        assert_eq!((raw.ty, raw.offset, raw.size), (Ty::Ident("char"), 26, 1));
+        assert_eq!(resolve("header.word").unwrap().offset, 24);
+        assert_eq!(
+            resolve("header.nope"),
+            Err(PathError::NoField("nope".into()))
+        );
+        assert_eq!(resolve("kind.x"), Err(PathError::NotAStruct("char".into())));
+        assert_eq!(
+            resolve("kind[0]"),
+            Err(PathError::NotAnArray("char".into()))
+        );
+        assert_eq!(
+            resolve("header.entries[4]"),
+            Err(PathError::OutOfBounds { index: 4, len: 4 })
+        );
+    }
+
+    #[test]
+    fn test_value_at_path() {
+        let mut value = Value::Struct(vec![
+            ("tag", Value::UInt(1)),
+            (
+                "",
+                Value::Struct(vec![(
+                    "items",
+                    Value::Array(vec![Value::Int(-1), Value::Int(-2)]),
+                )]),
+            ),
+        ]);
+        let path = FieldPath::parse("items[1]").unwrap();
+        assert_eq!(value.at(&path), Some(&Value::Int(-2)));
+        assert_eq!(value.replace_at(&path, Value::Int(7)), Some(Value::Int(-2)));
+        assert_eq!(value.at(&path), Some(&Value::Int(7)));
+        let missing = FieldPath::parse("tag[0]").unwrap();
+        assert_eq!(value.replace_at(&missing, Value::Int(0)), None);
+        assert_eq!(value.at(&FieldPath::default()), Some(&value));
+    }
+}
@@ -0,0 +1,521 @@
+//! Canonical primitive types, and their spellings in the supported languages
+
+use {
+    crate::{Endian, Ty},
+    std::collections::HashMap,
+};
+
+/// A primitive type, independent of the language it was spelled in
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum Primitive {
+    /// 8 bit signed integer
+    I8,
+    /// 16 bit signed integer
+    I16,
+    /// 32 bit signed integer
+    I32,
+    /// 64 bit signed integer
+    I64,
+    /// 128 bit signed integer
+    I128,
+    /// 8 bit unsigned integer
+    U8,
+    /// 16 bit unsigned integer
+    U16,
+    /// 32 bit unsigned integer
+    U32,
+    /// 64 bit unsigned integer
+    U64,
+    /// 128 bit unsigned integer
+    U128,
+    /// Pointer-sized signed integer, like `isize` or `intptr_t`
+    ISize,
+    /// Pointer-sized unsigned integer, like `usize` or `size_t`
+    USize,
+    /// C `long`, whose size depends on the data model
+    CLong,
+    /// C `unsigned long`, whose size depends on the data model
+    CULong,
+    /// Integer with an arbitrary number of bits, like Zig's `u3` or C23's `_BitInt(3)`
+    BitInt {
+        /// Whether the integer is signed
+        signed: bool,
+        /// Width in bits
+        bits: u16,
+    },
+    /// 16 bit floating point number, like WGSL's `f16` or HLSL's `half`
+    F16,
+    /// 32 bit floating point number
+    F32,
+    /// 64 bit floating point number
+    F64,
+    /// C `long double`, whose format depends on the target
+    LongDouble,
+    /// Boolean
+    Bool,
+    /// Rust `char`, a 32 bit Unicode scalar value
+    Char,
+    /// C `char`, whose signedness depends on the target
+    CChar,
+    /// C `wchar_t`, whose size depends on the target
+    WChar,
+    /// `void`, which has no values
+    Void,
+}
+
+impl Primitive {
+    /// Whether this is an integer type
+    #[must_use]
+    pub fn is_int(self) -> bool {
+        !matches!(
+            self,
+            Self::F16
+                | Self::F32
+                | Self::F64
+                | Self::LongDouble
+                | Self::Bool
+                | Self::Char
+                | Self::Void
+        )
+    }
+    /// Whether this is a signed integer type
+    ///
+    /// C's `char` counts as signed, like on most targets.
+    #[must_use]
+    pub fn is_signed(self) -> bool {
+        match self {
+            Self::I8
+            | Self::I16
+            | Self::I32
+            | Self::I64
+            | Self::I128
+            | Self::ISize
+            | Self::CLong
+            | Self::CChar => true,
+            Self::BitInt { signed, .. } => signed,
+            _ => false,
+        }
+    }
+    /// Whether this is a floating point type
+    #[must_use]
+    pub fn is_float(self) -> bool {
+        matches!(self, Self::F16 | Self::F32 | Self::F64 | Self::LongDouble)
+    }
+    /// Look up a primitive by any of its builtin spellings
+    ///
+    /// `char` is C's `char`, see [`PrimitiveMap::rust`] for Rust's. This doesn't include
+    /// aliases registered with [`PrimitiveMap::register`].
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        BUILTIN_NAMES
+            .iter()
+            .find_map(|&(builtin, prim)| (builtin == name).then_some(prim))
+            .or_else(|| from_c_keywords(name))
+            .or_else(|| from_bit_int_name(name))
+            .or_else(|| from_endian_name(name).map(|(prim, _)| prim))
+    }
+    /// The byte order that a primitive name fixes, like big endian for `u16be` or
+    /// Linux's `__be16`
+    #[must_use]
+    pub fn name_endian(name: &str) -> Option<Endian> {
+        from_endian_name(name).map(|(_, endian)| endian)
+    }
+}
+
+impl std::fmt::Display for Primitive {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Self::I8 => "i8",
+            Self::I16 => "i16",
+            Self::I32 => "i32",
+            Self::I64 => "i64",
+            Self::I128 => "i128",
+            Self::U8 => "u8",
+            Self::U16 => "u16",
+            Self::U32 => "u32",
+            Self::U64 => "u64",
+            Self::U128 => "u128",
+            Self::ISize => "isize",
+            Self::USize => "usize",
+            Self::CLong => "c_long",
+            Self::CULong => "c_ulong",
+            Self::BitInt { signed, bits } => {
+                return write!(f, "{}{bits}", if *signed { 'i' } else { 'u' });
+            }
+            Self::F16 => "f16",
+            Self::F32 => "f32",
+            Self::F64 => "f64",
+            Self::LongDouble => "long double",
+            Self::Bool => "bool",
+            Self::Char => "char",
+            Self::CChar => "c_char",
+            Self::WChar => "wchar_t",
+            Self::Void => "void",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Builtin single word type names from Rust, `libc`/`core::ffi`, `<stdint.h>`, Go, shading
+/// languages and Windows
+const BUILTIN_NAMES: &[(&str, Primitive)] = &[
+    // Rust
+    ("i8", Primitive::I8),
+    ("i16", Primitive::I16),
+    ("i32", Primitive::I32),
+    ("i64", Primitive::I64),
+    ("i128", Primitive::I128),
+    ("u8", Primitive::U8),
+    ("u16", Primitive::U16),
+    ("u32", Primitive::U32),
+    ("u64", Primitive::U64),
+    ("u128", Primitive::U128),
+    ("isize", Primitive::ISize),
+    ("usize", Primitive::USize),
+    ("f16", Primitive::F16),
+    ("f32", Primitive::F32),
+    ("f64", Primitive::F64),
+    ("bool", Primitive::Bool),
+    // libc and core::ffi
+    ("c_char", Primitive::CChar),
+    ("c_schar", Primitive::I8),
+    ("c_uchar", Primitive::U8),
+    ("c_short", Primitive::I16),
+    ("c_ushort", Primitive::U16),
+    ("c_int", Primitive::I32),
+    ("c_uint", Primitive::U32),
+    ("c_long", Primitive::CLong),
+    ("c_ulong", Primitive::CULong),
+    ("c_longlong", Primitive::I64),
+    ("c_ulonglong", Primitive::U64),
+    ("c_float", Primitive::F32),
+    ("c_double", Primitive::F64),
+    ("c_void", Primitive::Void),
+    ("size_t", Primitive::USize),
+    ("ssize_t", Primitive::ISize),
+    ("ptrdiff_t", Primitive::ISize),
+    ("intptr_t", Primitive::ISize),
+    ("uintptr_t", Primitive::USize),
+    ("wchar_t", Primitive::WChar),
+    // <stdint.h>, <stdbool.h> and <uchar.h>
+    ("int8_t", Primitive::I8),
+    ("int16_t", Primitive::I16),
+    ("int32_t", Primitive::I32),
+    ("int64_t", Primitive::I64),
+    ("uint8_t", Primitive::U8),
+    ("uint16_t", Primitive::U16),
+    ("uint32_t", Primitive::U32),
+    ("uint64_t", Primitive::U64),
+    ("__int128", Primitive::I128),
+    ("__uint128_t", Primitive::U128),
+    ("char16_t", Primitive::U16),
+    ("char32_t", Primitive::U32),
+    // Go, except for `int` and `uint`, which have a platform dependent width
+    ("int8", Primitive::I8),
+    ("int16", Primitive::I16),
+    ("int32", Primitive::I32),
+    ("int64", Primitive::I64),
+    ("uint8", Primitive::U8),
+    ("uint16", Primitive::U16),
+    ("uint32", Primitive::U32),
+    ("uint64", Primitive::U64),
+    ("uintptr", Primitive::USize),
+    ("float32", Primitive::F32),
+    ("float64", Primitive::F64),
+    ("byte", Primitive::U8),
+    ("rune", Primitive::I32),
+    // Shading languages
+    ("uint", Primitive::U32),
+    ("half", Primitive::F16),
+    ("dword", Primitive::U32),
+    // Windows
+    ("BYTE", Primitive::U8),
+    ("UCHAR", Primitive::U8),
+    ("BOOLEAN", Primitive::U8),
+    ("CHAR", Primitive::CChar),
+    ("WORD", Primitive::U16),
+    ("USHORT", Primitive::U16),
+    ("SHORT", Primitive::I16),
+    ("WCHAR", Primitive::U16),
+    ("DWORD", Primitive::U32),
+    ("UINT", Primitive::U32),
+    ("ULONG", Primitive::U32),
+    ("INT", Primitive::I32),
+    ("LONG", Primitive::I32),
+    ("BOOL", Primitive::I32),
+    ("QWORD", Primitive::U64),
+    ("DWORD64", Primitive::U64),
+    ("ULONGLONG", Primitive::U64),
+    ("ULONG64", Primitive::U64),
+    ("LONGLONG", Primitive::I64),
+    ("LONG64", Primitive::I64),
+    ("INT8", Primitive::I8),
+    ("INT16", Primitive::I16),
+    ("INT32", Primitive::I32),
+    ("INT64", Primitive::I64),
+    ("UINT8", Primitive::U8),
+    ("UINT16", Primitive::U16),
+    ("UINT32", Primitive::U32),
+    ("UINT64", Primitive::U64),
+    ("INT_PTR", Primitive::ISize),
+    ("LONG_PTR", Primitive::ISize),
+    ("UINT_PTR", Primitive::USize),
+    ("ULONG_PTR", Primitive::USize),
+    ("DWORD_PTR", Primitive::USize),
+    ("SIZE_T", Primitive::USize),
+    ("FLOAT", Primitive::F32),
+    ("VOID", Primitive::Void),
+];
+
+/// Map an arbitrary width integer name, like Zig's `u3` or `i7`, to a primitive
+fn from_bit_int_name(name: &str) -> Option<Primitive> {
+    let (signed, bits) = match name.split_at_checked(1)? {
+        ("u", bits) => (false, bits),
+        ("i", bits) => (true, bits),
+        _ => return None,
+    };
+    if !bits.bytes().all(|b| b.is_ascii_digit()) || bits.starts_with('0') {
+        return None;
+    }
+    Some(Primitive::BitInt {
+        signed,
+        bits: bits.parse().ok()?,
+    })
+}
+
+/// Map a primitive name with a byte order suffix, like `u16be` or `f32le`, or a Linux
+/// kernel name, like `__be16`, to the primitive and its byte order
+fn from_endian_name(name: &str) -> Option<(Primitive, Endian)> {
+    if let Some((endian, bits)) = name
+        .strip_prefix("__be")
+        .map(|bits| (Endian::Big, bits))
+        .or_else(|| name.strip_prefix("__le").map(|bits| (Endian::Little, bits)))
+    {
+        let prim = match bits {
+            "16" => Primitive::U16,
+            "32" => Primitive::U32,
+            "64" => Primitive::U64,
+            _ => return None,
+        };
+        return Some((prim, endian));
+    }
+    let (base, endian) = name
+        .strip_suffix("be")
+        .map(|base| (base, Endian::Big))
+        .or_else(|| name.strip_suffix("le").map(|base| (base, Endian::Little)))?;
+    let prim = match base {
+        "i16" | "i32" | "i64" | "i128" | "u16" | "u32" | "u64" | "u128" | "f16" | "f32" | "f64" => {
+            Primitive::from_name(base)?
+        }
+        _ => return None,
+    };
+    Some((prim, endian))
+}
+
+/// Map a combination of C type keywords, like `unsigned long int`, to a primitive
+fn from_c_keywords(name: &str) -> Option<Primitive> {
+    let (mut signed, mut unsigned, mut longs) = (false, false, 0);
+    let mut base = None;
+    for word in name.split_whitespace() {
+        match word {
+            "const" | "volatile" => {}
+            "signed" => signed = true,
+            "unsigned" => unsigned = true,
+            "long" => longs += 1,
+            "char" | "short" | "int" | "float" | "double" | "_Bool" | "bool" | "void"
+                if base.is_none() || (base == Some("int") && word != "int") =>
+            {
+                base = Some(word);
+            }
+            // `short int` and `long int` are the same as `short` and `long`
+            "int" if base == Some("short") => {}
+            _ => return None,
+        }
+    }
+    if signed && unsigned {
+        return None;
+    }
+    let int = |s, u| Some(if unsigned { u } else { s });
+    match (base, longs) {
+        (Some("char"), 0) if signed => Some(Primitive::I8),
+        (Some("char"), 0) => int(Primitive::CChar, Primitive::U8),
+        (Some("short"), 0) => int(Primitive::I16, Primitive::U16),
+        (Some("int") | None, 0) if base.is_some() || signed || unsigned => {
+            int(Primitive::I32, Primitive::U32)
+        }
+        (Some("int") | None, 1) => int(Primitive::CLong, Primitive::CULong),
+        (Some("int") | None, 2) => int(Primitive::I64, Primitive::U64),
+        _ if signed || unsigned => None,
+        (Some("float"), 0) => Some(Primitive::F32),
+        (Some("double"), 0) => Some(Primitive::F64),
+        (Some("double"), 1) => Some(Primitive::LongDouble),
+        (Some("_Bool" | "bool"), 0) => Some(Primitive::Bool),
+        (Some("void"), 0) => Some(Primitive::Void),
+        _ => None,
+    }
+}
+
+/// Maps type names to primitives
+///
+/// Starts out with the builtin names of all supported languages, and can be extended
+/// with project specific aliases, like `typedef uint32_t be32;`.
+///
+/// The one builtin name whose meaning differs between Rust and C is `char`, which
+/// [`PrimitiveMap::new`] treats as C's `char`.
+#[derive(Debug, Clone, Default)]
+pub struct PrimitiveMap {
+    aliases: HashMap<String, Primitive>,
+}
+
+impl PrimitiveMap {
+    /// Create a map with only the builtin names
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Create a map with the builtin names, where `char` is Rust's 32 bit `char`
+    #[must_use]
+    pub fn rust() -> Self {
+        let mut map = Self::new();
+        map.register("char", Primitive::Char);
+        map
+    }
+    /// Register `name` as an alias for `prim`
+    ///
+    /// Aliases take precedence over builtin names.
+    pub fn register(&mut self, name: impl Into<String>, prim: Primitive) {
+        self.aliases.insert(name.into(), prim);
+    }
+    /// Look up the primitive named by `name`
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<Primitive> {
+        self.aliases.get(name).copied().or_else(|| Primitive::from_name(name))
+    }
+    /// Look up the primitive a type refers to, if it is a primitive
+    #[must_use]
+    pub fn resolve(&self, ty: &Ty) -> Option<Primitive> {
+        match ty {
+            Ty::Ident(name) => self.get(name),
+            _ => None,
+        }
+    }
+}
+
+impl Ty<'_> {
+    /// The primitive this type refers to, if it uses a builtin primitive name
+    #[must_use]
+    pub fn primitive(&self) -> Option<Primitive> {
+        PrimitiveMap::new().resolve(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[test]
+    fn test_same_primitive_across_languages() {
+        for name in [
+            "u32",
+            "uint32_t",
+            "c_uint",
+            "DWORD",
+            "unsigned int",
+            "unsigned",
+        ] {
+            assert_eq!(Primitive::from_name(name), Some(Primitive::U32), "{name}");
+        }
+    }
+    #[test]
+    fn test_c_keyword_combinations() {
+        for (name, prim) in [
+            ("char", Primitive::CChar),
+            ("signed char", Primitive::I8),
+            ("unsigned char", Primitive::U8),
+            ("short int", Primitive::I16),
+            ("unsigned short", Primitive::U16),
+            ("long", Primitive::CLong),
+            ("unsigned long int", Primitive::CULong),
+            ("long long", Primitive::I64),
+            ("long long int", Primitive::I64),
+            ("long unsigned long", Primitive::U64),
+            ("long double", Primitive::LongDouble),
+            ("const  double", Primitive::F64),
+            ("_Bool", Primitive::Bool),
+        ] {
+            assert_eq!(Primitive::from_name(name), Some(prim), "{name}");
+        }
+        for name in [
+            "unsigned float",
+            "long char",
+            "signed unsigned",
+            "short long",
+            "char int",
+            "float int",
+            "double int",
+            "int int",
+        ] {
+            assert_eq!(Primitive::from_name(name), None, "{name}");
+        }
+    }
+    #[test]
+    fn test_floats_are_not_ints() {
+        for prim in [
+            Primitive::F16,
+            Primitive::F32,
+            Primitive::F64,
+            Primitive::LongDouble,
+        ] {
+            assert!(prim.is_float(), "{prim:?}");
+            assert!(!prim.is_int(), "{prim:?}");
+        }
+        assert!(!Primitive::U32.is_float());
+        assert!(Primitive::U32.is_int());
+    }
+    #[test]
+    fn test_bit_int_names() {
+        assert_eq!(
+            Primitive::from_name("u3"),
+            Some(Primitive::BitInt {
+                signed: false,
+                bits: 3
+            })
+        );
+        assert_eq!(
+            Primitive::from_name("i65"),
+            Some(Primitive::BitInt {
+                signed: true,
+                bits: 65
+            })
+        );
+        assert_eq!(Primitive::from_name("u32"), Some(Primitive::U32));
+        for name in ["u", "u03", "u3x", "u99999"] {
+            assert_eq!(Primitive::from_name(name), None, "{name}");
+        }
+    }
+    #[test]
+    fn test_endian_names() {
+        for (name, prim, endian) in [
+            ("u16be", Primitive::U16, Endian::Big),
+            ("i32le", Primitive::I32, Endian::Little),
+            ("f64be", Primitive::F64, Endian::Big),
+            ("__be32", Primitive::U32, Endian::Big),
+            ("__le16", Primitive::U16, Endian::Little),
+        ] {
+            assert_eq!(Primitive::from_name(name), Some(prim), "{name}");
+            assert_eq!(Primitive::name_endian(name), Some(endian), "{name}");
+        }
+        for name in ["u8be", "boolle", "__be8", "u16"] {
+            assert_eq!(Primitive::name_endian(name), None, "{name}");
+        }
+    }
+    #[test]
+    fn test_register_alias() {
+        let mut map = PrimitiveMap::new();
+        assert_eq!(map.resolve(&Ty::Ident("be32")), None);
+        map.register("be32", Primitive::U32);
+        assert_eq!(map.resolve(&Ty::Ident("be32")), Some(Primitive::U32));
+        assert_eq!(map.get("WORD"), Some(Primitive::U16));
+        assert_eq!(map.get("char"), Some(Primitive::CChar));
+        assert_eq!(PrimitiveMap::rust().get("char"), Some(Primitive::Char));
+    }
+}
@@ -0,0 +1,293 @@
+//! Looking up the types that fields refer to by name
+
+use {
+    crate::{Enum, Item, Primitive, PrimitiveMap, Struct, Ty},
+    std::{collections::HashMap, ops::Range},
+};
+
+/// Named types that fields can refer to, along with the primitive names
+///
+/// Holds structs, enums and type aliases, like C's `typedef`. Used to look up the
+/// definitions that `Ty::Ident` fields refer to, for example when computing a
+/// [`Layout`](crate::Layout), and to find the names that refer to nothing with
+/// [`Registry::unresolved`].
+#[derive(Debug, Clone, Default)]
+pub struct Registry<'s> {
+    structs: HashMap<&'s str, Struct<'s>>,
+    enums: HashMap<&'s str, Enum<'s>>,
+    aliases: HashMap<&'s str, Ty<'s>>,
+    primitives: PrimitiveMap,
+}
+
+/// What a type name refers to
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Definition<'r, 's> {
+    /// A primitive
+    Primitive(Primitive),
+    /// A struct of the registry
+    Struct(&'r Struct<'s>),
+    /// An enum of the registry
+    Enum(&'r Enum<'s>),
+    /// An alias of the registry
+    Alias {
+        /// Name of the alias
+        name: &'s str,
+        /// The type the alias stands for
+        ty: &'r Ty<'s>,
+    },
+}
+
+/// A type name used by a field that refers to nothing
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Unresolved<'s> {
+    /// Name of the struct the field belongs to
+    ///
+    /// For fields of inline structs, this is the name of the outermost struct.
+    pub struct_name: &'s str,
+    /// Name of the field
+    pub field: &'s str,
+    /// The type name that couldn't be resolved
+    pub name: &'s str,
+    /// Span of the field in the input it was parsed from
+    pub span: Range<usize>,
+}
+
+impl<'s> Registry<'s> {
+    /// Create an empty registry, with the builtin primitive names
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Create an empty registry, with the primitive names of `primitives`
+    #[must_use]
+    pub fn with_primitives(primitives: PrimitiveMap) -> Self {
+        Self {
+            primitives,
+            ..Self::default()
+        }
+    }
+    /// Add a struct, replacing any struct of the same name
+    pub fn insert(&mut self, struct_: Struct<'s>) {
+        self.structs.insert(struct_.name, struct_);
+    }
+    /// Add an enum, replacing any enum of the same name
+    pub fn insert_enum(&mut self, enum_: Enum<'s>) {
+        self.enums.insert(enum_.name, enum_);
+    }
+    /// Add an alias of `ty` named `name`, like C's `typedef ty name;`
+    pub fn insert_alias(&mut self, name: &'s str, ty: Ty<'s>) {
+        self.aliases.insert(name, ty);
+    }
+    /// Add a struct or an enum
+    pub fn insert_item(&mut self, item: Item<'s>) {
+        match item {
+            Item::Struct(struct_) => self.insert(struct_),
+            Item::Enum(enum_) => self.insert_enum(enum_),
+        }
+    }
+    /// Look up a struct by name
+    #[must_use]
+    pub fn get(&self, name: &str) -> Option<&Struct<'s>> {
+        self.structs.get(name)
+    }
+    /// The structs of the registry, in no particular order
+    pub fn structs(&self) -> impl Iterator<Item = &Struct<'s>> {
+        self.structs.values()
+    }
+    /// Look up an enum by name
+    #[must_use]
+    pub fn get_enum(&self, name: &str) -> Option<&Enum<'s>> {
+        self.enums.get(name)
+    }
+    /// Look up an alias by name
+    #[must_use]
+    pub fn get_alias(&self, name: &str) -> Option<&Ty<'s>> {
+        self.aliases.get(name)
+    }
+    /// The primitive names used to resolve types
+    #[must_use]
+    pub fn primitives(&self) -> &PrimitiveMap {
+        &self.primitives
+    }
+    /// Look up what a type name refers to
+    ///
+    /// Primitives take precedence, then structs, enums and aliases. Qualified names,
+    /// like `ns::Foo` or `pkg.Foo`, are looked up by their last segment if the full
+    /// name isn't found. Aliases aren't followed.
+    #[must_use]
+    pub fn resolve(&self, name: &str) -> Option<Definition<'_, 's>> {
+        if let Some(prim) = self.primitives.get(name) {
+            return Some(Definition::Primitive(prim));
+        }
+        let def = self
+            .structs
+            .get(name)
+            .map(Definition::Struct)
+            .or_else(|| self.enums.get(name).map(Definition::Enum))
+            .or_else(|| {
+                let (&name, ty) = self.aliases.get_key_value(name)?;
+                Some(Definition::Alias { name, ty })
+            });
+        match name.rsplit_once("::").or_else(|| name.rsplit_once('.')) {
+            Some((_, last)) if def.is_none() => self.resolve(last),
+            _ => def,
+        }
+    }
+    /// The primitive a type stands for, following aliases and enums
+    pub(crate) fn primitive(&self, ty: &Ty) -> Option<Primitive> {
+        let Ty::Ident(name) = ty else {
+            return None;
+        };
+        match self.resolve(name)? {
+            Definition::Primitive(prim) => Some(prim),
+            Definition::Enum(enum_) => {
+                enum_.ty.as_ref().map_or(Some(Primitive::I32), |ty| self.primitive(ty))
+            }
+            Definition::Alias { ty, .. } => self.primitive(ty),
+            Definition::Struct(_) => None,
+        }
+    }
+    /// Find the type names used by the fields of the structs of the registry that
+    /// refer to nothing
+    ///
+    /// The names are sorted by struct, then in the order of the fields.
+    #[must_use]
+    pub fn unresolved(&self) -> Vec<Unresolved<'s>> {
+        let mut structs: Vec<_> = self.structs().collect();
+        structs.sort_unstable_by_key(|struct_| struct_.name);
+        structs.into_iter().flat_map(|struct_| self.unresolved_in(struct_)).collect()
+    }
+    /// Find the type names used by the fields of `struct_` that refer to nothing in
+    /// the registry, including those of inline structs
+    #[must_use]
+    pub fn unresolved_in(&self, struct_: &Struct<'s>) -> Vec<Unresolved<'s>> {
+        let mut unresolved = Vec::new();
+        self.collect_unresolved(struct_.name, struct_, &mut unresolved);
+        unresolved
+    }
+    fn collect_unresolved(
+        &self,
+        struct_name: &'s str,
+        struct_: &Struct<'s>,
+        unresolved: &mut Vec<Unresolved<'s>>,
+    ) {
+        for field in &struct_.fields {
+            let mut tys = vec![&field.ty];
+            while let Some(ty) = tys.pop() {
+                match ty {
+                    Ty::Ident(name) => {
+                        if self.resolve(name).is_none() {
+                            unresolved.push(Unresolved {
+                                struct_name,
+                                field: field.name,
+                                name,
+                                span: field.span.clone(),
+                            });
+                        }
+                    }
+                    Ty::Array(array) => tys.push(&array.ty),
+                    Ty::Pointer(ty) => tys.push(ty),
+                    Ty::FnPtr(fn_ptr) => {
+                        tys.push(&fn_ptr.ret);
+                        tys.extend(fn_ptr.params.iter().rev());
+                    }
+                    Ty::Inline(inner) => self.collect_unresolved(struct_name, inner, unresolved),
+                    Ty::Vector(vector) => tys.push(&vector.ty),
+                    Ty::Matrix(matrix) => tys.push(&matrix.ty),
+                }
+            }
+        }
+    }
+}
+
+impl<'s> FromIterator<Struct<'s>> for Registry<'s> {
+    fn from_iter<I: IntoIterator<Item = Struct<'s>>>(iter: I) -> Self {
+        let mut registry = Self::new();
+        for struct_ in iter {
+            registry.insert(struct_);
+        }
+        registry
+    }
+}
+
+impl<'s> FromIterator<Item<'s>> for Registry<'s> {
+    fn from_iter<I: IntoIterator<Item = Item<'s>>>(iter: I) -> Self {
+        let mut registry = Self::new();
+        for item in iter {
+            registry.insert_item(item);
+        }
+        registry
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![expect(clippy::unwrap_used)]
+
+    use {super::*, pretty_assertions::assert_eq};
+
+    fn registry() -> Registry<'static> {
+        let mut registry: Registry = Item::parse_fbs(
+            "namespace Game;
+            enum Color : ubyte { Red, Green }
+            struct Vec3 { x: float; y: float; z: float; }",
+        )
+        .unwrap()
+        .into_iter()
+        .collect();
+        registry.insert_alias("Handle", Ty::Ident("u32"));
+        registry.insert_alias("Ptr", Ty::Pointer(Box::new(Ty::Ident("Missing"))));
+        registry
+    }
+
+    #[test]
+    fn test_resolve() {
+        let registry = registry();
+        assert_eq!(
+            registry.resolve("u8"),
+            Some(Definition::Primitive(Primitive::U8))
+        );
+        assert!(matches!(
+            registry.resolve("Game.Vec3"),
+            Some(Definition::Struct(s)) if s.name == "Vec3"
+        ));
+        assert!(matches!(
+            registry.resolve("Color"),
+            Some(Definition::Enum(_))
+        ));
+        assert_eq!(
+            registry.resolve("Handle"),
+            Some(Definition::Alias {
+                name: "Handle",
+                ty: &Ty::Ident("u32")
+            })
+        );
+        assert_eq!(registry.resolve("Missing"), None);
+    }
+
+    #[test]
+    fn test_unresolved() {
+        let registry = registry();
+        let input = "struct Mesh {
+            pos: Vec3,
+            color: Color,
+            handle: Handle,
+            next: *const Mesh,
+            inner: struct { cb: [*mut Unknown; 2] },
+            tags: [Tag; 4],
+        }";
+        let s = Struct::parse(input).unwrap();
+        let unresolved = registry.unresolved_in(&s);
+        let names: Vec<_> = unresolved.iter().map(|u| (u.struct_name, u.field, u.name)).collect();
+        assert_eq!(
+            names,
+            [
+                ("Mesh", "next", "Mesh"),
+                ("Mesh", "cb", "Unknown"),
+                ("Mesh", "tags", "Tag")
+            ]
+        );
+        assert_eq!(&input[unresolved[2].span.clone()], "tags");
+        assert_eq!(registry.unresolved(), []);
+    }
+}
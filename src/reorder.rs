@@ -0,0 +1,94 @@
+//! Suggesting field orders that waste less space on padding
+
+use crate::{LayoutError, Registry, ReprKind, Struct, StructKind, Target};
+
+/// A struct with its fields reordered to reduce padding
+#[derive(Debug, PartialEq, Clone)]
+pub struct Reordered<'s> {
+    /// The struct, with the fields in the suggested order
+    pub struct_: Struct<'s>,
+    /// How many bytes smaller the reordered struct is than the original
+    pub saved: u64,
+}
+
+impl<'s> Struct<'s> {
+    /// Suggest a field order for this struct that minimizes its size on `target`
+    ///
+    /// Fields are sorted by decreasing alignment, keeping the declaration order of
+    /// fields with the same alignment. Fields named in `hot` are kept together at the
+    /// start of the struct, which can cost some of the savings. Unions and explicit
+    /// layouts are returned unchanged.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the layout of the struct can't be computed, see
+    /// [`Struct::layout_for`].
+    pub fn reorder(
+        &self,
+        registry: &Registry,
+        target: &Target,
+        hot: &[&str],
+    ) -> Result<Reordered<'s>, LayoutError> {
+        let layout = self.layout_for(registry, target)?;
+        if self.kind == StructKind::Union || self.repr.kind == ReprKind::Explicit {
+            return Ok(Reordered {
+                struct_: self.clone(),
+                saved: 0,
+            });
+        }
+        let mut order: Vec<_> = self.fields.iter().zip(&layout.fields).collect();
+        order.sort_by_key(|(field, placed)| {
+            (!hot.contains(&field.name), std::cmp::Reverse(placed.align))
+        });
+        let struct_ = Self {
+            fields: order.into_iter().map(|(field, _)| field.clone()).collect(),
+            ..self.clone()
+        };
+        let saved = layout.size.saturating_sub(struct_.layout_for(registry, target)?.size);
+        Ok(Reordered { struct_, saved })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![expect(clippy::unwrap_used)]
+
+    use {super::*, pretty_assertions::assert_eq};
+
+    fn names<'s>(struct_: &Struct<'s>) -> Vec<&'s str> {
+        struct_.fields.iter().map(|field| field.name).collect()
+    }
+
+    #[test]
+    fn test_reorder() {
+        let s = Struct::parse_c("struct S { char a; double b; char c; int d; short e; };").unwrap();
+        let (registry, target) = (Registry::new(), Target::lp64());
+        let reordered = s.reorder(&registry, &target, &[]).unwrap();
+        assert_eq!(names(&reordered.struct_), ["b", "d", "e", "a", "c"]);
+        assert_eq!(reordered.saved, 32 - 16);
+        let reordered = s.reorder(&registry, &target, &["c", "a"]).unwrap();
+        assert_eq!(names(&reordered.struct_), ["a", "c", "b", "d", "e"]);
+        assert_eq!(reordered.saved, 32 - 24);
+        let u = Struct::parse_c("union U { char a; double b; };").unwrap();
+        assert_eq!(u.reorder(&registry, &target, &[]).unwrap().saved, 0);
+    }
+
+    #[test]
+    fn test_rust_layout_simulation() {
+        let registry: Registry = [Struct::parse("struct Inner { a: u8, b: u32, c: u8 }").unwrap()]
+            .into_iter()
+            .collect();
+        let target = Target::lp64();
+        let s = Struct::parse("struct Outer { x: u8, inner: Inner, y: u16 }").unwrap();
+        let layout = s.layout_rust(&registry, &target).unwrap();
+        let offsets: Vec<_> = layout.fields.iter().map(|f| (f.name, f.offset)).collect();
+        assert_eq!(offsets, [("x", 10), ("inner", 0), ("y", 8)]);
+        assert_eq!(layout.size, 12);
+        // In declaration order, `Inner` alone takes 12 bytes
+        assert_eq!(s.layout_for(&registry, &target).unwrap().size, 20);
+        // Only the default representation is reordered
+        let s = Struct::parse("#[repr(C)] struct C { a: u8, b: u32 }").unwrap();
+        let layout = s.layout_rust(&registry, &target).unwrap();
+        assert_eq!(layout.fields[1].offset, 4);
+    }
+}
@@ -0,0 +1,183 @@
+//! Human readable reports of struct layouts, in the style of `pahole`
+
+use {
+    crate::{Layout, Struct, StructKind},
+    std::fmt,
+};
+
+/// Width of the column of field declarations
+const DECL_WIDTH: usize = 40;
+
+/// A report of the layout of a struct, listing its fields with their offsets and sizes
+///
+/// Holes between fields, trailing padding and cache line boundaries are marked, and a
+/// summary compares the size of the struct to the sum of the sizes of its fields.
+/// Formatting the report with [`Display`](fmt::Display) prints it like this:
+///
+/// ```text
+/// struct Foo {
+///     a: u8,                                   /*     0     1 */
+///     /* XXX 7 bytes hole, try to pack */
+///     b: u64,                                  /*     8     8 */
+///
+///     /* size: 16, align: 8, cachelines: 1, members: 2 */
+///     /* sum members: 9, holes: 1, sum holes: 7 */
+///     /* last cacheline: 16 bytes */
+/// };
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Report<'a, 's> {
+    struct_: &'a Struct<'s>,
+    layout: &'a Layout<'s>,
+    cache_line: u64,
+}
+
+impl<'a, 's> Report<'a, 's> {
+    /// Create a report of `layout`, which was computed for `struct_`, with cache lines
+    /// of `cache_line` bytes
+    #[must_use]
+    pub fn new(struct_: &'a Struct<'s>, layout: &'a Layout<'s>, cache_line: u64) -> Self {
+        Self {
+            struct_,
+            layout,
+            cache_line: cache_line.max(1),
+        }
+    }
+}
+
+impl fmt::Display for Report<'_, '_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let keyword = match self.struct_.kind {
+            StructKind::Struct => "struct",
+            StructKind::Union => "union",
+        };
+        writeln!(f, "{keyword} {} {{", self.struct_.name)?;
+        let mut holes = self.layout.holes().peekable();
+        let mut line = 0;
+        // Mark the start of the cache line of `offset`, if it wasn't marked before
+        let mut boundary = |f: &mut fmt::Formatter<'_>, offset: u64| {
+            if offset / self.cache_line > line {
+                line = offset / self.cache_line;
+                writeln!(
+                    f,
+                    "    /* --- cacheline {line} boundary ({} bytes) --- */",
+                    line * self.cache_line
+                )?;
+            }
+            Ok(())
+        };
+        for (field, placed) in self.struct_.fields.iter().zip(&self.layout.fields) {
+            while let Some(hole) = holes.next_if(|hole| hole.offset < placed.offset) {
+                boundary(f, hole.offset)?;
+                writeln!(f, "    /* XXX {} bytes hole, try to pack */", hole.size)?;
+            }
+            boundary(f, placed.offset)?;
+            let mut decl = match field.name {
+                "" => field.ty.to_string(),
+                name => format!("{name}: {}", field.ty),
+            };
+            // Bitfields show their width after the type, and the bit their offset
+            // starts at within its byte
+            let mut offset = placed.offset.to_string();
+            if let Some(bits) = placed.bits {
+                decl = format!("{decl} : {}", bits.width);
+                offset = format!("{offset}:{}", bits.offset % 8);
+            }
+            decl.push(',');
+            write!(
+                f,
+                "    {decl:<DECL_WIDTH$} /* {offset:>5} {:>5} */",
+                placed.size
+            )?;
+            let last = placed.offset + placed.size.saturating_sub(1);
+            if last / self.cache_line > placed.offset / self.cache_line {
+                f.write_str(" /* crosses cacheline boundary */")?;
+            }
+            writeln!(f)?;
+        }
+        for hole in holes {
+            boundary(f, hole.offset)?;
+            writeln!(f, "    /* XXX {} bytes hole, try to pack */", hole.size)?;
+        }
+        writeln!(f)?;
+        writeln!(
+            f,
+            "    /* size: {}, align: {}, cachelines: {}, members: {} */",
+            self.layout.size,
+            self.layout.align,
+            self.layout.cache_lines(self.cache_line),
+            self.layout.fields.len()
+        )?;
+        write!(f, "    /* sum members: {}", self.layout.field_bytes())?;
+        let hole_count = self.layout.holes().count();
+        if hole_count > 0 {
+            let hole_bytes: u64 = self.layout.holes().map(|hole| hole.size).sum();
+            write!(f, ", holes: {hole_count}, sum holes: {hole_bytes}")?;
+        }
+        writeln!(f, " */")?;
+        let trailing = self.layout.trailing_padding();
+        if trailing > 0 {
+            writeln!(f, "    /* padding: {trailing} */")?;
+        }
+        let last_line = self.layout.size % self.cache_line;
+        if last_line > 0 {
+            writeln!(f, "    /* last cacheline: {last_line} bytes */")?;
+        }
+        writeln!(f, "}};")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![expect(clippy::unwrap_used)]
+
+    use {super::*, crate::Registry, pretty_assertions::assert_eq};
+
+    #[test]
+    fn test_report() {
+        let s = Struct::parse_c(
+            "struct Hot { char flag; long count; short id; char name[20]; void *next; };",
+        )
+        .unwrap();
+        let layout = s.layout(&Registry::new()).unwrap();
+        assert_eq!(
+            Report::new(&s, &layout, 32).to_string(),
+            "struct Hot {
+    flag: char,                              /*     0     1 */
+    /* XXX 7 bytes hole, try to pack */
+    count: long,                             /*     8     8 */
+    id: short,                               /*    16     2 */
+    name: [char; 20],                        /*    18    20 */ /* crosses cacheline boundary */
+    /* --- cacheline 1 boundary (32 bytes) --- */
+    /* XXX 2 bytes hole, try to pack */
+    next: *const void,                       /*    40     8 */
+
+    /* size: 48, align: 8, cachelines: 2, members: 5 */
+    /* sum members: 39, holes: 2, sum holes: 9 */
+    /* last cacheline: 16 bytes */
+};
+"
+        );
+    }
+
+    #[test]
+    fn test_report_bitfields() {
+        let s = Struct::parse_c("struct F { unsigned a : 3, b : 7; char c; };").unwrap();
+        let layout = s.layout(&Registry::new()).unwrap();
+        let report = Report::new(&s, &layout, 64).to_string();
+        assert!(
+            report.contains("    b: unsigned : 7,                         /*   0:3     2 */"),
+            "{report}"
+        );
+    }
+
+    #[test]
+    fn test_report_trailing_padding() {
+        let s = Struct::parse("#[repr(C)] struct T { a: u64, b: u8 }").unwrap();
+        let layout = s.layout(&Registry::new()).unwrap();
+        let report = Report::new(&s, &layout, 64).to_string();
+        assert!(report.contains("/* sum members: 9 */"), "{report}");
+        assert!(report.contains("/* padding: 7 */"), "{report}");
+        assert_eq!(layout.holes().count(), 0);
+    }
+}
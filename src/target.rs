@@ -0,0 +1,360 @@
+//! Target descriptions, with the sizes and alignments of primitives
+
+use {crate::Primitive, std::collections::HashMap};
+
+/// Byte order of a target
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum Endian {
+    /// Least significant byte first
+    #[default]
+    Little,
+    /// Most significant byte first
+    Big,
+}
+
+impl Endian {
+    /// Look up a byte order by name, like `big`, `le` or `little-endian`
+    #[must_use]
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "little" | "le" | "little-endian" => Some(Self::Little),
+            "big" | "be" | "big-endian" => Some(Self::Big),
+            _ => None,
+        }
+    }
+}
+
+/// Rules for laying out bitfields, which differ between compilers
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+pub enum Abi {
+    /// The System V rules of GCC and Clang
+    ///
+    /// Bitfields of any type are packed together, and only move to the next storage
+    /// unit of their type when they would straddle it.
+    #[default]
+    SysV,
+    /// The rules of MSVC
+    ///
+    /// Adjacent bitfields only share a storage unit if their types have the same size.
+    Msvc,
+}
+
+/// The properties of a target that affect layouts
+///
+/// Presets are available for the common data models, like [`Target::lp64`], and for
+/// target triples with [`Target::from_triple`]. The sizes and alignments of single
+/// primitives can be overridden with [`Target::set`].
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Target {
+    /// Size of pointers in bytes, which is also their alignment
+    pub pointer_size: u64,
+    /// Byte order
+    pub endian: Endian,
+    /// Largest alignment of any primitive, like C's `alignof(max_align_t)`
+    ///
+    /// The alignments of primitives are capped to this.
+    pub max_align: u64,
+    /// Rules for laying out bitfields
+    pub abi: Abi,
+    primitives: HashMap<Primitive, (u64, u64)>,
+}
+
+impl Default for Target {
+    /// 64 bit Linux, see [`Target::lp64`]
+    fn default() -> Self {
+        Self::lp64()
+    }
+}
+
+impl Target {
+    /// Create a target where all primitives have their natural size and alignment
+    ///
+    /// `usize`, `c_long` and pointers are `pointer_size` bytes wide, `long double` is
+    /// the same as `double`, and `wchar_t` is 4 bytes wide.
+    #[must_use]
+    pub fn new(pointer_size: u64, endian: Endian) -> Self {
+        let mut primitives = HashMap::new();
+        for (prims, size) in [
+            (
+                &[
+                    Primitive::I8,
+                    Primitive::U8,
+                    Primitive::Bool,
+                    Primitive::CChar,
+                ][..],
+                1,
+            ),
+            (&[Primitive::I16, Primitive::U16, Primitive::F16], 2),
+            (
+                &[
+                    Primitive::I32,
+                    Primitive::U32,
+                    Primitive::F32,
+                    Primitive::Char,
+                    Primitive::WChar,
+                ],
+                4,
+            ),
+            (
+                &[
+                    Primitive::I64,
+                    Primitive::U64,
+                    Primitive::F64,
+                    Primitive::LongDouble,
+                ],
+                8,
+            ),
+            (&[Primitive::I128, Primitive::U128], 16),
+            (
+                &[
+                    Primitive::ISize,
+                    Primitive::USize,
+                    Primitive::CLong,
+                    Primitive::CULong,
+                ],
+                pointer_size,
+            ),
+        ] {
+            for &prim in prims {
+                primitives.insert(prim, (size, size));
+            }
+        }
+        Self {
+            pointer_size,
+            endian,
+            max_align: 16,
+            abi: Abi::SysV,
+            primitives,
+        }
+    }
+    /// The LP64 data model of 64 bit Unix targets, like `x86_64-unknown-linux-gnu`
+    ///
+    /// `long` and pointers are 8 bytes wide, and `long double` is 16 bytes wide.
+    #[must_use]
+    pub fn lp64() -> Self {
+        let mut target = Self::new(8, Endian::Little);
+        target.set(Primitive::LongDouble, 16, 16);
+        target
+    }
+    /// The LLP64 data model of 64 bit Windows, like `x86_64-pc-windows-msvc`
+    ///
+    /// Pointers are 8 bytes wide, but `long` is 4 bytes wide, `long double` is the same
+    /// as `double`, and `wchar_t` is 2 bytes wide. Bitfields follow the [`Abi::Msvc`]
+    /// rules.
+    #[must_use]
+    pub fn llp64() -> Self {
+        let mut target = Self::new(8, Endian::Little);
+        target.abi = Abi::Msvc;
+        target.set(Primitive::CLong, 4, 4);
+        target.set(Primitive::CULong, 4, 4);
+        target.set(Primitive::WChar, 2, 2);
+        target
+    }
+    /// The ILP32 data model of 32 bit targets, like `armv7-unknown-linux-gnueabihf`
+    ///
+    /// `int`, `long` and pointers are 4 bytes wide, 64 bit types are 8 byte aligned,
+    /// and `long double` is the same as `double`.
+    #[must_use]
+    pub fn ilp32() -> Self {
+        let mut target = Self::new(4, Endian::Little);
+        target.set(Primitive::I128, 16, 8);
+        target.set(Primitive::U128, 16, 8);
+        target.max_align = 8;
+        target
+    }
+    /// Look up the preset for a target triple, like `x86_64-pc-windows-msvc`
+    ///
+    /// Only the architecture, the operating system, whether the vendor is Apple and
+    /// whether a Windows target uses MSVC or MinGW are taken into account. Returns `None`
+    /// for unknown architectures.
+    #[must_use]
+    pub fn from_triple(triple: &str) -> Option<Self> {
+        let arch = triple.split('-').next()?;
+        let windows = triple.contains("windows");
+        let lp64 = matches!(
+            arch,
+            "x86_64"
+                | "aarch64"
+                | "aarch64_be"
+                | "riscv64gc"
+                | "riscv64"
+                | "powerpc64"
+                | "powerpc64le"
+                | "s390x"
+                | "sparc64"
+                | "mips64"
+                | "mips64el"
+                | "loongarch64"
+        );
+        let mut target = match arch {
+            _ if lp64 && windows => Self::llp64(),
+            _ if lp64 => Self::lp64(),
+            // 32 bit x86 only aligns 64 bit types to 4 bytes inside of structs, except
+            // on Windows
+            "i386" | "i586" | "i686" | "x86" if windows => {
+                let mut target = Self::ilp32();
+                target.set(Primitive::WChar, 2, 2);
+                target.set(Primitive::I128, 16, 16);
+                target.set(Primitive::U128, 16, 16);
+                target.max_align = 16;
+                target
+            }
+            "i386" | "i586" | "i686" | "x86" => {
+                let mut target = Self::ilp32();
+                for prim in [Primitive::I64, Primitive::U64, Primitive::F64] {
+                    target.set(prim, 8, 4);
+                }
+                target.set(Primitive::LongDouble, 12, 4);
+                target.set(Primitive::I128, 16, 16);
+                target.set(Primitive::U128, 16, 16);
+                target.max_align = 16;
+                target
+            }
+            "wasm32" => {
+                let mut target = Self::ilp32();
+                target.set(Primitive::LongDouble, 16, 16);
+                target.max_align = 16;
+                target
+            }
+            _ if arch.starts_with("arm")
+                || arch.starts_with("thumb")
+                || arch.starts_with("riscv32")
+                || arch.starts_with("mips")
+                || arch.starts_with("powerpc")
+                || arch.starts_with("sparc") =>
+            {
+                Self::ilp32()
+            }
+            _ => return None,
+        };
+        let big_endian = matches!(
+            arch,
+            "aarch64_be"
+                | "powerpc64"
+                | "s390x"
+                | "sparc64"
+                | "mips64"
+                | "powerpc"
+                | "sparc"
+                | "mips"
+                | "armeb"
+                | "armebv7r"
+                | "thumbeb"
+        );
+        if big_endian {
+            target.endian = Endian::Big;
+        }
+        // Apple's 64 bit ARM ABI makes `long double` the same as `double`
+        if arch == "aarch64" && triple.contains("-apple-") {
+            target.set(Primitive::LongDouble, 8, 8);
+        }
+        if windows {
+            // MinGW keeps the 80 bit x87 `long double` of other x86 targets, while it is
+            // the same as `double` with MSVC
+            let msvc = triple.ends_with("msvc");
+            match arch {
+                "x86_64" if !msvc => target.set(Primitive::LongDouble, 16, 16),
+                "i386" | "i586" | "i686" | "x86" if !msvc => {
+                    target.set(Primitive::LongDouble, 12, 4);
+                }
+                _ => target.set(Primitive::LongDouble, 8, 8),
+            }
+            target.abi = Abi::Msvc;
+        }
+        Some(target)
+    }
+    /// Override the size and alignment of a primitive
+    pub fn set(&mut self, prim: Primitive, size: u64, align: u64) {
+        self.primitives.insert(prim, (size, align));
+    }
+    /// The size of a primitive in bytes, or `None` if it has no size, like `void`
+    #[must_use]
+    pub fn size_of(&self, prim: Primitive) -> Option<u64> {
+        self.scalar(prim).map(|(size, _)| size)
+    }
+    /// The alignment of a primitive in bytes, or `None` if it has no size, like `void`
+    #[must_use]
+    pub fn align_of(&self, prim: Primitive) -> Option<u64> {
+        self.scalar(prim).map(|(_, align)| align)
+    }
+    /// The size and alignment of a primitive
+    ///
+    /// Integers with an arbitrary number of bits, like `_BitInt(N)`, are rounded up to
+    /// the next integer type, or to a multiple of 64 bit integers beyond those.
+    pub(crate) fn scalar(&self, prim: Primitive) -> Option<(u64, u64)> {
+        let (size, align) = match prim {
+            Primitive::Void => return None,
+            Primitive::BitInt { bits, .. } if !self.primitives.contains_key(&prim) => {
+                let int = match u64::from(bits).div_ceil(8).next_power_of_two() {
+                    1 => Primitive::I8,
+                    2 => Primitive::I16,
+                    4 => Primitive::I32,
+                    _ => Primitive::I64,
+                };
+                let (size, align) = self.scalar(int)?;
+                (u64::from(bits).div_ceil(size * 8) * size, align)
+            }
+            _ => *self.primitives.get(&prim)?,
+        };
+        Some((size, align.min(self.max_align)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #![expect(clippy::unwrap_used)]
+
+    use {super::*, pretty_assertions::assert_eq};
+    #[test]
+    fn test_data_models() {
+        let lp64 = Target::lp64();
+        let windows = Target::llp64();
+        let ilp32 = Target::ilp32();
+        assert_eq!(lp64.size_of(Primitive::CLong), Some(8));
+        assert_eq!(windows.size_of(Primitive::CLong), Some(4));
+        assert_eq!(ilp32.size_of(Primitive::USize), Some(4));
+        assert_eq!(lp64.size_of(Primitive::LongDouble), Some(16));
+        assert_eq!(windows.size_of(Primitive::LongDouble), Some(8));
+        assert_eq!(ilp32.align_of(Primitive::I128), Some(8));
+        assert_eq!(lp64.size_of(Primitive::Void), None);
+        let bit_int = Primitive::BitInt {
+            signed: false,
+            bits: 65,
+        };
+        assert_eq!(lp64.scalar(bit_int), Some((16, 8)));
+        assert_eq!(ilp32.scalar(bit_int), Some((16, 8)));
+    }
+    #[test]
+    fn test_triples() {
+        let i686 = Target::from_triple("i686-unknown-linux-gnu").unwrap();
+        assert_eq!(i686.pointer_size, 4);
+        assert_eq!(i686.align_of(Primitive::F64), Some(4));
+        assert_eq!(i686.size_of(Primitive::LongDouble), Some(12));
+        let win32 = Target::from_triple("i686-pc-windows-msvc").unwrap();
+        assert_eq!(win32.align_of(Primitive::F64), Some(8));
+        assert_eq!(win32.size_of(Primitive::WChar), Some(2));
+        assert_eq!(win32.abi, Abi::Msvc);
+        assert_eq!(i686.abi, Abi::SysV);
+        assert_eq!(
+            Target::from_triple("x86_64-pc-windows-msvc"),
+            Some(Target::llp64())
+        );
+        assert_eq!(
+            Target::from_triple("armv7-unknown-linux-gnueabihf"),
+            Some(Target::ilp32())
+        );
+        let mingw = Target::from_triple("x86_64-pc-windows-gnu").unwrap();
+        assert_eq!(mingw.scalar(Primitive::LongDouble), Some((16, 16)));
+        assert_eq!(mingw.abi, Abi::Msvc);
+        let mingw32 = Target::from_triple("i686-pc-windows-gnu").unwrap();
+        assert_eq!(mingw32.scalar(Primitive::LongDouble), Some((12, 4)));
+        assert_eq!(win32.scalar(Primitive::LongDouble), Some((8, 8)));
+        let apple = Target::from_triple("aarch64-apple-darwin").unwrap();
+        assert_eq!(apple.scalar(Primitive::LongDouble), Some((8, 8)));
+        let linux = Target::from_triple("aarch64-unknown-linux-gnu").unwrap();
+        assert_eq!(linux.scalar(Primitive::LongDouble), Some((16, 16)));
+        let ppc = Target::from_triple("powerpc64-unknown-linux-gnu").unwrap();
+        assert_eq!(ppc.endian, Endian::Big);
+        assert_eq!(Target::from_triple("unknown-arch-none"), None);
+    }
+}
@@ -13,9 +13,18 @@ pub enum TokenKind {
     RBrace,
     LSqBracket,
     RSqBracket,
+    LParen,
+    RParen,
     Colon,
     Semi,
     Comma,
+    Star,
+    Hash,
+    Lt,
+    Gt,
+    Eq,
+    Amp,
+    StrLit,
 }
 
 enum Status {
@@ -23,6 +32,10 @@ enum Status {
     InToken { start: usize, kind: TokenKind },
     FwSlash,
     InComment,
+    InBlockComment,
+    BlockCommentStar,
+    InStr { start: usize, escaped: bool },
+    InRawStr { start: usize },
 }
 
 #[derive(Debug)]
@@ -34,9 +47,98 @@ pub struct TokenizeError {
 #[derive(Debug)]
 pub enum TokenizeErrorKind {
     UnexpectedByte,
+    UnterminatedStr,
+}
+
+fn punct_kind(b: u8) -> Option<TokenKind> {
+    Some(match b {
+        b'{' => TokenKind::LBrace,
+        b'}' => TokenKind::RBrace,
+        b'[' => TokenKind::LSqBracket,
+        b']' => TokenKind::RSqBracket,
+        b'(' => TokenKind::LParen,
+        b')' => TokenKind::RParen,
+        b':' => TokenKind::Colon,
+        b';' => TokenKind::Semi,
+        b',' => TokenKind::Comma,
+        b'*' => TokenKind::Star,
+        b'#' => TokenKind::Hash,
+        b'<' => TokenKind::Lt,
+        b'>' => TokenKind::Gt,
+        b'=' => TokenKind::Eq,
+        b'&' => TokenKind::Amp,
+        _ => return None,
+    })
+}
+
+fn keyword_or(text: &str, kind: TokenKind) -> TokenKind {
+    match text {
+        "struct" => TokenKind::KwStruct,
+        _ => kind,
+    }
+}
+
+/// Handle the first byte of a token, pushing single byte tokens right away
+fn start_token(b: u8, i: usize, tokens: &mut Vec<Token>) -> Status {
+    match b {
+        b'/' => Status::FwSlash,
+        b'A'..=b'Z' | b'a'..=b'z' | b'_' => Status::InToken {
+            start: i,
+            kind: TokenKind::Ident,
+        },
+        b'0'..=b'9' => Status::InToken {
+            start: i,
+            kind: TokenKind::NumLit,
+        },
+        b'"' => Status::InStr {
+            start: i,
+            escaped: false,
+        },
+        // Go raw strings
+        b'`' => Status::InRawStr { start: i },
+        _ => {
+            if let Some(kind) = punct_kind(b) {
+                tokens.push(Token {
+                    span: i..i + 1,
+                    kind,
+                });
+            }
+            Status::Init
+        }
+    }
+}
+
+/// Handle a byte inside of a string, pushing the string token at its end
+fn continue_str(status: Status, b: u8, i: usize, tokens: &mut Vec<Token>) -> Status {
+    let (start, end) = match status {
+        Status::InStr { start, escaped } => match b {
+            b'"' if !escaped => (start, true),
+            b'\\' => {
+                return Status::InStr {
+                    start,
+                    escaped: !escaped,
+                };
+            }
+            _ => {
+                return Status::InStr {
+                    start,
+                    escaped: false,
+                };
+            }
+        },
+        Status::InRawStr { start } => (start, b == b'`'),
+        _ => return status,
+    };
+    if !end {
+        return status;
+    }
+    tokens.push(Token {
+        span: start..i + 1,
+        kind: TokenKind::StrLit,
+    });
+    Status::Init
 }
 
-#[expect(clippy::range_plus_one, reason = "API dictates (exclusive) Range")]
 pub(crate) fn tokenize(src: &str) -> Result<Vec<Token>, TokenizeError> {
     let mut status = Status::Init;
     let mut tokens = Vec::new();
@@ -44,52 +146,7 @@ pub(crate) fn tokenize(src: &str) -> Result<Vec<Token>, TokenizeError> {
         loop {
             match status {
                 Status::Init => {
-                    match b {
-                        b'{' => tokens.push(Token {
-                            span: i..i + 1,
-                            kind: TokenKind::LBrace,
-                        }),
-                        b'}' => tokens.push(Token {
-                            span: i..i + 1,
-                            kind: TokenKind::RBrace,
-                        }),
-                        b'[' => tokens.push(Token {
-                            span: i..i + 1,
-                            kind: TokenKind::LSqBracket,
-                        }),
-                        b']' => tokens.push(Token {
-                            span: i..i + 1,
-                            kind: TokenKind::RSqBracket,
-                        }),
-                        b':' => tokens.push(Token {
-                            span: i..i + 1,
-                            kind: TokenKind::Colon,
-                        }),
-                        b';' => tokens.push(Token {
-                            span: i..i + 1,
-                            kind: TokenKind::Semi,
-                        }),
-                        b',' => tokens.push(Token {
-                            span: i..i + 1,
-                            kind: TokenKind::Comma,
-                        }),
-                        b'/' => {
-                            status = Status::FwSlash;
-                        }
-                        b'A'..=b'Z' | b'a'..=b'z' | b'_' => {
-                            status = Status::InToken {
-                                start: i,
-                                kind: TokenKind::Ident,
-                            }
-                        }
-                        b'0'..=b'9' => {
-                            status = Status::InToken {
-                                start: i,
-                                kind: TokenKind::NumLit,
-                            }
-                        }
-                        _ => {}
-                    }
+                    status = start_token(b, i, &mut tokens);
                     break;
                 }
                 Status::InToken { start, kind } => match b {
@@ -97,20 +154,22 @@ pub(crate) fn tokenize(src: &str) -> Result<Vec<Token>, TokenizeError> {
                         break;
                     }
                     _ => {
-                        let text = &src[start..i];
-                        let kind = match text {
-                            "struct" => TokenKind::KwStruct,
-                            _ => kind,
-                        };
                         tokens.push(Token {
                             span: start..i,
-                            kind,
+                            kind: keyword_or(&src[start..i], kind),
                         });
                         status = Status::Init;
                     }
                 },
                 Status::FwSlash => match b {
-                    b'/' => status = Status::InComment,
+                    b'/' => {
+                        status = Status::InComment;
+                        break;
+                    }
+                    b'*' => {
+                        status = Status::InBlockComment;
+                        break;
+                    }
                     _ => {
                         return Err(TokenizeError {
                             span: i..i + 1,
@@ -124,9 +183,40 @@ pub(crate) fn tokenize(src: &str) -> Result<Vec<Token>, TokenizeError> {
                     }
                     break;
                 }
+                Status::InBlockComment => {
+                    if b == b'*' {
+                        status = Status::BlockCommentStar;
+                    }
+                    break;
+                }
+                Status::InStr { .. } | Status::InRawStr { .. } => {
+                    status = continue_str(status, b, i, &mut tokens);
+                    break;
+                }
+                Status::BlockCommentStar => {
+                    status = match b {
+                        b'/' => Status::Init,
+                        b'*' => Status::BlockCommentStar,
+                        _ => Status::InBlockComment,
+                    };
+                    break;
+                }
             }
         }
     }
+    match status {
+        Status::InToken { start, kind } => tokens.push(Token {
+            span: start..src.len(),
+            kind: keyword_or(&src[start..], kind),
+        }),
+        Status::InStr { start, .. } | Status::InRawStr { start } => {
+            return Err(TokenizeError {
+                span: start..src.len(),
+                kind: TokenizeErrorKind::UnterminatedStr,
+            });
+        }
+        _ => {}
+    }
     Ok(tokens)
 }
 
@@ -331,4 +421,38 @@ mod tests {
             ],
         );
     }
+    #[test]
+    fn test_tokenize_str_lit() {
+        let src = r#"x = "a \" b";"#;
+        assert_eq!(
+            tokenize(src).unwrap(),
+            &[
+                Token {
+                    span: 0..1,
+                    kind: TokenKind::Ident
+                },
+                Token {
+                    span: 2..3,
+                    kind: TokenKind::Eq
+                },
+                Token {
+                    span: 4..12,
+                    kind: TokenKind::StrLit
+                },
+                Token {
+                    span: 12..13,
+                    kind: TokenKind::Semi
+                },
+            ],
+        );
+        assert!(tokenize(r#"x = "abc"#).is_err());
+        let src = r#"`a:"\" b`"#;
+        assert_eq!(
+            tokenize(src).unwrap(),
+            &[Token {
+                span: 0..src.len(),
+                kind: TokenKind::StrLit
+            }],
+        );
+    }
 }
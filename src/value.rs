@@ -0,0 +1,113 @@
+//! Dynamically typed values of structs and their fields
+
+use std::fmt;
+
+/// A value of a type, like the decoded contents of a struct
+#[derive(Debug, PartialEq, Clone)]
+pub enum Value<'s> {
+    /// A signed integer, or a C `char`
+    Int(i128),
+    /// An unsigned integer, or a pointer
+    UInt(u128),
+    /// A floating point number
+    Float(f64),
+    /// A boolean
+    Bool(bool),
+    /// A Rust `char`
+    Char(char),
+    /// The elements of an array, vector or matrix
+    ///
+    /// Matrices are arrays of their column vectors, or row vectors if they are row
+    /// major.
+    Array(Vec<Value<'s>>),
+    /// The fields of a struct, in declaration order
+    ///
+    /// Anonymous members have an empty name.
+    Struct(Vec<(&'s str, Value<'s>)>),
+    /// Raw bytes, for values of unknown types
+    Bytes(Vec<u8>),
+}
+
+impl Value<'_> {
+    /// Look up the value of a field of a struct value by name, descending into
+    /// anonymous members
+    #[must_use]
+    pub fn field(&self, name: &str) -> Option<&Self> {
+        let Self::Struct(fields) = self else {
+            return None;
+        };
+        fields.iter().find_map(|(field, value)| match *field {
+            "" => value.field(name),
+            field => (field == name).then_some(value),
+        })
+    }
+    /// Look up an element of an array value
+    #[must_use]
+    pub fn index(&self, index: usize) -> Option<&Self> {
+        match self {
+            Self::Array(elems) => elems.get(index),
+            _ => None,
+        }
+    }
+    /// Look up the value of a field of a struct value by name for modifying it,
+    /// descending into anonymous members
+    pub fn field_mut(&mut self, name: &str) -> Option<&mut Self> {
+        let Self::Struct(fields) = self else {
+            return None;
+        };
+        fields.iter_mut().find_map(|(field, value)| match *field {
+            "" => value.field_mut(name),
+            field => (field == name).then_some(value),
+        })
+    }
+    /// Look up an element of an array value for modifying it
+    pub fn index_mut(&mut self, index: usize) -> Option<&mut Self> {
+        match self {
+            Self::Array(elems) => elems.get_mut(index),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Value<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Int(int) => write!(f, "{int}"),
+            Self::UInt(int) => write!(f, "{int}"),
+            Self::Float(float) => write!(f, "{float:?}"),
+            Self::Bool(bool) => write!(f, "{bool}"),
+            Self::Char(char) => write!(f, "{char:?}"),
+            Self::Array(elems) => {
+                f.write_str("[")?;
+                for (i, elem) in elems.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{elem}")?;
+                }
+                f.write_str("]")
+            }
+            Self::Struct(fields) => {
+                f.write_str("{")?;
+                for (i, (name, value)) in fields.iter().enumerate() {
+                    f.write_str(if i > 0 { ", " } else { " " })?;
+                    match *name {
+                        "" => write!(f, "{value}")?,
+                        name => write!(f, "{name}: {value}")?,
+                    }
+                }
+                f.write_str(if fields.is_empty() { "}" } else { " }" })
+            }
+            Self::Bytes(bytes) => {
+                f.write_str("<")?;
+                for (i, byte) in bytes.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(" ")?;
+                    }
+                    write!(f, "{byte:02x}")?;
+                }
+                f.write_str(">")
+            }
+        }
+    }
+}